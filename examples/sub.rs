@@ -0,0 +1,82 @@
+//! fetches a subscription url or ssconf:// link and prints it in another format, the pipeline
+//! behind the `sub` subcommand of https://github.com/ehsan2003/ss-uri-cli
+//!
+//! cargo run --example sub --features rustls,clash -- <url> [plain|base64|sip008|clash]
+use std::process::ExitCode;
+
+use ss_uri::{
+    ClashProvider, Compat, SIP008Config, Sip008Client, Sip008Document, Sip008Server, Subscription,
+    SIP008_VERSION,
+};
+
+fn render(subscription: &Subscription, format: &str) -> Option<String> {
+    let configs = &subscription.configs;
+    let lines = || {
+        configs
+            .iter()
+            .map(|config| config.to_uri(Compat::Spec))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    match format {
+        "plain" => Some(lines()),
+        "base64" => Some(base64::encode(lines())),
+        "sip008" => Some(
+            Sip008Document {
+                version: SIP008_VERSION,
+                servers: configs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, config)| Sip008Server::from_config(i.to_string(), config))
+                    .collect(),
+                bytes_used: subscription.bytes_used,
+                bytes_remaining: subscription.bytes_remaining,
+            }
+            .to_json(),
+        ),
+        "clash" => Some(ClashProvider::from_configs(configs).to_yaml()),
+        _ => None,
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let (Some(link), format) = (args.next(), args.next()) else {
+        eprintln!("usage: sub <url|ssconf://...> [plain|base64|sip008|clash]");
+        return ExitCode::FAILURE;
+    };
+    let format = format.unwrap_or_else(|| "plain".to_string());
+    let config = if link.starts_with("ssconf") {
+        match SIP008Config::parse(&link) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("invalid ssconf link: {e:?}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        SIP008Config {
+            location: link,
+            cert_finger_print: None,
+            http_method: None,
+        }
+    };
+    let subscription = match Sip008Client::default().fetch_subscription(&config).await {
+        Ok(result) => result.document,
+        Err(e) => {
+            eprintln!("fetching the subscription failed: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match render(&subscription, &format) {
+        Some(output) => {
+            println!("{output}");
+            ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!("unknown format {format:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,128 @@
+#ifndef SS_URI_H
+#define SS_URI_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// result of the fallible calls, zero on success
+typedef enum SsUriStatus {
+  SS_URI_STATUS_OK = 0,
+  SS_URI_STATUS_NULL_POINTER = 1,
+  SS_URI_STATUS_INVALID_UTF8 = 2,
+  SS_URI_STATUS_INVALID_URL = 3,
+  SS_URI_STATUS_INVALID_PROTOCOL = 4,
+  SS_URI_STATUS_INVALID_HOST = 5,
+  SS_URI_STATUS_INVALID_PORT = 6,
+  SS_URI_STATUS_INVALID_METHOD = 7,
+  SS_URI_STATUS_INVALID_PASSWORD = 8,
+  SS_URI_STATUS_INVALID_PREFIX = 9,
+  SS_URI_STATUS_INVALID_TAG = 10,
+  SS_URI_STATUS_UNEXPECTED_PATH = 11,
+  SS_URI_STATUS_UNEXPECTED_CHARACTERS = 12,
+  SS_URI_STATUS_LIMIT_EXCEEDED = 13,
+  SS_URI_STATUS_INVALID_ARGUMENT = 14,
+} SsUriStatus;
+
+// client presets of [`Compat`] as seen from C, the calls take them as a `uint32_t` so values
+// outside the enum are rejected rather than undefined behavior
+typedef enum SsUriCompat {
+  SS_URI_COMPAT_SPEC = 0,
+  SS_URI_COMPAT_OUTLINE = 1,
+  SS_URI_COMPAT_SHADOWSOCKS_ANDROID = 2,
+  SS_URI_COMPAT_SHADOWSOCKS_WINDOWS = 3,
+  SS_URI_COMPAT_SHADOWSOCKS_RUST = 4,
+  SS_URI_COMPAT_SHADOWROCKET = 5,
+} SsUriCompat;
+
+// configs compare and hash equal regardless of `tag_raw`, it only shapes serialization, for
+// deduplicating servers that differ only in their tag or extras see [`SSConfig::same_server`]
+typedef struct SsUriConfig SsUriConfig;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// parses `uri` with the given client preset, one of [`SsUriCompat`], and stores the config in
+// `out`, `out` is left untouched on failure
+//
+// # Safety
+// `uri` must be a valid nul terminated string and `out` a valid pointer to write to
+enum SsUriStatus ss_uri_parse(const char *uri, uint32_t compat, struct SsUriConfig **out);
+
+// frees a config returned by [`ss_uri_parse`], null is ignored
+//
+// # Safety
+// `config` must come from [`ss_uri_parse`] and must not be used afterwards
+void ss_uri_free(struct SsUriConfig *config);
+
+// frees a string returned by the library, null is ignored
+//
+// # Safety
+// `s` must come from this library and must not be used afterwards
+void ss_uri_string_free(char *s);
+
+// static description of a status, never freed
+const char *ss_uri_status_message(enum SsUriStatus status);
+
+// serializes the config as a sip002 uri the given client, one of [`SsUriCompat`], imports,
+// null when `config` is null or `compat` is out of range
+//
+// # Safety
+// `config` must be null or come from [`ss_uri_parse`]
+char *ss_uri_to_uri(const struct SsUriConfig *config, uint32_t compat);
+
+// serializes the config with [`SSConfig::to_sip002`], null when `config` is null
+//
+// # Safety
+// `config` must be null or come from [`ss_uri_parse`]
+char *ss_uri_to_sip002(const struct SsUriConfig *config);
+
+// serializes the config with [`SSConfig::to_legacy_base64_encoded`], null when `config` is null
+//
+// # Safety
+// `config` must be null or come from [`ss_uri_parse`]
+char *ss_uri_to_legacy_base64(const struct SsUriConfig *config);
+
+// the method name, e.g. `aes-128-gcm`
+//
+// # Safety
+// `config` must be null or come from [`ss_uri_parse`]
+char *ss_uri_method(const struct SsUriConfig *config);
+
+// the password, null when it contains a nul byte
+//
+// # Safety
+// `config` must be null or come from [`ss_uri_parse`]
+char *ss_uri_password(const struct SsUriConfig *config);
+
+// the host, ipv6 addresses in brackets
+//
+// # Safety
+// `config` must be null or come from [`ss_uri_parse`]
+char *ss_uri_host(const struct SsUriConfig *config);
+
+// the port, zero when `config` is null
+//
+// # Safety
+// `config` must be null or come from [`ss_uri_parse`]
+uint16_t ss_uri_port(const struct SsUriConfig *config);
+
+// the tag, null when absent
+//
+// # Safety
+// `config` must be null or come from [`ss_uri_parse`]
+char *ss_uri_tag(const struct SsUriConfig *config);
+
+// the raw `plugin` parameter, null when absent
+//
+// # Safety
+// `config` must be null or come from [`ss_uri_parse`]
+char *ss_uri_plugin(const struct SsUriConfig *config);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SS_URI_H */
@@ -0,0 +1,166 @@
+//! upgrade advice for configs still on stream ciphers
+use crate::{Method, SSConfig, LINT_MIN_PASSWORD_LEN};
+
+/// what a config should change to, see [`SSConfig::audit`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Upgrade {
+    pub method: Method,
+    /// the old password can't be trusted, either the cipher leaks it or it is too short
+    pub regenerate_password: bool,
+}
+
+impl Upgrade {
+    /// the config with the upgrade applied, `new_password` is only called when the password
+    /// has to be regenerated
+    pub fn apply(&self, config: &SSConfig, new_password: impl FnOnce() -> String) -> SSConfig {
+        SSConfig {
+            method: self.method,
+            password: if self.regenerate_password {
+                new_password()
+            } else {
+                config.password.clone()
+            },
+            ..config.clone()
+        }
+    }
+}
+
+impl SSConfig {
+    /// the upgrade a config on a stream cipher needs, `None` for aead methods, the chacha and
+    /// salsa ciphers move to chacha20-ietf-poly1305 and everything else to aes-256-gcm
+    /// ```
+    /// use ss_uri::{Method, SSConfig, Upgrade};
+    /// let config = SSConfig::parse("ss://cmM0LW1kNTp0ZXN0QDE5Mi4xNjguMTAwLjE6ODg4OA").unwrap();
+    /// let upgrade = config.audit().unwrap();
+    /// assert_eq!(
+    ///     upgrade,
+    ///     Upgrade {
+    ///         method: Method::Aes256Gcm,
+    ///         regenerate_password: true,
+    ///     }
+    /// );
+    /// let upgraded = upgrade.apply(&config, || "new password".to_string());
+    /// assert_eq!(upgraded.password, "new password");
+    /// assert_eq!(upgraded.audit(), None);
+    /// ```
+    pub fn audit(&self) -> Option<Upgrade> {
+        let method = match self.method {
+            method if method.is_aead() => return None,
+            Method::Salsa20 | Method::Chacha20 | Method::Chacha20Ietf => {
+                Method::Chacha20IetfPoly1305
+            }
+            _ => Method::Aes256Gcm,
+        };
+        let broken = matches!(self.method, Method::Rc4Md5 | Method::BfCfb);
+        let short = self.password.chars().count() < LINT_MIN_PASSWORD_LEN;
+        Some(Upgrade {
+            method,
+            regenerate_password: broken || short,
+        })
+    }
+}
+
+/// one config of an [`AuditReport`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AuditEntry {
+    pub before: SSConfig,
+    pub upgrade: Option<Upgrade>,
+    /// the upgraded config once [`AuditReport::apply`] ran, `None` before that and for configs
+    /// that need no upgrade
+    pub after: Option<SSConfig>,
+}
+
+/// the advice for a batch of configs, e.g. every server of a subscription
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct AuditReport {
+    pub entries: Vec<AuditEntry>,
+}
+
+impl AuditReport {
+    /// the entries that need an upgrade
+    pub fn upgrades(&self) -> impl Iterator<Item = &AuditEntry> {
+        self.entries.iter().filter(|e| e.upgrade.is_some())
+    }
+    /// fills in `after` for every entry that needs an upgrade, `new_password` is called for
+    /// each config whose password has to be regenerated
+    pub fn apply(&mut self, mut new_password: impl FnMut(&SSConfig) -> String) {
+        for entry in &mut self.entries {
+            if let Some(upgrade) = entry.upgrade {
+                let after = upgrade.apply(&entry.before, || new_password(&entry.before));
+                entry.after = Some(after);
+            }
+        }
+    }
+    /// the configs after the upgrades, unchanged ones included
+    pub fn configs(&self) -> Vec<SSConfig> {
+        self.entries
+            .iter()
+            .map(|e| e.after.clone().unwrap_or_else(|| e.before.clone()))
+            .collect()
+    }
+}
+
+/// audits every config for bulk hardening, nothing changes until [`AuditReport::apply`]
+/// ```
+/// use ss_uri::{Method, SSConfig};
+/// let configs = [
+///     "ss://YWVzLTI1Ni1nY206Y29ycmVjdGhvcnNl@192.168.100.1:8888",
+///     "ss://Y2hhY2hhMjAtaWV0Zjpjb3JyZWN0aG9yc2U@192.168.100.2:8888",
+/// ]
+/// .map(|uri| SSConfig::parse(uri).unwrap());
+/// let mut report = ss_uri::audit(&configs);
+/// assert_eq!(report.upgrades().count(), 1);
+/// report.apply(|_| unreachable!("the password is kept"));
+/// let methods = report.configs().iter().map(|c| c.method).collect::<Vec<_>>();
+/// assert_eq!(methods, [Method::Aes256Gcm, Method::Chacha20IetfPoly1305]);
+/// ```
+pub fn audit<'a>(configs: impl IntoIterator<Item = &'a SSConfig>) -> AuditReport {
+    let entries = configs
+        .into_iter()
+        .map(|config| AuditEntry {
+            before: config.clone(),
+            upgrade: config.audit(),
+            after: None,
+        })
+        .collect();
+    AuditReport { entries }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_stream_cipher_gets_an_aead_upgrade() {
+        let mut config =
+            SSConfig::parse("ss://YWVzLTI1Ni1nY206Y29ycmVjdGhvcnNl@192.168.100.1:8888").unwrap();
+        for method in Method::ALL {
+            config.method = method;
+            match config.audit() {
+                Some(upgrade) => {
+                    assert!(!method.is_aead());
+                    assert!(upgrade.method.is_aead());
+                    let broken = matches!(method, Method::Rc4Md5 | Method::BfCfb);
+                    assert_eq!(upgrade.regenerate_password, broken, "{method}");
+                }
+                None => assert!(method.is_aead()),
+            }
+        }
+    }
+
+    #[test]
+    fn reports_keep_configs_that_need_nothing() {
+        let configs = [
+            "ss://cmM0LW1kNTp0ZXN0@192.168.100.1:8888#A",
+            "ss://YWVzLTI1Ni1nY206Y29ycmVjdGhvcnNl@192.168.100.2:8888#B",
+        ]
+        .map(|uri| SSConfig::parse(uri).unwrap());
+        let mut report = audit(&configs);
+        report.apply(|config| format!("{}-regenerated", config.tag.as_deref().unwrap()));
+        let after = report.configs();
+        assert_eq!(after[0].password, "A-regenerated");
+        assert_eq!(after[0].method, Method::Aes256Gcm);
+        assert_eq!(after[1], configs[1]);
+        assert_eq!(report.entries[1].after, None);
+    }
+}
@@ -0,0 +1,302 @@
+//! borrowed counterpart of [`SSConfig`] for processing many uris without allocating per field
+use std::borrow::Cow;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use percent_encoding::percent_decode_str;
+use url::Host;
+
+use crate::{entry, KnownParam, Method, Mode, OutlinePrefix, SSConfig, SSParseError};
+
+/// a config whose fields point into the parsed uri where possible
+///
+/// host, tag and query parameters borrow from the input unless they had to be decoded, the
+/// password always comes out of the base64 user info so it is owned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SSConfigRef<'a> {
+    pub host: Host<Cow<'a, str>>,
+    pub port: u16,
+    pub method: Method,
+    pub password: Cow<'a, str>,
+    pub tag: Option<Cow<'a, str>>,
+    /// query parameters other than `prefix` and the mode in the order they appear
+    pub extra: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+    pub prefix: Option<OutlinePrefix>,
+    pub mode: Option<Mode>,
+}
+
+impl<'a> SSConfigRef<'a> {
+    /// accepts exactly what [`SSConfig::parse`] accepts, plain sip002 uris are split in place
+    /// while anything unusual goes through [`SSConfig::parse`] and comes out owned
+    /// ```
+    /// use std::borrow::Cow;
+    /// use ss_uri::{SSConfig, SSConfigRef};
+    /// let uri = "ss://YWVzLTEyOC1nY206dGVzdA@example.com:8888/?plugin=obfs-local#Foo";
+    /// let config = SSConfigRef::parse(uri).unwrap();
+    /// assert!(matches!(config.tag, Some(Cow::Borrowed("Foo"))));
+    /// assert_eq!(config.to_owned(), SSConfig::parse(uri).unwrap());
+    /// ```
+    pub fn parse(s: &'a str) -> Result<Self, SSParseError> {
+        match Self::parse_in_place(s) {
+            Some(config) => Ok(config),
+            None => SSConfig::parse(s).map(Self::from),
+        }
+    }
+
+    /// detaches the config from the input while keeping the `Cow` fields, for storing parse
+    /// results next to ones that never borrowed
+    /// ```
+    /// use ss_uri::SSConfigRef;
+    /// let stored: SSConfigRef<'static> = {
+    ///     let uri = String::from("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8888#Foo");
+    ///     SSConfigRef::parse(&uri).unwrap().into_static()
+    /// };
+    /// assert_eq!(stored.tag.as_deref(), Some("Foo"));
+    /// ```
+    pub fn into_static(self) -> SSConfigRef<'static> {
+        let own = |s: Cow<'_, str>| Cow::Owned(s.into_owned());
+        SSConfigRef {
+            host: match self.host {
+                Host::Domain(domain) => Host::Domain(own(domain)),
+                Host::Ipv4(ip) => Host::Ipv4(ip),
+                Host::Ipv6(ip) => Host::Ipv6(ip),
+            },
+            port: self.port,
+            method: self.method,
+            password: own(self.password),
+            tag: self.tag.map(own),
+            extra: self
+                .extra
+                .into_iter()
+                .map(|(k, v)| (own(k), own(v)))
+                .collect(),
+            prefix: self.prefix,
+            mode: self.mode,
+        }
+    }
+
+    /// copies the borrowed fields into an [`SSConfig`]
+    pub fn to_owned(&self) -> SSConfig {
+        self.clone().into_owned()
+    }
+
+    pub fn into_owned(self) -> SSConfig {
+        SSConfig {
+            host: match self.host {
+                Host::Domain(domain) => Host::Domain(domain.into_owned()),
+                Host::Ipv4(ip) => Host::Ipv4(ip),
+                Host::Ipv6(ip) => Host::Ipv6(ip),
+            },
+            port: self.port,
+            method: self.method,
+            password: self.password.into_owned(),
+            tag: self.tag.map(Cow::into_owned),
+            tag_raw: None,
+            extra: (!self.extra.is_empty()).then(|| {
+                self.extra
+                    .into_iter()
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect()
+            }),
+            prefix: self.prefix,
+            mode: self.mode,
+        }
+    }
+
+    /// the common `ss://userinfo@host:port/?query#tag` shape, `None` whenever the input could
+    /// be read differently by the url parser [`SSConfig::parse`] relies on
+    fn parse_in_place(s: &'a str) -> Option<Self> {
+        if !s.bytes().all(|b| b.is_ascii_graphic() && b != b'\\') {
+            return None;
+        }
+        let rest = entry::strip_scheme(s, "ss")?;
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (rest, None),
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, query),
+            None => (rest, ""),
+        };
+        let rest = rest.strip_suffix('/').unwrap_or(rest);
+        let (user_info, endpoint) = rest.split_once('@')?;
+        if !user_info
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'_' | b'='))
+        {
+            return None;
+        }
+        let (host, port) = endpoint.rsplit_once(':')?;
+        if port.is_empty() || !port.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let port = port.parse().ok().filter(|port| *port != 0)?;
+        let host = borrow_host(host)?;
+
+        let decoded = entry::decode_base64_lenient(user_info)?;
+        let (method, password) = decoded.split_once(':')?;
+        let method = method.parse().ok()?;
+
+        let mut prefix = None;
+        let mut extra = Vec::new();
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            // the mode parameters override each other, the owned parser sorts that out
+            if key == KnownParam::Mode.key() || KnownParam::Udp.aliases().contains(&&*key) {
+                return None;
+            } else if key == "prefix" {
+                prefix = Some(value);
+            } else {
+                extra.push((key, value));
+            }
+        }
+        let prefix = match prefix {
+            Some(prefix) => Some(OutlinePrefix::from_param(&prefix).ok()?),
+            None => None,
+        };
+
+        Some(SSConfigRef {
+            host,
+            port,
+            method,
+            password: Cow::Owned(password.to_string()),
+            tag: fragment.map(|f| percent_decode_str(f).decode_utf8_lossy()),
+            extra,
+            prefix,
+            mode: None,
+        })
+    }
+}
+
+/// hosts the url parser leaves untouched, anything it would normalize returns `None`
+fn borrow_host(host: &str) -> Option<Host<Cow<'_, str>>> {
+    if let Some(ip) = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        return ip.parse::<Ipv6Addr>().ok().map(Host::Ipv6);
+    }
+    if let Ok(ip) = host.parse::<Ipv4Addr>() {
+        return Some(Host::Ipv4(ip));
+    }
+    let plain_label = |label: &str| {
+        !label.is_empty()
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+    };
+    // the url parser reads a domain ending in a numeric label as an ipv4 address
+    let last = host.rsplit('.').next()?;
+    let numeric = last.bytes().all(|b| b.is_ascii_digit()) || last.starts_with("0x");
+    (!numeric && !host.contains("--") && host.split('.').all(plain_label))
+        .then_some(Host::Domain(Cow::Borrowed(host)))
+}
+
+impl From<SSConfig> for SSConfigRef<'_> {
+    fn from(config: SSConfig) -> Self {
+        SSConfigRef {
+            host: match config.host {
+                Host::Domain(domain) => Host::Domain(Cow::Owned(domain)),
+                Host::Ipv4(ip) => Host::Ipv4(ip),
+                Host::Ipv6(ip) => Host::Ipv6(ip),
+            },
+            port: config.port,
+            method: config.method,
+            password: Cow::Owned(config.password),
+            tag: config.tag.map(Cow::Owned),
+            extra: config
+                .extra
+                .into_iter()
+                .flatten()
+                .map(|(k, v)| (Cow::Owned(k), Cow::Owned(v)))
+                .collect(),
+            prefix: config.prefix,
+            mode: config.mode,
+        }
+    }
+}
+
+/// a view of an owned config, nothing is copied except the prefix
+impl<'a> From<&'a SSConfig> for SSConfigRef<'a> {
+    fn from(config: &'a SSConfig) -> Self {
+        SSConfigRef {
+            host: match &config.host {
+                Host::Domain(domain) => Host::Domain(Cow::Borrowed(domain)),
+                Host::Ipv4(ip) => Host::Ipv4(*ip),
+                Host::Ipv6(ip) => Host::Ipv6(*ip),
+            },
+            port: config.port,
+            method: config.method,
+            password: Cow::Borrowed(&config.password),
+            tag: config.tag.as_deref().map(Cow::Borrowed),
+            extra: config
+                .extra
+                .iter()
+                .flatten()
+                .map(|(k, v)| (Cow::Borrowed(k.as_str()), Cow::Borrowed(v.as_str())))
+                .collect(),
+            prefix: config.prefix.clone(),
+            mode: config.mode,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::{INVALID_URIS, VALID_URIS};
+    use crate::test_util::mutate;
+
+    #[test]
+    fn borrows_from_plain_uris() {
+        let config = SSConfigRef::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@example.com:8888/?plugin=obfs-local%3Bobfs%3Dhttp&a=b#Foo",
+        )
+        .unwrap();
+        assert!(matches!(
+            config.host,
+            Host::Domain(Cow::Borrowed("example.com"))
+        ));
+        assert!(matches!(config.tag, Some(Cow::Borrowed("Foo"))));
+        assert!(matches!(
+            config.extra[1],
+            (Cow::Borrowed("a"), Cow::Borrowed("b"))
+        ));
+        assert_eq!(config.extra[0].1, "obfs-local;obfs=http");
+    }
+
+    #[test]
+    fn borrows_from_owned_configs() {
+        let owned =
+            SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8888/?plugin=obfs-local#Foo")
+                .unwrap();
+        let view = SSConfigRef::from(&owned);
+        assert!(matches!(view.password, Cow::Borrowed("test")));
+        assert_eq!(view.to_owned(), owned);
+        assert_eq!(view.into_static().into_owned(), owned);
+    }
+
+    #[test]
+    fn agrees_with_the_owned_parser() {
+        let seeds = VALID_URIS
+            .iter()
+            .map(|v| v.uri)
+            .chain(INVALID_URIS.iter().map(|v| v.uri))
+            .chain([
+                "ss://YWVzLTEyOC1nY206dGVzdA@EXAMPLE.com:8888",
+                "ss://YWVzLTEyOC1nY206dGVzdA@xn--p8j.example:8888",
+                "ss://YWVzLTEyOC1nY206dGVzdA@1.0x10:8888",
+                "ss://YWVzLTEyOC1nY206dGVzdA@[::ffff:1.2.3.4]:08888/?prefix=%16%03%01&a=1&a=2",
+            ])
+            .collect::<Vec<_>>();
+        mutate(
+            &seeds,
+            "0123456789abcxyzABC:@#?=%/[]._-+&\\ ",
+            5000,
+            |input| {
+                assert_eq!(
+                    SSConfigRef::parse(input).map(SSConfigRef::into_owned),
+                    SSConfig::parse(input),
+                    "{input}"
+                );
+            },
+        );
+    }
+}
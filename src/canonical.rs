@@ -0,0 +1,117 @@
+//! normalized configs and comparisons for deduplication and caching
+use url::Host;
+
+use crate::SSConfig;
+
+/// whether both uris parse to the same config once canonicalized, telling apart neither padded
+/// and unpadded base64, legacy and sip002 uris nor different encodings of the same tag,
+/// anything that doesn't parse is equivalent to nothing
+/// ```
+/// assert!(ss_uri::equivalent(
+///     "ss://YWVzLTEyOC1nY206dGVzdA==@192.168.100.1:8888/#Foo%20Bar",
+///     "ss://YWVzLTEyOC1nY206dGVzdEAxOTIuMTY4LjEwMC4xOjg4ODg#Foo Bar",
+/// ));
+/// assert!(!ss_uri::equivalent("ss://invalid", "ss://invalid"));
+/// ```
+pub fn equivalent(a: &str, b: &str) -> bool {
+    match (SSConfig::parse(a), SSConfig::parse(b)) {
+        (Ok(a), Ok(b)) => a.canonicalize() == b.canonicalize(),
+        _ => false,
+    }
+}
+
+impl SSConfig {
+    /// the config in normalized form, lowercase punycode domains, compressed ipv6 addresses,
+    /// plugin options without redundant escapes or empty entries, extra parameters sorted the
+    /// way [`QueryOrder::Sorted`] writes them and no raw fragment
+    ///
+    /// serializing a canonical config with [`SSConfig::to_uri`] and parsing the uri again gives
+    /// back the same config, so canonical configs can be compared and cached by their uri
+    /// ```
+    /// use ss_uri::{Compat, SSConfig};
+    /// let config = SSConfig::parse(
+    ///     "ss://YWVzLTEyOC1nY206dGVzdA@[2001:DB8:0::1]:8888/?b=2&plugin=obfs-local%3B%3Bobfs%3Dhttp&a=1#%46oo",
+    /// )
+    /// .unwrap()
+    /// .canonicalize();
+    /// assert_eq!(
+    ///     config.to_uri(Compat::Spec),
+    ///     "ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888/?plugin=obfs-local%3Bobfs%3Dhttp&a=1&b=2#Foo"
+    /// );
+    /// let uri = config.to_uri(Compat::Spec);
+    /// assert_eq!(SSConfig::parse(&uri).unwrap().canonicalize(), config);
+    /// ```
+    ///
+    /// [`QueryOrder::Sorted`]: crate::QueryOrder::Sorted
+    pub fn canonicalize(&self) -> Self {
+        let host = self.canonical_host();
+        let plugin = self.plugin().map(|plugin| plugin.normalized().to_string());
+        let extra = self.extra.clone().map(|mut extra| {
+            if let Some(plugin) = plugin {
+                extra.insert("plugin".to_string(), plugin);
+            }
+            extra.sort();
+            extra
+        });
+        SSConfig {
+            host,
+            tag_raw: None,
+            extra: extra.filter(|extra| !extra.is_empty()),
+            ..self.clone()
+        }
+    }
+    /// whether both configs connect to the same host and port, domains compared in their
+    /// normalized form
+    pub fn endpoint_eq(&self, other: &Self) -> bool {
+        self.endpoint() == other.endpoint()
+    }
+    /// the host and port [`SSConfig::endpoint_eq`] compares, for keying maps of servers
+    pub(crate) fn endpoint(&self) -> (Host, u16) {
+        (self.canonical_host(), self.port)
+    }
+    /// whether both configs reach the same server the same way, method, password, endpoint and
+    /// plugin match while the tag and other extra parameters may differ, for deduplicating
+    /// servers across subscriptions
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let a = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@Example.com:8888/?plugin=obfs-local%3Bobfs%3Dhttp#HK%2001").unwrap();
+    /// let b = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8888/?group=x&plugin=obfs-local%3B%3Bobfs%3Dhttp#Hong%20Kong").unwrap();
+    /// assert!(a.same_server(&b));
+    /// let c = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8888#HK%2001").unwrap();
+    /// assert!(a.endpoint_eq(&c) && !a.same_server(&c));
+    /// ```
+    pub fn same_server(&self, other: &Self) -> bool {
+        self.endpoint_eq(other)
+            && self.method == other.method
+            && self.password == other.password
+            && self.plugin().map(|p| p.normalized()) == other.plugin().map(|p| p.normalized())
+    }
+    fn canonical_host(&self) -> Host {
+        match &self.host {
+            Host::Domain(_) => Host::parse(&self.host_ascii()).unwrap_or(self.host.clone()),
+            host => host.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Compat;
+
+    #[test]
+    fn canonical_configs_survive_a_round_trip() {
+        for uri in [
+            "ss://YWVzLTEyOC1nY206dGVzdA@Example.COM:8888",
+            "ss://YWVzLTEyOC1nY206dGVzdA@例え.jp:8888/?z=1&plugin=v2ray-plugin%3Bpath%3D%5C%2Fws%3B&z=0#%E6%97%A5%20%2B",
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?prefix=%16%03&plugin=#HK+01",
+            "ss://YmYtY2ZiOnRlc3RAMTkyLjE2OC4xMDAuMTo4ODg4#Foo Bar",
+        ] {
+            let config = SSConfig::parse(uri).unwrap().canonicalize();
+            let serialized = config.to_uri(Compat::Spec);
+            let reparsed = SSConfig::parse(&serialized).unwrap().canonicalize();
+            assert_eq!(reparsed, config, "{uri}");
+            assert_eq!(reparsed.to_uri(Compat::Spec), serialized, "{uri}");
+        }
+    }
+}
@@ -0,0 +1,509 @@
+use core::fmt;
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use crate::{entry, Mode, Plugin, QueryParams, SSConfig, SSParseError};
+
+/// a clash `proxies:` entry of type `ss`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ClashProxy {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub proxy_type: String,
+    pub server: String,
+    pub port: u16,
+    pub cipher: String,
+    pub password: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub udp: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugin_opts: BTreeMap<String, Value>,
+    /// Clash.Meta: tunnel udp through the tcp connection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub udp_over_tcp: Option<bool>,
+    /// Clash.Meta: udp-over-tcp protocol version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub udp_over_tcp_version: Option<u8>,
+    /// Clash.Meta: utls fingerprint used by the `shadow-tls` plugin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_fingerprint: Option<String>,
+    /// Clash.Meta: multiplexing options
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub smux: BTreeMap<String, Value>,
+}
+
+/// a clash proxy-provider payload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClashProvider {
+    pub proxies: Vec<ClashProxy>,
+    #[serde(
+        rename = "health-check",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub health_check: Option<ClashHealthCheck>,
+}
+
+/// the `health-check` stanza of a proxy provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClashHealthCheck {
+    pub enable: bool,
+    pub url: String,
+    /// seconds between checks
+    pub interval: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClashError {
+    InvalidYaml,
+    UnsupportedType,
+    InvalidConfig(SSParseError),
+}
+impl fmt::Display for ClashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for ClashError {}
+
+impl ClashProxy {
+    /// builds a proxy entry, the tag is used as name falling back to `host:port`.
+    /// the `obfs-local` and `v2ray-plugin` options are translated to clash's `plugin-opts`,
+    /// options of other plugins are copied as they are
+    pub fn from_config(config: &SSConfig) -> Self {
+        let (plugin, plugin_opts) = match config.plugin() {
+            Some(plugin) => {
+                let (name, opts) = plugin_to_clash(&plugin);
+                (Some(name), opts)
+            }
+            None => (None, BTreeMap::new()),
+        };
+        let extra = |key: &str| config.extra.as_ref().and_then(|e| e.get(key));
+        let flag = |key: &str| extra(key).map(|value| value == "true" || value == "1");
+        let smux = config
+            .extra
+            .iter()
+            .flatten()
+            .filter_map(|(key, value)| {
+                let key = key.strip_prefix(SMUX_PREFIX)?;
+                Some((key.to_string(), scalar(value)))
+            })
+            .collect();
+        Self {
+            name: entry::name_or_endpoint(config),
+            proxy_type: "ss".to_string(),
+            server: entry::host_to_string(&config.host),
+            port: config.port,
+            cipher: config.method.to_string(),
+            password: config.password.clone(),
+            udp: config.mode.map(|mode| mode.udp()),
+            plugin,
+            plugin_opts,
+            udp_over_tcp: flag("udp-over-tcp"),
+            udp_over_tcp_version: extra("udp-over-tcp-version").and_then(|v| v.parse().ok()),
+            client_fingerprint: extra("client-fingerprint").cloned(),
+            smux,
+        }
+    }
+    /// converts the entry back, `udp` becomes [`SSConfig::mode`], the Clash.Meta fields are
+    /// kept in extra parameters named after the clash keys, `smux` options are flattened into `smux.<key>`
+    pub fn to_config(&self) -> Result<SSConfig, ClashError> {
+        if self.proxy_type != "ss" {
+            return Err(ClashError::UnsupportedType);
+        }
+        let plugin = self
+            .plugin
+            .as_deref()
+            .filter(|p| !p.is_empty())
+            .map(|name| plugin_from_clash(name, &self.plugin_opts));
+        let mut config = entry::build_config(
+            &self.server,
+            self.port,
+            &self.cipher,
+            &self.password,
+            Some(self.name.clone()).filter(|n| !n.is_empty()),
+            plugin.as_ref().map(|p| p.name.as_str()),
+            plugin.as_ref().and_then(|p| p.opts.as_deref()),
+        )
+        .map_err(ClashError::InvalidConfig)?;
+        config.mode = self.udp.map(Mode::from_udp);
+        let extra = [
+            (
+                "udp-over-tcp".to_string(),
+                self.udp_over_tcp.map(|v| v.to_string()),
+            ),
+            (
+                "udp-over-tcp-version".to_string(),
+                self.udp_over_tcp_version.map(|v| v.to_string()),
+            ),
+            (
+                "client-fingerprint".to_string(),
+                self.client_fingerprint.clone(),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+        .chain(
+            self.smux
+                .iter()
+                .map(|(key, value)| (format!("{}{}", SMUX_PREFIX, key), scalar_to_string(value))),
+        );
+        for (key, value) in extra {
+            config
+                .extra
+                .get_or_insert_with(QueryParams::new)
+                .insert(key, value);
+        }
+        Ok(config)
+    }
+}
+
+impl ClashProvider {
+    /// builds a provider out of the configs, duplicate names get a ` 2`, ` 3`, ... suffix
+    /// ```
+    /// use ss_uri::{ClashProvider, SSConfig};
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#server").unwrap();
+    /// let provider = ClashProvider::from_configs([&config, &config]);
+    /// assert_eq!(provider.proxies[1].name, "server 2");
+    /// assert!(provider.to_yaml().starts_with("proxies:"));
+    /// ```
+    pub fn from_configs<'a, I>(configs: I) -> Self
+    where
+        I: IntoIterator<Item = &'a SSConfig>,
+    {
+        let mut names = HashSet::new();
+        let proxies = configs
+            .into_iter()
+            .map(|config| {
+                let mut proxy = ClashProxy::from_config(config);
+                let base = proxy.name.clone();
+                let mut n = 1;
+                while !names.insert(proxy.name.clone()) {
+                    n += 1;
+                    proxy.name = format!("{} {}", base, n);
+                }
+                proxy
+            })
+            .collect();
+        Self {
+            proxies,
+            health_check: None,
+        }
+    }
+    pub fn with_health_check(mut self, url: impl Into<String>, interval: u64) -> Self {
+        self.health_check = Some(ClashHealthCheck {
+            enable: true,
+            url: url.into(),
+            interval,
+        });
+        self
+    }
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(self).expect("clash provider is always serializable")
+    }
+}
+
+/// extracts the shadowsocks proxies out of a whole clash config, other proxy types are skipped
+/// ```
+/// use ss_uri::parse_clash_proxies;
+/// let configs = parse_clash_proxies(r#"
+/// proxies:
+///   - name: "ss1"
+///     type: ss
+///     server: example.com
+///     port: 443
+///     cipher: aes-256-gcm
+///     password: "password"
+///   - name: "vmess"
+///     type: vmess
+///     server: example.com
+///     port: 443
+/// "#).unwrap();
+/// assert_eq!(configs.len(), 1);
+/// assert_eq!(configs[0].tag, Some("ss1".to_string()));
+/// ```
+pub fn parse_clash_proxies(yaml: &str) -> Result<Vec<SSConfig>, ClashError> {
+    #[derive(Deserialize)]
+    struct ClashConfig {
+        #[serde(default)]
+        proxies: Vec<Value>,
+    }
+    let config: ClashConfig = serde_yaml::from_str(yaml).map_err(|_| ClashError::InvalidYaml)?;
+    config
+        .proxies
+        .into_iter()
+        .filter(|proxy| proxy.get("type").and_then(Value::as_str) == Some("ss"))
+        .map(|proxy| {
+            let proxy: ClashProxy =
+                serde_yaml::from_value(proxy).map_err(|_| ClashError::InvalidYaml)?;
+            proxy.to_config()
+        })
+        .collect()
+}
+
+const SMUX_PREFIX: &str = "smux.";
+
+fn plugin_to_clash(plugin: &Plugin) -> (String, BTreeMap<String, Value>) {
+    let mut opts = BTreeMap::new();
+    let name = match plugin.name.as_str() {
+        "obfs-local" | "simple-obfs" => {
+            for (key, value) in plugin.options() {
+                match (key.as_str(), value) {
+                    ("obfs", Some(mode)) => opts.insert("mode".to_string(), Value::from(mode)),
+                    ("obfs-host", Some(host)) => opts.insert("host".to_string(), Value::from(host)),
+                    (key, value) => opts.insert(key.to_string(), option_value(value)),
+                };
+            }
+            "obfs".to_string()
+        }
+        "v2ray-plugin" => {
+            opts.insert("mode".to_string(), Value::from("websocket"));
+            for (key, value) in plugin.options() {
+                match (key.as_str(), value) {
+                    ("mux", Some(mux)) => {
+                        opts.insert("mux".to_string(), Value::from(mux != "0"));
+                    }
+                    (key, value) => {
+                        opts.insert(key.to_string(), option_value(value));
+                    }
+                }
+            }
+            plugin.name.clone()
+        }
+        name => {
+            for (key, value) in plugin.options() {
+                opts.insert(key, option_value(value));
+            }
+            name.to_string()
+        }
+    };
+    (name, opts)
+}
+
+fn plugin_from_clash(name: &str, opts: &BTreeMap<String, Value>) -> Plugin {
+    let value_of = |value: &Value| match value {
+        Value::Bool(true) => None,
+        other => Some(scalar_to_string(other)),
+    };
+    let mut options = Vec::new();
+    let name = match name {
+        "obfs" => {
+            for (key, value) in opts {
+                match key.as_str() {
+                    "mode" => options.push(("obfs".to_string(), value_of(value))),
+                    "host" => options.push(("obfs-host".to_string(), value_of(value))),
+                    _ => options.push((key.clone(), value_of(value))),
+                }
+            }
+            "obfs-local"
+        }
+        "v2ray-plugin" => {
+            for (key, value) in opts {
+                match (key.as_str(), value) {
+                    ("mode", Value::String(mode)) if mode == "websocket" => {}
+                    (_, Value::Bool(false)) => {}
+                    ("mux", Value::Bool(true)) => {}
+                    _ => options.push((key.clone(), value_of(value))),
+                }
+            }
+            "v2ray-plugin"
+        }
+        name => {
+            for (key, value) in opts {
+                if value != &Value::Bool(false) {
+                    options.push((key.clone(), value_of(value)));
+                }
+            }
+            name
+        }
+    };
+    Plugin::from_options(
+        name,
+        options.iter().map(|(k, v)| (k.as_str(), v.as_deref())),
+    )
+}
+
+fn option_value(value: Option<String>) -> Value {
+    match value {
+        Some(value) => scalar(&value),
+        None => Value::from(true),
+    }
+}
+
+/// plain numbers and booleans are typed, so fields like shadow-tls' `version` stay integers
+fn scalar(value: &str) -> Value {
+    match value {
+        "true" => Value::from(true),
+        "false" => Value::from(false),
+        _ => match value.parse::<u64>() {
+            Ok(number) => Value::from(number),
+            Err(_) => Value::from(value),
+        },
+    }
+}
+
+fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => serde_yaml::to_string(other)
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Method;
+    use url::Host;
+
+    const CONFIG: &str = r#"
+port: 7890
+proxies:
+  - name: "obfs"
+    type: ss
+    server: 2001:db8::1
+    port: 443
+    cipher: chacha20-ietf-poly1305
+    password: "password"
+    udp: true
+    plugin: obfs
+    plugin-opts:
+      mode: tls
+      host: bing.com
+  - name: "v2ray"
+    type: ss
+    server: example.com
+    port: 443
+    cipher: aes-256-gcm
+    password: "password"
+    plugin: v2ray-plugin
+    plugin-opts:
+      mode: websocket
+      tls: true
+      skip-cert-verify: false
+      host: example.com
+      path: "/"
+      mux: true
+  - name: "shadow-tls"
+    type: ss
+    server: example.com
+    port: 443
+    cipher: aes-128-gcm
+    password: "password"
+    udp-over-tcp: true
+    udp-over-tcp-version: 2
+    client-fingerprint: chrome
+    plugin: shadow-tls
+    plugin-opts:
+      host: cloud.tencent.com
+      password: shadow_tls_password
+      version: 3
+    smux:
+      enabled: true
+      protocol: h2mux
+      max-connections: 4
+  - name: "trojan"
+    type: trojan
+    server: example.com
+    port: 443
+    password: "password"
+"#;
+
+    #[test]
+    fn can_parse_shadowsocks_proxies_of_a_clash_config() {
+        let configs = parse_clash_proxies(CONFIG).unwrap();
+        assert_eq!(configs.len(), 3);
+
+        assert_eq!(configs[0].host, Host::parse("[2001:db8::1]").unwrap());
+        assert_eq!(configs[0].method, Method::Chacha20IetfPoly1305);
+        assert_eq!(
+            configs[0].plugin().unwrap().to_string(),
+            "obfs-local;obfs-host=bing.com;obfs=tls"
+        );
+        assert_eq!(configs[0].mode, Some(Mode::TcpAndUdp));
+
+        assert_eq!(
+            configs[1].plugin().unwrap().to_string(),
+            "v2ray-plugin;host=example.com;path=/;tls"
+        );
+    }
+
+    #[test]
+    fn clash_proxies_round_trip_through_ss_config() {
+        for proxy in serde_yaml::from_str::<Value>(CONFIG).unwrap()["proxies"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .take(3)
+        {
+            let proxy: ClashProxy = serde_yaml::from_value(proxy.clone()).unwrap();
+            let config = proxy.to_config().unwrap();
+            let converted = ClashProxy::from_config(&config);
+            assert_eq!(converted.to_config().unwrap(), config);
+            assert_eq!(converted.plugin, proxy.plugin);
+            assert_eq!(converted.plugin_opts["host"], proxy.plugin_opts["host"]);
+        }
+    }
+
+    #[test]
+    fn keeps_clash_meta_fields() {
+        let configs = parse_clash_proxies(CONFIG).unwrap();
+        let extra = configs[2].extra.as_ref().unwrap();
+        assert_eq!(extra["udp-over-tcp"], "true");
+        assert_eq!(extra["client-fingerprint"], "chrome");
+        assert_eq!(extra["smux.protocol"], "h2mux");
+
+        let proxy = ClashProxy::from_config(&configs[2]);
+        assert_eq!(proxy.udp_over_tcp_version, Some(2));
+        assert_eq!(proxy.plugin_opts["version"], Value::from(3));
+        assert_eq!(proxy.smux["enabled"], Value::from(true));
+        assert_eq!(proxy.smux["max-connections"], Value::from(4));
+    }
+
+    #[test]
+    fn names_untagged_proxies_after_their_endpoint() {
+        let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888").unwrap();
+        let proxy = ClashProxy::from_config(&config);
+        assert_eq!(proxy.name, "[2001:db8::1]:8888");
+        assert_eq!(proxy.server, "2001:db8::1");
+        assert_eq!(proxy.udp, None);
+    }
+
+    #[test]
+    fn provider_names_are_unique_and_health_check_is_optional() {
+        let tagged = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#a").unwrap();
+        let mut suffixed = tagged.clone();
+        suffixed.tag = Some("a 2".to_string());
+        let provider = ClashProvider::from_configs([&tagged, &suffixed, &tagged]);
+        let names: Vec<_> = provider.proxies.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["a", "a 2", "a 3"]);
+        assert!(!provider.to_yaml().contains("health-check"));
+
+        let yaml = provider
+            .with_health_check("http://www.gstatic.com/generate_204", 300)
+            .to_yaml();
+        let parsed: ClashProvider = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed.health_check.unwrap().interval, 300);
+        assert_eq!(parse_clash_proxies(&yaml).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn rejects_other_proxy_types() {
+        let mut proxy = ClashProxy::from_config(
+            &SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888").unwrap(),
+        );
+        proxy.proxy_type = "vmess".to_string();
+        assert_eq!(proxy.to_config(), Err(ClashError::UnsupportedType));
+        assert_eq!(
+            parse_clash_proxies("proxies: ["),
+            Err(ClashError::InvalidYaml)
+        );
+    }
+}
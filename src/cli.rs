@@ -0,0 +1,172 @@
+//! clap argument types, `value_parser!(SSConfig)` and `value_parser!(SIP008Config)` pick up the
+//! parsers below
+use std::ffi::OsStr;
+
+use clap::builder::{TypedValueParser, ValueParserFactory};
+use clap::error::ErrorKind;
+use clap::{Arg, Command, Error};
+
+use crate::{ParseOptions, SIP008Config, SIP008ParseError, SSConfig, SSParseError};
+
+/// parses `ss://` arguments with [`SSConfig::parse_with_options`], errors name what's wrong
+/// without echoing the uri as it carries the password
+/// ```
+/// use clap::{value_parser, Arg, Command};
+/// use ss_uri::SSConfig;
+/// let cmd = Command::new("client").arg(
+///     Arg::new("server")
+///         .long("server")
+///         .value_parser(value_parser!(SSConfig)),
+/// );
+/// let matches = cmd
+///     .clone()
+///     .try_get_matches_from(["client", "--server", "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888"])
+///     .unwrap();
+/// assert_eq!(matches.get_one::<SSConfig>("server").unwrap().port, 8888);
+/// let err = cmd
+///     .try_get_matches_from(["client", "--server", "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:0"])
+///     .unwrap_err();
+/// assert!(err.to_string().contains("invalid port"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SSConfigValueParser {
+    options: ParseOptions,
+}
+
+impl SSConfigValueParser {
+    /// a parser applying `options`, e.g. [`ParseOptions::strict`]
+    pub fn new(options: ParseOptions) -> Self {
+        SSConfigValueParser { options }
+    }
+}
+
+impl TypedValueParser for SSConfigValueParser {
+    type Value = SSConfig;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<SSConfig, Error> {
+        let value = to_str(cmd, arg, value)?;
+        SSConfig::parse_with_options(value, &self.options)
+            .map_err(|err| invalid(cmd, arg, describe(err)))
+    }
+}
+
+impl ValueParserFactory for SSConfig {
+    type Parser = SSConfigValueParser;
+    fn value_parser() -> Self::Parser {
+        SSConfigValueParser::default()
+    }
+}
+
+/// parses `ssconf://` arguments with [`SIP008Config::parse`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SIP008ConfigValueParser;
+
+impl TypedValueParser for SIP008ConfigValueParser {
+    type Value = SIP008Config;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<SIP008Config, Error> {
+        let value = to_str(cmd, arg, value)?;
+        SIP008Config::parse(value).map_err(|err| {
+            let reason = match err {
+                SIP008ParseError::InvalidUrl => "not a valid url",
+                SIP008ParseError::InvalidProtocol => "expected an ssconf:// url",
+                SIP008ParseError::InvalidPort => "invalid port",
+                SIP008ParseError::InvalidHost => "invalid host",
+            };
+            invalid(cmd, arg, reason)
+        })
+    }
+}
+
+impl ValueParserFactory for SIP008Config {
+    type Parser = SIP008ConfigValueParser;
+    fn value_parser() -> Self::Parser {
+        SIP008ConfigValueParser
+    }
+}
+
+fn describe(err: SSParseError) -> &'static str {
+    match err {
+        SSParseError::InvalidUrl => "not a valid url",
+        SSParseError::InvalidProtocol => "expected an ss:// uri",
+        SSParseError::InvalidHost => "invalid host",
+        SSParseError::InvalidPort => "invalid port",
+        SSParseError::InvalidMethod => "unknown encryption method",
+        SSParseError::InvalidPassword => "invalid user info, expected base64 of method:password",
+        SSParseError::InvalidPrefix => "invalid outline prefix",
+        SSParseError::InvalidTag => "the tag isn't valid utf-8",
+        SSParseError::UnexpectedPath => "unexpected path after the port",
+        SSParseError::UnexpectedCharacters => "characters that can't appear in an uri",
+        SSParseError::LimitExceeded => "the uri is too large",
+    }
+}
+
+fn to_str<'a>(cmd: &Command, arg: Option<&Arg>, value: &'a OsStr) -> Result<&'a str, Error> {
+    value
+        .to_str()
+        .ok_or_else(|| invalid(cmd, arg, "not valid utf-8"))
+}
+
+fn invalid(cmd: &Command, arg: Option<&Arg>, reason: &str) -> Error {
+    let message = match arg {
+        Some(arg) => format!("invalid value for '{arg}': {reason}"),
+        None => format!("invalid value: {reason}"),
+    };
+    cmd.clone().error(ErrorKind::ValueValidation, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::value_parser;
+
+    use super::*;
+
+    #[test]
+    fn errors_leave_out_the_password() {
+        let cmd = Command::new("client")
+            .arg(
+                Arg::new("server")
+                    .long("server")
+                    .value_parser(SSConfigValueParser::new(ParseOptions {
+                        strict: true,
+                        ..ParseOptions::default()
+                    })),
+            )
+            .arg(
+                Arg::new("subscription")
+                    .long("subscription")
+                    .value_parser(value_parser!(SIP008Config)),
+            );
+        let uri = format!(
+            "ss://{}@192.168.100.1:8888/servers",
+            base64::encode("aes-128-gcm:secret")
+        );
+        let err = cmd
+            .clone()
+            .try_get_matches_from(["client", "--server", &uri])
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+        let message = err.to_string();
+        assert!(
+            message.contains("'--server <server>': unexpected path after the port"),
+            "{message}"
+        );
+        assert!(!message.contains(&uri[5..]), "{message}");
+
+        let matches = cmd
+            .try_get_matches_from(["client", "--subscription", "ssconf://example.com/sub.json"])
+            .unwrap();
+        let config = matches.get_one::<SIP008Config>("subscription").unwrap();
+        assert_eq!(config.location, "https://example.com:443/sub.json");
+    }
+}
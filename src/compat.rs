@@ -0,0 +1,195 @@
+use std::borrow::Cow;
+
+use url::Host;
+
+use crate::{
+    Base64Alphabet, Base64Variant, KnownParam, ParseOptions, QueryOrder, SSConfig, SSParseError,
+    SerializeOptions,
+};
+
+/// client whose quirks [`SSConfig::parse_with`] and [`SSConfig::to_uri`] follow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compat {
+    /// SIP002 as written, url-safe base64 without padding and a slash only before a query
+    Spec,
+    /// padded base64 and always a slash, what outline's own `ss-uri` produces
+    Outline,
+    ShadowsocksAndroid,
+    /// shadowsocks-windows always puts a slash after the port and writes the group in base64
+    ShadowsocksWindows,
+    ShadowsocksRust,
+    /// padded base64 and never a slash, see [`SSConfig::parse_shadowrocket`]
+    Shadowrocket,
+}
+
+impl Compat {
+    fn base64(self) -> Base64Variant {
+        match self {
+            Compat::Outline | Compat::Shadowrocket => Base64Variant {
+                alphabet: Base64Alphabet::Standard,
+                padded: true,
+            },
+            _ => Base64Variant::SIP002,
+        }
+    }
+    fn slash(self, has_query: bool) -> bool {
+        match self {
+            Compat::Outline | Compat::ShadowsocksWindows => true,
+            Compat::Shadowrocket => false,
+            _ => has_query,
+        }
+    }
+}
+
+impl SSConfig {
+    /// parses an uri the way the given client would, `Spec` behaves like [`SSConfig::parse`]
+    /// while the client presets also accept plain user info and shadowrocket's quirks
+    /// ```
+    /// use ss_uri::{Compat, SSConfig};
+    /// let uri = "ss://aes-128-gcm:test@192.168.100.1:8888";
+    /// assert!(SSConfig::parse_with(uri, Compat::Spec).is_err());
+    /// let config = SSConfig::parse_with(uri, Compat::ShadowsocksRust).unwrap();
+    /// assert_eq!(config.password, "test");
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(s), fields(len = s.len()), err(level = "debug"))
+    )]
+    pub fn parse_with(s: &str, compat: Compat) -> Result<Self, SSParseError> {
+        match compat {
+            Compat::Spec => Self::parse(s),
+            Compat::Shadowrocket => Self::parse_shadowrocket_checked(s),
+            _ => Self::parse(s).or_else(|err| Self::parse_shadowrocket_checked(s).map_err(|_| err)),
+        }
+    }
+    /// [`SSConfig::parse_shadowrocket`] held to the same defaults as [`SSConfig::parse`]
+    fn parse_shadowrocket_checked(s: &str) -> Result<Self, SSParseError> {
+        let options = ParseOptions::default();
+        options.limits.check(s)?;
+        Self::parse_shadowrocket(s).and_then(|config| options.check(s, config))
+    }
+    /// serializes the config as a sip002 uri the given client imports
+    /// ```
+    /// use ss_uri::{Compat, SSConfig};
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo").unwrap();
+    /// assert_eq!(config.to_uri(Compat::Spec), "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo");
+    /// assert_eq!(config.to_uri(Compat::Outline), "ss://YWVzLTEyOC1nY206dGVzdA==@192.168.100.1:8888/#Foo");
+    /// ```
+    pub fn to_uri(&self, compat: Compat) -> String {
+        self.to_uri_ordered(compat, QueryOrder::Insertion)
+    }
+    /// like [`SSConfig::to_uri`] with the extra parameters written in the given order, for uris
+    /// that stay the same however the config was built
+    /// ```
+    /// use ss_uri::{Compat, QueryOrder, SSConfig};
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8888/?b=1&plugin=obfs-local&a=2").unwrap();
+    /// assert_eq!(
+    ///     config.to_uri_ordered(Compat::Spec, QueryOrder::Sorted),
+    ///     "ss://YWVzLTEyOC1nY206dGVzdA@example.com:8888/?plugin=obfs-local&a=2&b=1"
+    /// );
+    /// ```
+    pub fn to_uri_ordered(&self, compat: Compat, order: QueryOrder) -> String {
+        let options = SerializeOptions {
+            query_order: order,
+            ..SerializeOptions::default()
+        };
+        self.to_uri_with(compat, &options)
+    }
+    /// like [`SSConfig::to_uri`] with the given options
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
+    pub fn to_uri_with(&self, compat: Compat, options: &SerializeOptions) -> String {
+        let user_info = options
+            .base64
+            .unwrap_or(compat.base64())
+            .encode(format!("{}:{}", self.method, self.password));
+        let extra = match (compat, self.group()) {
+            (Compat::ShadowsocksWindows, Some(Cow::Borrowed(group))) => {
+                let encoded = base64::encode_config(group, base64::URL_SAFE_NO_PAD);
+                let mut extra = self.extra.clone().unwrap_or_default();
+                extra.insert(KnownParam::Group.key().to_string(), encoded);
+                Cow::Owned(Some(extra))
+            }
+            _ => Cow::Borrowed(&self.extra),
+        };
+        let query = if options.skip_query {
+            String::new()
+        } else {
+            Self::encode_query(
+                extra.as_ref().as_ref(),
+                self.mode.and_then(|mode| compat.mode_param(mode)),
+                self.prefix.as_ref(),
+                options.query_order,
+            )
+        };
+        let slash = match options.slash.unwrap_or(compat.slash(!query.is_empty())) {
+            true => "/",
+            false => "",
+        };
+        let hash = if options.skip_tag {
+            String::new()
+        } else {
+            Self::get_hash(&self.tag, &self.tag_raw, options)
+        };
+        let host = match &self.host {
+            Host::Domain(_) if options.unicode_host => self.host_unicode(),
+            host => Self::get_uri_formatted_host(host),
+        };
+        format!("ss://{user_info}@{host}:{}{slash}{query}{hash}", self.port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPATS: [Compat; 6] = [
+        Compat::Spec,
+        Compat::Outline,
+        Compat::ShadowsocksAndroid,
+        Compat::ShadowsocksWindows,
+        Compat::ShadowsocksRust,
+        Compat::Shadowrocket,
+    ];
+
+    #[test]
+    fn serializes_per_client() {
+        let config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdD8-Pz8@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp",
+        )
+        .unwrap();
+        let uris = COMPATS.map(|compat| config.to_uri(compat));
+        assert_eq!(
+            uris,
+            [
+                "ss://YWVzLTEyOC1nY206dGVzdD8-Pz8@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp",
+                "ss://YWVzLTEyOC1nY206dGVzdD8+Pz8=@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp",
+                "ss://YWVzLTEyOC1nY206dGVzdD8-Pz8@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp",
+                "ss://YWVzLTEyOC1nY206dGVzdD8-Pz8@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp",
+                "ss://YWVzLTEyOC1nY206dGVzdD8-Pz8@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp",
+                "ss://YWVzLTEyOC1nY206dGVzdD8+Pz8=@192.168.100.1:8888?plugin=obfs-local%3Bobfs%3Dhttp",
+            ]
+        );
+    }
+
+    #[test]
+    fn every_client_parses_its_own_output() {
+        let config =
+            SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888#Foo%20Bar").unwrap();
+        for compat in COMPATS {
+            let uri = config.to_uri(compat);
+            assert_eq!(SSConfig::parse_with(&uri, compat).unwrap(), config, "{uri}");
+        }
+    }
+
+    #[test]
+    fn every_client_rejects_port_zero() {
+        for compat in COMPATS {
+            assert_eq!(
+                SSConfig::parse_with("ss://YWVzLTEyOC1nY206dGVzdA@1.2.3.4:0", compat),
+                Err(SSParseError::InvalidPort),
+                "{compat:?}"
+            );
+            assert!(SSConfig::parse_with("ss://aes-128-gcm:test@1.2.3.4:0", compat).is_err());
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! known-good and known-bad shadowsocks uris collected from the SIP002 spec, outline's
+//! `ss-uri` test suite and real-world subscriptions, for checking other parsers or wrappers
+//! against the same inputs this crate is tested with
+//! ```
+//! use ss_uri::conformance::{INVALID_URIS, VALID_URIS};
+//! use ss_uri::SSConfig;
+//! for vector in VALID_URIS {
+//!     let config = SSConfig::parse(vector.uri).unwrap();
+//!     assert!(vector.matches(&config), "{}", vector.name);
+//! }
+//! for vector in INVALID_URIS {
+//!     assert!(SSConfig::parse(vector.uri).is_err(), "{}", vector.name);
+//! }
+//! ```
+use crate::SSConfig;
+
+/// an uri every parser should accept along with what it has to decode to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ValidVector {
+    pub name: &'static str,
+    pub uri: &'static str,
+    pub method: &'static str,
+    pub password: &'static str,
+    /// host as `url::Host` formats it, ipv6 addresses in brackets
+    pub host: &'static str,
+    pub port: u16,
+    pub tag: Option<&'static str>,
+    /// the raw `plugin` parameter
+    pub plugin: Option<&'static str>,
+}
+
+/// an uri every parser should reject
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InvalidVector {
+    pub name: &'static str,
+    pub uri: &'static str,
+}
+
+impl ValidVector {
+    /// whether a parsed config decodes every field of the vector
+    pub fn matches(&self, config: &SSConfig) -> bool {
+        config.method.as_str() == self.method
+            && config.password == self.password
+            && config.host.to_string() == self.host
+            && config.port == self.port
+            && config.tag.as_deref() == self.tag
+            && config
+                .extra
+                .as_ref()
+                .and_then(|e| e.get("plugin"))
+                .map(String::as_str)
+                == self.plugin
+    }
+}
+
+pub const VALID_URIS: &[ValidVector] = &[
+    ValidVector {
+        name: "sip002 example without plugin",
+        uri: "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Example1",
+        method: "aes-128-gcm",
+        password: "test",
+        host: "192.168.100.1",
+        port: 8888,
+        tag: Some("Example1"),
+        plugin: None,
+    },
+    ValidVector {
+        name: "sip002 example with plugin",
+        uri:
+            "ss://cmM0LW1kNTpwYXNzd2Q@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp#Example2",
+        method: "rc4-md5",
+        password: "passwd",
+        host: "192.168.100.1",
+        port: 8888,
+        tag: Some("Example2"),
+        plugin: Some("obfs-local;obfs=http"),
+    },
+    ValidVector {
+        name: "legacy base64 with unencoded tag",
+        uri: "ss://YmYtY2ZiOnRlc3RAMTkyLjE2OC4xMDAuMTo4ODg4#Foo Bar",
+        method: "bf-cfb",
+        password: "test",
+        host: "192.168.100.1",
+        port: 8888,
+        tag: Some("Foo Bar"),
+        plugin: None,
+    },
+    ValidVector {
+        name: "padded user info",
+        uri: "ss://YWVzLTEyOC1nY206dGVzdA==@192.168.100.1:8888#Foo%20Bar",
+        method: "aes-128-gcm",
+        password: "test",
+        host: "192.168.100.1",
+        port: 8888,
+        tag: Some("Foo Bar"),
+        plugin: None,
+    },
+    ValidVector {
+        name: "ipv6 host",
+        uri: "ss://YWVzLTEyOC1nY206dGVzdA@[2001:0:ce49:7601:e866:efff:62c3:fffe]:8888",
+        method: "aes-128-gcm",
+        password: "test",
+        host: "[2001:0:ce49:7601:e866:efff:62c3:fffe]",
+        port: 8888,
+        tag: None,
+        plugin: None,
+    },
+    ValidVector {
+        name: "compressed ipv6 host",
+        uri: "ss://YWVzLTEyOC1nY206dGVzdA@[2001::fffe]:8888",
+        method: "aes-128-gcm",
+        password: "test",
+        host: "[2001::fffe]",
+        port: 8888,
+        tag: None,
+        plugin: None,
+    },
+    ValidVector {
+        name: "non latin password",
+        uri: "ss://YWVzLTEyOC1nY2065bCP5rSe5LiN6KGl5aSn5rSe5ZCD6Ium@192.168.100.1:8888",
+        method: "aes-128-gcm",
+        password: "小洞不补大洞吃苦",
+        host: "192.168.100.1",
+        port: 8888,
+        tag: None,
+        plugin: None,
+    },
+    ValidVector {
+        name: "non latin tag",
+        uri: "ss://YWVzLTEyOC1nY206dGVzdA@example.com:443#%E6%97%A5%E6%9C%AC%2001",
+        method: "aes-128-gcm",
+        password: "test",
+        host: "example.com",
+        port: 443,
+        tag: Some("日本 01"),
+        plugin: None,
+    },
+    ValidVector {
+        name: "port 80 without plugin parameters",
+        uri: "ss://cmM0LW1kNTpwYXNzd2Q@192.168.100.1:80",
+        method: "rc4-md5",
+        password: "passwd",
+        host: "192.168.100.1",
+        port: 80,
+        tag: None,
+        plugin: None,
+    },
+];
+
+pub const INVALID_URIS: &[InvalidVector] = &[
+    InvalidVector {
+        name: "empty input",
+        uri: "",
+    },
+    InvalidVector {
+        name: "other scheme",
+        uri: "http://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888",
+    },
+    InvalidVector {
+        name: "missing port",
+        uri: "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1",
+    },
+    InvalidVector {
+        name: "port out of range",
+        uri: "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:88888",
+    },
+    InvalidVector {
+        name: "port zero",
+        uri: "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:0",
+    },
+    InvalidVector {
+        name: "legacy port zero",
+        uri: "ss://YWVzLTEyOC1nY206dGVzdEAxOTIuMTY4LjEwMC4xOjA",
+    },
+    InvalidVector {
+        name: "unknown method",
+        uri: "ss://Zm9vOnRlc3Q@192.168.100.1:8888",
+    },
+    InvalidVector {
+        name: "missing password",
+        uri: "ss://YWVzLTEyOC1nY20@192.168.100.1:8888",
+    },
+    InvalidVector {
+        name: "user info that isn't base64",
+        uri: "ss://not*base64@192.168.100.1:8888",
+    },
+    InvalidVector {
+        name: "legacy payload without host",
+        uri: "ss://YWVzLTEyOC1nY206dGVzdA",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vectors_hold_for_this_parser() {
+        for vector in VALID_URIS {
+            let config = SSConfig::parse(vector.uri).unwrap();
+            assert!(vector.matches(&config), "{}", vector.name);
+        }
+        for vector in INVALID_URIS {
+            assert!(SSConfig::parse(vector.uri).is_err(), "{}", vector.name);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! what changed between two fetches of a subscription
+use std::collections::{HashMap, VecDeque};
+
+use crate::SSConfig;
+
+/// a difference between two server lists, servers are matched by host and port
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ServerChange {
+    Added(SSConfig),
+    Removed(SSConfig),
+    /// the endpoint stayed while the tag, credentials or extra parameters changed
+    Changed {
+        old: SSConfig,
+        new: SSConfig,
+    },
+}
+
+/// lists changed and added servers in the order of `new` followed by the removed ones in the
+/// order of `old`
+///
+/// endpoints are compared the way [`SSConfig::endpoint_eq`] does and configs the way
+/// [`SSConfig::canonicalize`] leaves them, so a differently encoded uri or reordered query
+/// isn't a change. when an endpoint appears several times the occurrences are paired up in
+/// order
+/// ```
+/// use ss_uri::{diff, SSConfig, ServerChange};
+/// let parse = |uri| SSConfig::parse(uri).unwrap();
+/// let old = [
+///     parse("ss://YWVzLTEyOC1nY206dGVzdA@a.example.com:8388#A"),
+///     parse("ss://YWVzLTEyOC1nY206dGVzdA@b.example.com:8388#B"),
+/// ];
+/// let new = [
+///     parse("ss://YWVzLTEyOC1nY206dGVzdA@b.example.com:8388#B%20(new)"),
+///     parse("ss://YWVzLTEyOC1nY206dGVzdA@c.example.com:8388#C"),
+/// ];
+/// let changes = diff(&old, &new);
+/// assert!(matches!(&changes[..], [
+///     ServerChange::Changed { old, new },
+///     ServerChange::Added(c),
+///     ServerChange::Removed(a),
+/// ] if old.tag.as_deref() == Some("B") && new.tag.as_deref() == Some("B (new)")
+///     && c.tag.as_deref() == Some("C") && a.tag.as_deref() == Some("A")));
+/// ```
+pub fn diff(old: &[SSConfig], new: &[SSConfig]) -> Vec<ServerChange> {
+    let mut unmatched = HashMap::<_, VecDeque<usize>>::new();
+    for (i, config) in old.iter().enumerate() {
+        unmatched.entry(config.endpoint()).or_default().push_back(i);
+    }
+    let mut matched = vec![false; old.len()];
+    let mut changes = Vec::new();
+    for config in new {
+        let i = unmatched
+            .get_mut(&config.endpoint())
+            .and_then(VecDeque::pop_front);
+        match i {
+            None => changes.push(ServerChange::Added(config.clone())),
+            Some(i) => {
+                matched[i] = true;
+                if old[i].canonicalize() != config.canonicalize() {
+                    changes.push(ServerChange::Changed {
+                        old: old[i].clone(),
+                        new: config.clone(),
+                    });
+                }
+            }
+        }
+    }
+    let removed = old.iter().zip(matched).filter(|(_, matched)| !matched);
+    changes.extend(removed.map(|(config, _)| ServerChange::Removed(config.clone())));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(uri: &str) -> SSConfig {
+        SSConfig::parse(uri).unwrap()
+    }
+
+    #[test]
+    fn reencoded_uris_are_unchanged() {
+        let old = [parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA==@Example.com:8388/?b=1&plugin=obfs-local%3B%3Bobfs%3Dhttp&a=2#%46oo",
+        )];
+        let new = [parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@example.com:8388/?plugin=obfs-local%3Bobfs%3Dhttp&a=2&b=1#Foo",
+        )];
+        assert_eq!(diff(&old, &new), []);
+        let changed = [new[0].clone().with_password("other")];
+        assert!(matches!(
+            &diff(&old, &changed)[..],
+            [ServerChange::Changed { .. }]
+        ));
+    }
+
+    #[test]
+    fn repeated_endpoints_pair_up_in_order() {
+        let a = parse("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8388#1");
+        let b = a.clone().with_tag("2");
+        let c = a.clone().with_tag("3");
+        let (ab, ac) = ([a.clone(), b.clone()], [a.clone(), c.clone()]);
+        assert_eq!(diff(&ab, &ac[..1]), [ServerChange::Removed(b.clone())]);
+        assert_eq!(diff(&ab[..1], &ac), [ServerChange::Added(c.clone())]);
+        assert_eq!(
+            diff(&ab, &ac[1..]),
+            [
+                ServerChange::Changed { old: a, new: c },
+                ServerChange::Removed(b)
+            ]
+        );
+    }
+}
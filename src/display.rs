@@ -0,0 +1,238 @@
+//! `fmt::Display` adapters writing uris straight into the formatter
+use core::fmt::{self, Write};
+use std::io;
+
+use percent_encoding::{percent_encode, utf8_percent_encode, NON_ALPHANUMERIC};
+
+use crate::{Compat, SSConfig};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// formats a config like [`SSConfig::to_sip002`], see [`SSConfig::sip002_display`]
+#[derive(Debug, Clone, Copy)]
+pub struct Sip002Display<'a>(&'a SSConfig);
+
+/// formats a config like [`SSConfig::to_legacy_base64_encoded`], see [`SSConfig::legacy_display`]
+#[derive(Debug, Clone, Copy)]
+pub struct LegacyDisplay<'a>(&'a SSConfig);
+
+/// standard base64 without padding, encoded three bytes at a time as they are written
+struct Base64Writer<'a, W: Write> {
+    out: &'a mut W,
+    pending: [u8; 3],
+    len: usize,
+}
+
+impl<'a, W: Write> Base64Writer<'a, W> {
+    fn new(out: &'a mut W) -> Self {
+        Self {
+            out,
+            pending: [0; 3],
+            len: 0,
+        }
+    }
+    fn flush(&mut self) -> fmt::Result {
+        let [a, b, c] = self.pending;
+        let group = u32::from_be_bytes([0, a, b, c]);
+        for i in 0..=self.len {
+            let index = (group >> (18 - 6 * i)) & 0x3f;
+            self.out.write_char(char::from(ALPHABET[index as usize]))?;
+        }
+        self.pending = [0; 3];
+        self.len = 0;
+        Ok(())
+    }
+    fn finish(mut self) -> fmt::Result {
+        if self.len > 0 {
+            self.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Base64Writer<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            self.pending[self.len] = byte;
+            self.len += 1;
+            if self.len == 3 {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_hash(f: &mut fmt::Formatter<'_>, config: &SSConfig) -> fmt::Result {
+    if let Some(raw) = SSConfig::raw_hash(&config.tag, &config.tag_raw) {
+        return write!(f, "#{raw}");
+    }
+    match config.tag.as_deref() {
+        Some(tag) if !tag.is_empty() => {
+            write!(f, "#{}", percent_encode(tag.as_bytes(), crate::FRAGMENT))
+        }
+        _ => Ok(()),
+    }
+}
+
+impl fmt::Display for Sip002Display<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let config = self.0;
+        f.write_str("ss://")?;
+        let mut user_info = Base64Writer::new(f);
+        write!(user_info, "{}:{}", config.method, config.password)?;
+        user_info.finish()?;
+        write!(f, "@{}:{}/", config.host, config.port)?;
+
+        let mode = config.mode.and_then(|mode| Compat::Spec.mode_param(mode));
+        let extra = config.extra.iter().flatten();
+        let mut separator = '?';
+        for (key, value) in extra.map(|(k, v)| (k.as_str(), v.as_str())).chain(mode) {
+            f.write_char(separator)?;
+            separator = '&';
+            url::form_urlencoded::byte_serialize(key.as_bytes())
+                .try_for_each(|s| f.write_str(s))?;
+            f.write_char('=')?;
+            url::form_urlencoded::byte_serialize(value.as_bytes())
+                .try_for_each(|s| f.write_str(s))?;
+        }
+        if let Some(prefix) = &config.prefix {
+            f.write_char(separator)?;
+            f.write_str("prefix=")?;
+            for byte in prefix.as_bytes() {
+                let mut buf = [0; 4];
+                let c = char::from(*byte).encode_utf8(&mut buf);
+                write!(f, "{}", utf8_percent_encode(c, NON_ALPHANUMERIC))?;
+            }
+        }
+        write_hash(f, config)
+    }
+}
+
+impl fmt::Display for LegacyDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let config = self.0;
+        f.write_str("ss://")?;
+        let mut payload = Base64Writer::new(f);
+        write!(
+            payload,
+            "{}:{}@{}:{}",
+            config.method, config.password, config.host, config.port
+        )?;
+        payload.finish()?;
+        write_hash(f, config)
+    }
+}
+
+impl SSConfig {
+    /// formats as [`SSConfig::to_sip002`] does without building intermediate strings
+    /// ```
+    /// use std::fmt::Write;
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo").unwrap();
+    /// let mut out = String::new();
+    /// writeln!(out, "{}", config.sip002_display()).unwrap();
+    /// assert_eq!(out, "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/#Foo\n");
+    /// ```
+    pub fn sip002_display(&self) -> Sip002Display<'_> {
+        Sip002Display(self)
+    }
+    /// formats as [`SSConfig::to_legacy_base64_encoded`] does without building intermediate strings
+    pub fn legacy_display(&self) -> LegacyDisplay<'_> {
+        LegacyDisplay(self)
+    }
+    /// appends the [`SSConfig::to_sip002`] form to an existing buffer
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888").unwrap();
+    /// let mut out = String::from("uri: ");
+    /// config.write_sip002(&mut out).unwrap();
+    /// assert_eq!(out, "uri: ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/");
+    /// ```
+    pub fn write_sip002<W: Write + ?Sized>(&self, w: &mut W) -> fmt::Result {
+        write!(w, "{}", self.sip002_display())
+    }
+    /// appends the [`SSConfig::to_legacy_base64_encoded`] form to an existing buffer
+    pub fn write_legacy_base64<W: Write + ?Sized>(&self, w: &mut W) -> fmt::Result {
+        write!(w, "{}", self.legacy_display())
+    }
+    /// writes the [`SSConfig::to_sip002`] form to a file, socket or any other byte sink
+    pub fn write_sip002_to<W: io::Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "{}", self.sip002_display())
+    }
+    /// writes the [`SSConfig::to_legacy_base64_encoded`] form to a byte sink
+    pub fn write_legacy_base64_to<W: io::Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "{}", self.legacy_display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_base64_incrementally() {
+        for input in [
+            "",
+            "a",
+            "ab",
+            "abc",
+            "abcd",
+            "小洞不补大洞吃苦",
+            "\u{1}\u{7f}?>",
+        ] {
+            let mut out = String::new();
+            let mut writer = Base64Writer::new(&mut out);
+            for c in input.chars() {
+                writer.write_char(c).unwrap();
+            }
+            writer.finish().unwrap();
+            assert_eq!(out, base64::encode_config(input, base64::STANDARD_NO_PAD));
+        }
+    }
+
+    #[test]
+    fn writes_into_existing_buffers() {
+        let configs = [
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo",
+            "ss://YmYtY2ZiOnRlc3RAMTkyLjE2OC4xMDAuMTo4ODg4#Bar",
+        ]
+        .map(|uri| SSConfig::parse(uri).unwrap());
+        let mut text = String::new();
+        let mut bytes = Vec::new();
+        for config in &configs {
+            config.write_sip002(&mut text).unwrap();
+            text.push('\n');
+            config.write_legacy_base64_to(&mut bytes).unwrap();
+            bytes.push(b'\n');
+        }
+        let expected =
+            |f: fn(&SSConfig) -> String| configs.iter().map(|c| f(c) + "\n").collect::<String>();
+        assert_eq!(text, expected(SSConfig::to_sip002));
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            expected(SSConfig::to_legacy_base64_encoded)
+        );
+    }
+
+    #[test]
+    fn matches_the_string_serializers() {
+        for uri in [
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo%20Bar",
+            "ss://YWVzLTEyOC1nY206dGVzdDE@[2001:db8::1]:8888/?plugin=obfs-local%3Bobfs%3Dhttp&a=b%20c",
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?a=1&mode=tcp_and_udp#x",
+            "ss://YWVzLTEyOC1nY2065bCP5rSe5LiN6KGl5aSn5rSe5ZCD6Ium@example.com:443/?prefix=%16%03%01%00%C2%A8#%E6%97%A5",
+        ] {
+            let config = SSConfig::parse(uri).unwrap();
+            assert_eq!(config.sip002_display().to_string(), config.to_sip002());
+            assert_eq!(
+                SSConfig::parse(&config.sip002_display().to_string()).unwrap(),
+                config
+            );
+            assert_eq!(
+                config.legacy_display().to_string(),
+                config.to_legacy_base64_encoded()
+            );
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! conversion between [`SSConfig`] and the flat server entries used by json/yaml based formats
+use std::borrow::Cow;
+use std::net::Ipv6Addr;
+
+use url::Host;
+
+use crate::{Plugin, QueryParams, SSConfig, SSParseError};
+
+/// parses a bare host as found in server entries, ipv6 addresses come without brackets
+pub(crate) fn parse_host(host: &str) -> Result<Host, SSParseError> {
+    match host.parse::<Ipv6Addr>() {
+        Ok(ip) => Ok(Host::Ipv6(ip)),
+        Err(_) => Host::parse(host).map_err(|_| SSParseError::InvalidHost),
+    }
+}
+
+/// formats a host the way server entries expect it, ipv6 addresses without brackets
+pub(crate) fn host_to_string(host: &Host) -> String {
+    match host {
+        Host::Ipv6(ip) => ip.to_string(),
+        host => host.to_string(),
+    }
+}
+
+/// splits the `plugin` extra parameter into the plugin name and its options
+pub(crate) fn plugin_parts(config: &SSConfig) -> (Option<String>, Option<String>) {
+    match config.plugin() {
+        Some(plugin) => (Some(plugin.name), plugin.opts),
+        None => (None, None),
+    }
+}
+
+/// assembles a config out of the fields every server entry format carries
+pub(crate) fn build_config(
+    server: &str,
+    port: u16,
+    method: &str,
+    password: &str,
+    tag: Option<String>,
+    plugin: Option<&str>,
+    plugin_opts: Option<&str>,
+) -> Result<SSConfig, SSParseError> {
+    let host = parse_host(server)?;
+    let method = method.parse().map_err(|_| SSParseError::InvalidMethod)?;
+    let extra = plugin.filter(|p| !p.is_empty()).map(|plugin| {
+        let plugin = Plugin::new(plugin, plugin_opts.map(str::to_string));
+        QueryParams::from([("plugin".to_string(), plugin.to_string())])
+    });
+    Ok(SSConfig {
+        host,
+        port,
+        method,
+        password: password.to_string(),
+        tag,
+        tag_raw: None,
+        extra,
+        prefix: None,
+        mode: None,
+    })
+}
+
+/// the name used by formats that require one, the tag falling back to `host:port`
+pub(crate) fn name_or_endpoint(config: &SSConfig) -> String {
+    config
+        .tag
+        .clone()
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| {
+            format!(
+                "{}:{}",
+                SSConfig::get_uri_formatted_host(&config.host),
+                config.port
+            )
+        })
+}
+
+/// drops the byte order mark and surrounding whitespace that links copied from documents and
+/// lines read from files tend to carry
+pub(crate) fn trim_input(s: &str) -> &str {
+    s.trim_start_matches('\u{feff}').trim()
+}
+
+/// restores the brackets of an ipv6 host that javascript's `encodeURI` percent-encoded, as in
+/// `ss://userinfo@%5B2001:db8::1%5D:8388`
+pub(crate) fn unescape_host_brackets(s: &str) -> Cow<'_, str> {
+    let Some(start) = s.find("://").map(|i| i + 3) else {
+        return Cow::Borrowed(s);
+    };
+    let authority = s[start..].split(['/', '?', '#']).next().unwrap_or_default();
+    let host_start = start + authority.rfind('@').map_or(0, |i| i + 1);
+    let host = &s[host_start..start + authority.len()];
+    let is_escape = |i: usize, escape: &str| {
+        host.get(i..i + 3)
+            .is_some_and(|e| e.eq_ignore_ascii_case(escape))
+    };
+    if !is_escape(0, "%5B") {
+        return Cow::Borrowed(s);
+    }
+    let Some(close) = (3..host.len()).find(|&i| is_escape(i, "%5D")) else {
+        return Cow::Borrowed(s);
+    };
+    Cow::Owned(format!(
+        "{}[{}]{}",
+        &s[..host_start],
+        &host[3..close],
+        &s[host_start + close + 3..]
+    ))
+}
+
+/// the rest of `s` after `scheme://`, the scheme matched regardless of case since some chat
+/// apps capitalize links
+pub(crate) fn strip_scheme<'a>(s: &'a str, scheme: &str) -> Option<&'a str> {
+    let (found, rest) = s.split_once("://")?;
+    found.eq_ignore_ascii_case(scheme).then_some(rest)
+}
+
+/// splits a comma separated proxy line, commas inside double quotes don't split and neither do
+/// quotes escaped by [`quote`]
+pub(crate) fn split_fields(line: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if quoted => {
+                chars.next_if(|&(_, c)| c == '"' || c == '\\');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                fields.push(line[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(line[start..].trim());
+    fields
+}
+
+/// splits a `key=value` field of a proxy line, the value is unquoted
+pub(crate) fn split_pair(field: &str) -> Option<(&str, Cow<'_, str>)> {
+    let (key, value) = field.split_once('=')?;
+    Some((key.trim(), unquote(value.trim())))
+}
+
+/// removes the double quotes around a value along with the escapes [`quote`] added, other
+/// backslashes are kept as they are
+pub(crate) fn unquote(value: &str) -> Cow<'_, str> {
+    let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return Cow::Borrowed(value);
+    };
+    if !inner.contains('\\') {
+        return Cow::Borrowed(inner);
+    }
+    let mut unescaped = String::with_capacity(inner.len());
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(chars.next_if(|&c| c == '"' || c == '\\').unwrap_or(c)),
+            c => unescaped.push(c),
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+/// wraps values that would break a proxy line in double quotes, see [`always_quote`]
+pub(crate) fn quote(value: &str) -> String {
+    if value.contains([',', '"', '=']) || value.trim() != value {
+        always_quote(value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// wraps a value in double quotes, quotes and backslashes inside are escaped with a backslash
+pub(crate) fn always_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// decodes base64 of either alphabet, with or without padding, into a string, whitespace left
+/// behind by line wrapping is skipped
+pub(crate) fn decode_base64_lenient(input: &str) -> Option<String> {
+    let unwrapped;
+    let input = if input.contains(|c: char| c.is_ascii_whitespace()) {
+        unwrapped = input.replace(|c: char| c.is_ascii_whitespace(), "");
+        &unwrapped
+    } else {
+        input
+    };
+    let input = input.trim_end_matches('=');
+    #[cfg(feature = "base64-simd")]
+    if let Some(decoded) = decode_base64_simd(input) {
+        return String::from_utf8(decoded).ok();
+    }
+    let normalized = input.replace('-', "+").replace('_', "/");
+    let decoded = base64::decode_config(normalized, base64::STANDARD_NO_PAD).ok()?;
+    String::from_utf8(decoded).ok()
+}
+
+/// simd fast path for input using a single alphabet, `None` leaves mixed alphabets and
+/// anything it rejects to the scalar decoder
+#[cfg(feature = "base64-simd")]
+fn decode_base64_simd(input: &str) -> Option<Vec<u8>> {
+    let engine = match (input.contains(['+', '/']), input.contains(['-', '_'])) {
+        (_, false) => base64_simd::STANDARD_NO_PAD,
+        (false, true) => base64_simd::URL_SAFE_NO_PAD,
+        (true, true) => return None,
+    };
+    engine.decode_to_vec(input).ok()
+}
+
+#[cfg(all(test, feature = "base64-simd"))]
+mod tests {
+    use super::*;
+    use crate::test_util::Rng;
+
+    #[test]
+    fn simd_decoding_agrees_with_the_scalar_decoder() {
+        let alphabet = b"AQgwz09+/-_=*";
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+        for _ in 0..20_000 {
+            let len = rng.below(24);
+            let input = (0..len)
+                .map(|_| char::from(alphabet[rng.below(alphabet.len())]))
+                .collect::<String>();
+            let input = input.trim_end_matches('=');
+            if let Some(decoded) = decode_base64_simd(input) {
+                let normalized = input.replace('-', "+").replace('_', "/");
+                assert_eq!(
+                    base64::decode_config(normalized, base64::STANDARD_NO_PAD).ok(),
+                    Some(decoded),
+                    "{input}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,868 @@
+use core::fmt;
+use std::error::Error;
+use std::time::{Duration, Instant, SystemTime};
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ETAG, RETRY_AFTER};
+use reqwest::{redirect, Response, Url};
+#[cfg(feature = "rustls")]
+use sha2::{Digest, Sha256};
+
+use crate::{
+    parse_outline_dynamic_key, OutlineKeyError, SIP008Config, SSConfig, Sip008Document,
+    Sip008DocumentError, Subscription, SubscriptionError, SubscriptionInfo,
+};
+
+#[cfg(not(any(feature = "rustls", feature = "native-tls")))]
+compile_error!(
+    "`tokio` and `__fetch` need a TLS backend, enable the `rustls` or the `native-tls` feature"
+);
+
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_MAX_BODY_SIZE: usize = 4 * 1024 * 1024;
+
+/// builder for [`Sip008Client`]
+#[derive(Debug, Clone)]
+pub struct Sip008ClientBuilder {
+    max_redirects: usize,
+    headers: HeaderMap,
+    user_agent: Option<String>,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    max_body_size: usize,
+    request_body: Vec<u8>,
+}
+
+/// downloads SIP008 online configurations pointed to by a [`SIP008Config`]
+#[derive(Debug, Clone)]
+pub struct Sip008Client {
+    settings: Sip008ClientBuilder,
+}
+
+/// a fetched document along with the http metadata of the response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchResult<T = Sip008Document> {
+    pub document: T,
+    pub status: u16,
+    pub etag: Option<String>,
+    pub retry_after: Option<RetryAfter>,
+    /// parsed `subscription-userinfo` header
+    pub subscription_info: Option<SubscriptionInfo>,
+    /// time from sending the request until the whole body was received
+    pub response_time: Duration,
+    /// wall clock time the request was sent at
+    pub fetched_at: SystemTime,
+}
+
+/// value of the `Retry-After` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RetryAfter {
+    Delay(Duration),
+    At(SystemTime),
+}
+
+impl<T> FetchResult<T> {
+    fn try_map<U>(
+        self,
+        f: impl FnOnce(T) -> Result<U, FetchError>,
+    ) -> Result<FetchResult<U>, FetchError> {
+        Ok(FetchResult {
+            document: f(self.document)?,
+            status: self.status,
+            etag: self.etag,
+            retry_after: self.retry_after,
+            subscription_info: self.subscription_info,
+            response_time: self.response_time,
+            fetched_at: self.fetched_at,
+        })
+    }
+}
+
+impl RetryAfter {
+    /// parses either the delay-seconds or the http-date form
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        match value.parse::<u64>() {
+            Ok(seconds) => Some(RetryAfter::Delay(Duration::from_secs(seconds))),
+            Err(_) => httpdate::parse_http_date(value).ok().map(RetryAfter::At),
+        }
+    }
+}
+
+/// reasons a redirect is refused by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RedirectError {
+    TooManyRedirects,
+    Downgrade,
+}
+impl fmt::Display for RedirectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for RedirectError {}
+
+#[derive(Debug)]
+pub enum FetchError {
+    InvalidLocation,
+    InvalidHttpMethod,
+    Request(reqwest::Error),
+    Timeout,
+    BodyTooLarge,
+    Redirect(RedirectError),
+    /// the certificate of a host didn't match the pin, nothing was sent to it
+    FingerprintMismatch,
+    /// pinned configs need the `rustls` backend, `native-tls` can't check the pin before
+    /// sending the request
+    FingerprintUnsupported,
+    Status(u16),
+    Document(Sip008DocumentError),
+    AccessKey(OutlineKeyError),
+    Subscription(SubscriptionError),
+}
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for FetchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FetchError::Request(e) => Some(e),
+            FetchError::Redirect(e) => Some(e),
+            FetchError::Document(e) => Some(e),
+            FetchError::AccessKey(e) => Some(e),
+            FetchError::Subscription(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl From<reqwest::Error> for FetchError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            return FetchError::Timeout;
+        }
+        let mut source = err.source();
+        while let Some(e) = source {
+            if let Some(redirect) = e.downcast_ref::<RedirectError>() {
+                return FetchError::Redirect(*redirect);
+            }
+            #[cfg(feature = "rustls")]
+            if is_pin_mismatch(e) {
+                return FetchError::FingerprintMismatch;
+            }
+            source = e.source();
+        }
+        FetchError::Request(err)
+    }
+}
+
+impl Default for Sip008ClientBuilder {
+    fn default() -> Self {
+        Self {
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            headers: HeaderMap::new(),
+            user_agent: None,
+            connect_timeout: Some(DEFAULT_CONNECT_TIMEOUT),
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            request_body: Vec::new(),
+        }
+    }
+}
+
+impl Sip008ClientBuilder {
+    /// maximum number of redirects followed before giving up, zero disables redirects
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+    /// adds a header sent with every request, e.g. a provider access token
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+    /// overrides the `User-Agent` header, an invalid value fails the fetch with [`FetchError::Request`]
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+    /// timeout for establishing the connection, `None` waits forever
+    pub fn connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+    /// timeout for each read of the response, `None` waits forever
+    pub fn read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+    /// maximum accepted size of the response body in bytes
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+    /// body sent when the ssconf uri asks for a method other than GET, empty by default
+    pub fn request_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.request_body = body.into();
+        self
+    }
+    pub fn build(self) -> Sip008Client {
+        Sip008Client { settings: self }
+    }
+}
+
+impl Default for Sip008Client {
+    fn default() -> Self {
+        Sip008ClientBuilder::default().build()
+    }
+}
+
+impl Sip008Client {
+    pub fn builder() -> Sip008ClientBuilder {
+        Sip008ClientBuilder::default()
+    }
+    /// fetches and parses the document at `config.location`
+    ///
+    /// the request uses `config.http_method` when present, GET otherwise.
+    /// redirects are followed up to the configured limit, https to http downgrades are refused.
+    /// when the config pins a certificate fingerprint the certificate chain is not validated
+    /// against the system roots, instead the certificate of every host connected to, redirect
+    /// targets included, must match the pin during the handshake, before anything is sent.
+    /// pins need the `rustls` backend and an https location.
+    pub async fn fetch(&self, config: &SIP008Config) -> Result<FetchResult, FetchError> {
+        self.fetch_raw(config).await?.try_map(|body| {
+            let body = std::str::from_utf8(&body)
+                .map_err(|_| FetchError::Document(Sip008DocumentError::InvalidJson))?;
+            Sip008Document::parse(body).map_err(FetchError::Document)
+        })
+    }
+    /// resolves an outline dynamic access key, the response may either be a single ss:// uri
+    /// or outline's json server config, see [`parse_outline_dynamic_key`]
+    pub async fn resolve_outline_key(
+        &self,
+        config: &SIP008Config,
+    ) -> Result<FetchResult<SSConfig>, FetchError> {
+        self.fetch_raw(config).await?.try_map(|body| {
+            let body = std::str::from_utf8(&body)
+                .map_err(|_| FetchError::AccessKey(OutlineKeyError::InvalidJson))?;
+            parse_outline_dynamic_key(body).map_err(FetchError::AccessKey)
+        })
+    }
+    /// fetches a subscription in any format [`Subscription::parse`] detects, plain https
+    /// subscription urls can be wrapped as `SIP008Config { location, .. }`
+    pub async fn fetch_subscription(
+        &self,
+        config: &SIP008Config,
+    ) -> Result<FetchResult<Subscription>, FetchError> {
+        self.fetch_raw(config)
+            .await?
+            .try_map(|body| Subscription::parse(&body).map_err(FetchError::Subscription))
+    }
+
+    /// the location is left out of the span, the path of an online config is its secret
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(host), err(level = "debug"))
+    )]
+    async fn fetch_raw(&self, config: &SIP008Config) -> Result<FetchResult<Vec<u8>>, FetchError> {
+        let url = Url::parse(&config.location).map_err(|_| FetchError::InvalidLocation)?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("host", url.host_str());
+        let fingerprint = config.cert_finger_print.as_deref();
+        if fingerprint.is_some() && url.scheme() != "https" {
+            return Err(FetchError::FingerprintMismatch);
+        }
+        let client = self.http_client(fingerprint)?;
+
+        let method = match config.http_method.as_deref() {
+            Some(method) => reqwest::Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                .map_err(|_| FetchError::InvalidHttpMethod)?,
+            None => reqwest::Method::GET,
+        };
+        let request = match method {
+            reqwest::Method::GET | reqwest::Method::HEAD => client.request(method, url),
+            method => client
+                .request(method, url)
+                .body(self.settings.request_body.clone()),
+        };
+        let fetched_at = SystemTime::now();
+        let started = Instant::now();
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(FetchError::Status(status.as_u16()));
+        }
+        let headers = response.headers();
+        let etag = headers
+            .get(ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let retry_after = headers
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(RetryAfter::parse);
+        let subscription_info = headers
+            .get("subscription-userinfo")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| SubscriptionInfo::parse(v).ok());
+
+        let body = self.read_body(response).await?;
+        let response_time = started.elapsed();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            status = status.as_u16(),
+            bytes = body.len(),
+            ?response_time,
+            "fetched online config"
+        );
+        Ok(FetchResult {
+            document: body,
+            status: status.as_u16(),
+            etag,
+            retry_after,
+            subscription_info,
+            response_time,
+            fetched_at,
+        })
+    }
+
+    async fn read_body(&self, mut response: Response) -> Result<Vec<u8>, FetchError> {
+        let max_body_size = self.settings.max_body_size;
+        if response
+            .content_length()
+            .is_some_and(|length| length > max_body_size as u64)
+        {
+            return Err(FetchError::BodyTooLarge);
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > max_body_size {
+                return Err(FetchError::BodyTooLarge);
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+
+    fn http_client(&self, fingerprint: Option<&str>) -> Result<reqwest::Client, FetchError> {
+        let settings = &self.settings;
+        let max_redirects = settings.max_redirects;
+        let policy = redirect::Policy::custom(move |attempt| {
+            match check_redirect(attempt.url(), attempt.previous(), max_redirects) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e),
+            }
+        });
+        let builder = reqwest::Client::builder()
+            .redirect(policy)
+            .default_headers(settings.headers.clone());
+        let builder = match &settings.user_agent {
+            Some(user_agent) => builder.user_agent(user_agent.as_str()),
+            None => builder,
+        };
+        let builder = match settings.connect_timeout {
+            Some(timeout) => builder.connect_timeout(timeout),
+            None => builder,
+        };
+        let builder = match settings.read_timeout {
+            Some(timeout) => builder.read_timeout(timeout),
+            None => builder,
+        };
+        // rustls wins when both backends are compiled in
+        #[cfg(feature = "rustls")]
+        let builder = match fingerprint {
+            Some(fingerprint) => builder.tls_backend_preconfigured(pinned_tls_config(fingerprint)),
+            None => builder.tls_backend_rustls(),
+        };
+        #[cfg(all(feature = "native-tls", not(feature = "rustls")))]
+        let builder = match fingerprint {
+            Some(_) => return Err(FetchError::FingerprintUnsupported),
+            None => builder.tls_backend_native(),
+        };
+        Ok(builder.build()?)
+    }
+}
+
+/// accepts exactly the certificate matching the pin, the chain and the host name are not
+/// checked but the peer still has to prove it holds the certificate's key
+#[cfg(feature = "rustls")]
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    fingerprint: String,
+    provider: std::sync::Arc<rustls::crypto::CryptoProvider>,
+}
+
+#[cfg(feature = "rustls")]
+impl rustls::client::danger::ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        if fingerprint_matches(end_entity, &self.fingerprint) {
+            Ok(rustls::client::danger::ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// client config checking every handshake, redirects included, against the pin
+#[cfg(feature = "rustls")]
+fn pinned_tls_config(fingerprint: &str) -> rustls::ClientConfig {
+    let provider = std::sync::Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+    let verifier = PinnedCertVerifier {
+        fingerprint: fingerprint.to_string(),
+        provider: provider.clone(),
+    };
+    let mut config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .expect("the default provider supports the default protocol versions")
+        .dangerous()
+        .with_custom_certificate_verifier(std::sync::Arc::new(verifier))
+        .with_no_client_auth();
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    config
+}
+
+/// whether a handshake failed on the pin, rustls errors arrive wrapped in io errors whose
+/// `source` skips them
+#[cfg(feature = "rustls")]
+fn is_pin_mismatch(mut err: &(dyn Error + 'static)) -> bool {
+    while let Some(inner) = err
+        .downcast_ref::<std::io::Error>()
+        .and_then(std::io::Error::get_ref)
+    {
+        err = inner;
+    }
+    matches!(
+        err.downcast_ref::<rustls::Error>(),
+        Some(rustls::Error::InvalidCertificate(
+            rustls::CertificateError::ApplicationVerificationFailure
+        ))
+    )
+}
+
+fn check_redirect(next: &Url, previous: &[Url], max_redirects: usize) -> Result<(), RedirectError> {
+    if previous.len() > max_redirects {
+        return Err(RedirectError::TooManyRedirects);
+    }
+    if next.scheme() != "https" && previous.iter().any(|url| url.scheme() == "https") {
+        return Err(RedirectError::Downgrade);
+    }
+    Ok(())
+}
+
+/// compares the sha256 of a DER certificate with a hex fingerprint, colons and case are ignored
+#[cfg(feature = "rustls")]
+fn fingerprint_matches(certificate: &[u8], fingerprint: &str) -> bool {
+    let expected = fingerprint
+        .chars()
+        .filter(|c| *c != ':')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let actual = Sha256::digest(certificate)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    expected == actual
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::SubscriptionSource;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    const EMPTY_DOCUMENT: &str = r#"{"version": 1, "servers": []}"#;
+
+    fn urls(input: &[&str]) -> Vec<Url> {
+        input.iter().map(|u| Url::parse(u).unwrap()).collect()
+    }
+
+    #[test]
+    fn follows_redirects_up_to_the_limit() {
+        let next = Url::parse("https://b.example.com/").unwrap();
+        let previous = urls(&["https://a.example.com/"]);
+        assert_eq!(check_redirect(&next, &previous, 1), Ok(()));
+        assert_eq!(
+            check_redirect(&next, &previous, 0),
+            Err(RedirectError::TooManyRedirects)
+        );
+    }
+
+    #[test]
+    fn refuses_https_to_http_downgrades() {
+        let next = Url::parse("http://b.example.com/").unwrap();
+        let previous = urls(&["https://a.example.com/", "https://c.example.com/"]);
+        assert_eq!(
+            check_redirect(&next, &previous, 10),
+            Err(RedirectError::Downgrade)
+        );
+    }
+
+    async fn read_request(stream: &mut TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buf = [0; 4096];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap_or(0);
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request).into_owned();
+            let complete = text.split_once("\r\n\r\n").map(|(head, body)| {
+                let length = head
+                    .lines()
+                    .find_map(|l| {
+                        l.to_ascii_lowercase()
+                            .strip_prefix("content-length: ")
+                            .map(str::to_string)
+                    })
+                    .and_then(|l| l.parse::<usize>().ok())
+                    .unwrap_or(0);
+                body.len() >= length
+            });
+            if n == 0 || complete == Some(true) {
+                return text;
+            }
+        }
+    }
+
+    /// serves `response` to every connection and reports the received requests
+    async fn serve(response: impl Into<Vec<u8>>) -> (SIP008Config, UnboundedReceiver<String>) {
+        serve_sequence(vec![response.into()]).await
+    }
+
+    /// serves the responses in order, repeating the last one once they run out
+    pub(crate) async fn serve_sequence(
+        responses: Vec<Vec<u8>>,
+    ) -> (SIP008Config, UnboundedReceiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let location = format!("http://{}/", listener.local_addr().unwrap());
+        let (requests, received) = unbounded_channel();
+        tokio::spawn(async move {
+            let mut index = 0;
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let _ = requests.send(read_request(&mut stream).await);
+                let _ = stream.write_all(&responses[index]).await;
+                index = (index + 1).min(responses.len() - 1);
+            }
+        });
+        let config = SIP008Config {
+            location,
+            cert_finger_print: None,
+            http_method: None,
+        };
+        (config, received)
+    }
+
+    /// a minimal http response carrying `document`
+    pub(crate) fn document_response(document: &str) -> Vec<u8> {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{document}",
+            document.len()
+        )
+        .into_bytes()
+    }
+
+    #[tokio::test]
+    async fn redirect_policy_errors_are_surfaced_as_redirect_errors() {
+        let (config, _) = serve(
+            b"HTTP/1.1 302 Found\r\nLocation: /\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        let client = Sip008Client::builder().max_redirects(2).build();
+        let err = client.fetch(&config).await.unwrap_err();
+        assert!(matches!(
+            err,
+            FetchError::Redirect(RedirectError::TooManyRedirects)
+        ));
+    }
+
+    #[tokio::test]
+    async fn sends_custom_headers_and_user_agent() {
+        let (config, mut requests) = serve(document_response(EMPTY_DOCUMENT)).await;
+        let client = Sip008Client::builder()
+            .header(
+                HeaderName::from_static("x-token"),
+                HeaderValue::from_static("secret"),
+            )
+            .user_agent("ClashForAndroid/2.5.12")
+            .build();
+        let result = client.fetch(&config).await.unwrap();
+        assert!(result.document.servers.is_empty());
+
+        let request = requests.recv().await.unwrap().to_ascii_lowercase();
+        assert!(request.contains("x-token: secret\r\n"));
+        assert!(request.contains("user-agent: clashforandroid/2.5.12\r\n"));
+    }
+
+    #[tokio::test]
+    async fn refuses_bodies_larger_than_the_limit() {
+        let (config, _) = serve(document_response(EMPTY_DOCUMENT)).await;
+        let client = Sip008Client::builder().max_body_size(16).build();
+        let err = client.fetch(&config).await.unwrap_err();
+        assert!(matches!(err, FetchError::BodyTooLarge));
+
+        let (config, _) =
+            serve(b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n{\"version\": 1, \"servers\": []}")
+                .await;
+        let err = client.fetch(&config).await.unwrap_err();
+        assert!(matches!(err, FetchError::BodyTooLarge));
+    }
+
+    #[tokio::test]
+    async fn uses_the_http_method_requested_by_the_uri() {
+        let (mut config, mut requests) = serve(document_response(EMPTY_DOCUMENT)).await;
+        config.http_method = Some("post".to_string());
+        let client = Sip008Client::builder().request_body("token=1").build();
+        client.fetch(&config).await.unwrap();
+
+        let request = requests.recv().await.unwrap();
+        assert!(request.starts_with("POST / HTTP/1.1\r\n"));
+        assert!(request.ends_with("\r\n\r\ntoken=1"));
+
+        config.http_method = Some("NOT A METHOD".to_string());
+        let err = client.fetch(&config).await.unwrap_err();
+        assert!(matches!(err, FetchError::InvalidHttpMethod));
+    }
+
+    #[tokio::test]
+    async fn reports_http_metadata() {
+        let (config, _) = serve(
+            b"HTTP/1.1 200 OK\r\nETag: \"abc\"\r\nRetry-After: 120\r\nSubscription-Userinfo: upload=1; download=2; total=10\r\nContent-Length: 29\r\nConnection: close\r\n\r\n{\"version\": 1, \"servers\": []}",
+        )
+        .await;
+        let before = SystemTime::now();
+        let result = Sip008Client::default().fetch(&config).await.unwrap();
+        assert_eq!(result.status, 200);
+        assert_eq!(result.etag, Some("\"abc\"".to_string()));
+        assert_eq!(
+            result.retry_after,
+            Some(RetryAfter::Delay(Duration::from_secs(120)))
+        );
+        assert!(result.fetched_at >= before);
+        assert_eq!(result.subscription_info.unwrap().remaining(), Some(7));
+    }
+
+    #[test]
+    fn parses_retry_after_dates() {
+        assert_eq!(
+            RetryAfter::parse("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(RetryAfter::At(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(1445412480)
+            ))
+        );
+        assert_eq!(RetryAfter::parse("soon"), None);
+    }
+
+    #[tokio::test]
+    async fn resolves_outline_dynamic_keys() {
+        let (config, _) = serve(document_response(
+            r#"{"server": "example.com", "server_port": 443, "password": "test", "method": "chacha20-ietf-poly1305"}"#,
+        ))
+        .await;
+        let result = Sip008Client::default()
+            .resolve_outline_key(&config)
+            .await
+            .unwrap();
+        assert_eq!(result.document.port, 443);
+
+        let (config, _) = serve(document_response(
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888\n",
+        ))
+        .await;
+        let result = Sip008Client::default()
+            .resolve_outline_key(&config)
+            .await
+            .unwrap();
+        assert_eq!(result.document.port, 8888);
+    }
+
+    #[tokio::test]
+    async fn fetches_subscriptions_in_any_format() {
+        let body = base64::encode("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo\n");
+        let (config, _) = serve(document_response(&body)).await;
+        let result = Sip008Client::default()
+            .fetch_subscription(&config)
+            .await
+            .unwrap();
+        assert_eq!(result.document.source, SubscriptionSource::Base64Uris);
+        assert_eq!(result.document.configs[0].port, 8888);
+
+        let (config, _) = serve(document_response("<html></html>")).await;
+        let err = Sip008Client::default()
+            .fetch_subscription(&config)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            FetchError::Subscription(SubscriptionError::UnknownFormat)
+        ));
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_unresponsive_servers() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = SIP008Config {
+            location: format!("http://{}/", listener.local_addr().unwrap()),
+            cert_finger_print: None,
+            http_method: None,
+        };
+        let client = Sip008Client::builder()
+            .read_timeout(Some(Duration::from_millis(100)))
+            .build();
+        let err = client.fetch(&config).await.unwrap_err();
+        assert!(matches!(err, FetchError::Timeout));
+        drop(listener);
+    }
+
+    /// serves `response` over tls with a fresh self-signed certificate, reporting the requests
+    /// that made it through the handshake, returns the location and the certificate fingerprint
+    #[cfg(feature = "rustls")]
+    fn serve_tls(response: Vec<u8>) -> (String, String, std::sync::mpsc::Receiver<String>) {
+        use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+        use std::io::{Read, Write};
+        use std::sync::Arc;
+
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let certificate = certified.cert.der().clone();
+        let fingerprint = Sha256::digest(&certificate)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>();
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+            certified.signing_key.serialize_der(),
+        ));
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+        let config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![certificate], key)
+            .unwrap();
+        let config = Arc::new(config);
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let location = format!("https://{}/", listener.local_addr().unwrap());
+        let (requests, received) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let connection = rustls::ServerConnection::new(config.clone()).unwrap();
+                let mut stream = rustls::StreamOwned::new(connection, stream.unwrap());
+                let mut buf = [0; 4096];
+                if let Ok(n @ 1..) = stream.read(&mut buf) {
+                    let _ = requests.send(String::from_utf8_lossy(&buf[..n]).into_owned());
+                    let _ = stream.write_all(&response);
+                    stream.conn.send_close_notify();
+                    let _ = stream.flush();
+                }
+            }
+        });
+        (location, fingerprint, received)
+    }
+
+    #[cfg(feature = "rustls")]
+    #[tokio::test]
+    async fn checks_pins_during_the_handshake() {
+        let (location, fingerprint, requests) = serve_tls(document_response(EMPTY_DOCUMENT));
+        let mut config = SIP008Config {
+            location,
+            cert_finger_print: Some(fingerprint.to_ascii_uppercase()),
+            http_method: Some("POST".to_string()),
+        };
+        let client = Sip008Client::builder().request_body("token=1").build();
+        client.fetch(&config).await.unwrap();
+        assert!(requests.recv().unwrap().ends_with("token=1"));
+
+        config.cert_finger_print = Some("00".repeat(32));
+        let err = client.fetch(&config).await.unwrap_err();
+        assert!(matches!(err, FetchError::FingerprintMismatch), "{err:?}");
+        assert!(requests.try_recv().is_err());
+
+        config.location = config.location.replacen("https", "http", 1);
+        let err = client.fetch(&config).await.unwrap_err();
+        assert!(matches!(err, FetchError::FingerprintMismatch), "{err:?}");
+    }
+
+    #[cfg(feature = "rustls")]
+    #[tokio::test]
+    async fn checks_pins_on_redirect_targets() {
+        let (target, _, target_requests) = serve_tls(document_response(EMPTY_DOCUMENT));
+        let redirect = format!(
+            "HTTP/1.1 302 Found\r\nLocation: {target}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        );
+        let (location, fingerprint, requests) = serve_tls(redirect.into_bytes());
+        let config = SIP008Config {
+            location,
+            cert_finger_print: Some(fingerprint),
+            http_method: None,
+        };
+        let err = Sip008Client::default().fetch(&config).await.unwrap_err();
+        assert!(matches!(err, FetchError::FingerprintMismatch), "{err:?}");
+        assert!(requests.recv().is_ok());
+        assert!(target_requests.try_recv().is_err());
+    }
+
+    #[cfg(all(feature = "native-tls", not(feature = "rustls")))]
+    #[tokio::test]
+    async fn refuses_pins_without_rustls() {
+        let config = SIP008Config {
+            location: "https://127.0.0.1:1/".to_string(),
+            cert_finger_print: Some("00".repeat(32)),
+            http_method: None,
+        };
+        let err = Sip008Client::default().fetch(&config).await.unwrap_err();
+        assert!(matches!(err, FetchError::FingerprintUnsupported));
+    }
+
+    #[cfg(feature = "rustls")]
+    #[test]
+    fn compares_certificate_fingerprints() {
+        let certificate = b"certificate";
+        let fingerprint = Sha256::digest(certificate)
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect::<Vec<_>>()
+            .join(":");
+        assert!(fingerprint_matches(certificate, &fingerprint));
+        assert!(!fingerprint_matches(b"other", &fingerprint));
+    }
+}
@@ -0,0 +1,329 @@
+//! C ABI over the parser, the matching header is `include/ss_uri.h`
+//!
+//! ownership rules:
+//! - input strings are borrowed for the duration of the call and must be nul terminated utf-8
+//! - a config returned by [`ss_uri_parse`] is owned by the caller and freed with [`ss_uri_free`]
+//! - every `char *` returned by the library is owned by the caller and freed with
+//!   [`ss_uri_string_free`], except the static messages of [`ss_uri_status_message`]
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use crate::{Compat, SSConfig, SSParseError};
+
+/// result of the fallible calls, zero on success
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SsUriStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidUtf8 = 2,
+    InvalidUrl = 3,
+    InvalidProtocol = 4,
+    InvalidHost = 5,
+    InvalidPort = 6,
+    InvalidMethod = 7,
+    InvalidPassword = 8,
+    InvalidPrefix = 9,
+    InvalidTag = 10,
+    UnexpectedPath = 11,
+    UnexpectedCharacters = 12,
+    LimitExceeded = 13,
+    InvalidArgument = 14,
+}
+
+/// client presets of [`Compat`] as seen from C, the calls take them as a `uint32_t` so values
+/// outside the enum are rejected rather than undefined behavior
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SsUriCompat {
+    Spec = 0,
+    Outline = 1,
+    ShadowsocksAndroid = 2,
+    ShadowsocksWindows = 3,
+    ShadowsocksRust = 4,
+    Shadowrocket = 5,
+}
+
+impl From<SSParseError> for SsUriStatus {
+    fn from(err: SSParseError) -> Self {
+        match err {
+            SSParseError::InvalidUrl => SsUriStatus::InvalidUrl,
+            SSParseError::InvalidProtocol => SsUriStatus::InvalidProtocol,
+            SSParseError::InvalidHost => SsUriStatus::InvalidHost,
+            SSParseError::InvalidPort => SsUriStatus::InvalidPort,
+            SSParseError::InvalidMethod => SsUriStatus::InvalidMethod,
+            SSParseError::InvalidPassword => SsUriStatus::InvalidPassword,
+            SSParseError::InvalidPrefix => SsUriStatus::InvalidPrefix,
+            SSParseError::InvalidTag => SsUriStatus::InvalidTag,
+            SSParseError::UnexpectedPath => SsUriStatus::UnexpectedPath,
+            SSParseError::UnexpectedCharacters => SsUriStatus::UnexpectedCharacters,
+            SSParseError::LimitExceeded => SsUriStatus::LimitExceeded,
+        }
+    }
+}
+
+impl SsUriCompat {
+    fn from_raw(compat: u32) -> Option<Self> {
+        Some(match compat {
+            0 => SsUriCompat::Spec,
+            1 => SsUriCompat::Outline,
+            2 => SsUriCompat::ShadowsocksAndroid,
+            3 => SsUriCompat::ShadowsocksWindows,
+            4 => SsUriCompat::ShadowsocksRust,
+            5 => SsUriCompat::Shadowrocket,
+            _ => return None,
+        })
+    }
+}
+
+impl From<SsUriCompat> for Compat {
+    fn from(compat: SsUriCompat) -> Self {
+        match compat {
+            SsUriCompat::Spec => Compat::Spec,
+            SsUriCompat::Outline => Compat::Outline,
+            SsUriCompat::ShadowsocksAndroid => Compat::ShadowsocksAndroid,
+            SsUriCompat::ShadowsocksWindows => Compat::ShadowsocksWindows,
+            SsUriCompat::ShadowsocksRust => Compat::ShadowsocksRust,
+            SsUriCompat::Shadowrocket => Compat::Shadowrocket,
+        }
+    }
+}
+
+/// parses `uri` with the given client preset, one of [`SsUriCompat`], and stores the config in
+/// `out`, `out` is left untouched on failure
+///
+/// # Safety
+/// `uri` must be a valid nul terminated string and `out` a valid pointer to write to
+#[no_mangle]
+pub unsafe extern "C" fn ss_uri_parse(
+    uri: *const c_char,
+    compat: u32,
+    out: *mut *mut SSConfig,
+) -> SsUriStatus {
+    if uri.is_null() || out.is_null() {
+        return SsUriStatus::NullPointer;
+    }
+    let Some(compat) = SsUriCompat::from_raw(compat) else {
+        return SsUriStatus::InvalidArgument;
+    };
+    let Ok(uri) = CStr::from_ptr(uri).to_str() else {
+        return SsUriStatus::InvalidUtf8;
+    };
+    match SSConfig::parse_with(uri, compat.into()) {
+        Ok(config) => {
+            *out = Box::into_raw(Box::new(config));
+            SsUriStatus::Ok
+        }
+        Err(err) => err.into(),
+    }
+}
+
+/// frees a config returned by [`ss_uri_parse`], null is ignored
+///
+/// # Safety
+/// `config` must come from [`ss_uri_parse`] and must not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn ss_uri_free(config: *mut SSConfig) {
+    if !config.is_null() {
+        drop(Box::from_raw(config));
+    }
+}
+
+/// frees a string returned by the library, null is ignored
+///
+/// # Safety
+/// `s` must come from this library and must not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn ss_uri_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// static description of a status, never freed
+#[no_mangle]
+pub extern "C" fn ss_uri_status_message(status: SsUriStatus) -> *const c_char {
+    let message: &'static CStr = match status {
+        SsUriStatus::Ok => c"ok",
+        SsUriStatus::NullPointer => c"null pointer",
+        SsUriStatus::InvalidUtf8 => c"invalid utf-8",
+        SsUriStatus::InvalidUrl => c"invalid url",
+        SsUriStatus::InvalidProtocol => c"invalid protocol",
+        SsUriStatus::InvalidHost => c"invalid host",
+        SsUriStatus::InvalidPort => c"invalid port",
+        SsUriStatus::InvalidMethod => c"invalid method",
+        SsUriStatus::InvalidPassword => c"invalid password",
+        SsUriStatus::InvalidPrefix => c"invalid prefix",
+        SsUriStatus::InvalidTag => c"invalid tag",
+        SsUriStatus::UnexpectedPath => c"unexpected path",
+        SsUriStatus::UnexpectedCharacters => c"unexpected characters",
+        SsUriStatus::LimitExceeded => c"limit exceeded",
+        SsUriStatus::InvalidArgument => c"invalid argument",
+    };
+    message.as_ptr()
+}
+
+/// serializes the config as a sip002 uri the given client, one of [`SsUriCompat`], imports,
+/// null when `config` is null or `compat` is out of range
+///
+/// # Safety
+/// `config` must be null or come from [`ss_uri_parse`]
+#[no_mangle]
+pub unsafe extern "C" fn ss_uri_to_uri(config: *const SSConfig, compat: u32) -> *mut c_char {
+    let Some(compat) = SsUriCompat::from_raw(compat) else {
+        return ptr::null_mut();
+    };
+    with_config(config, |config| Some(config.to_uri(compat.into())))
+}
+
+/// serializes the config with [`SSConfig::to_sip002`], null when `config` is null
+///
+/// # Safety
+/// `config` must be null or come from [`ss_uri_parse`]
+#[no_mangle]
+pub unsafe extern "C" fn ss_uri_to_sip002(config: *const SSConfig) -> *mut c_char {
+    with_config(config, |config| Some(config.to_sip002()))
+}
+
+/// serializes the config with [`SSConfig::to_legacy_base64_encoded`], null when `config` is null
+///
+/// # Safety
+/// `config` must be null or come from [`ss_uri_parse`]
+#[no_mangle]
+pub unsafe extern "C" fn ss_uri_to_legacy_base64(config: *const SSConfig) -> *mut c_char {
+    with_config(config, |config| Some(config.to_legacy_base64_encoded()))
+}
+
+/// the method name, e.g. `aes-128-gcm`
+///
+/// # Safety
+/// `config` must be null or come from [`ss_uri_parse`]
+#[no_mangle]
+pub unsafe extern "C" fn ss_uri_method(config: *const SSConfig) -> *mut c_char {
+    with_config(config, |config| Some(config.method.as_str().to_string()))
+}
+
+/// the password, null when it contains a nul byte
+///
+/// # Safety
+/// `config` must be null or come from [`ss_uri_parse`]
+#[no_mangle]
+pub unsafe extern "C" fn ss_uri_password(config: *const SSConfig) -> *mut c_char {
+    with_config(config, |config| Some(config.password.clone()))
+}
+
+/// the host, ipv6 addresses in brackets
+///
+/// # Safety
+/// `config` must be null or come from [`ss_uri_parse`]
+#[no_mangle]
+pub unsafe extern "C" fn ss_uri_host(config: *const SSConfig) -> *mut c_char {
+    with_config(config, |config| Some(config.host.to_string()))
+}
+
+/// the port, zero when `config` is null
+///
+/// # Safety
+/// `config` must be null or come from [`ss_uri_parse`]
+#[no_mangle]
+pub unsafe extern "C" fn ss_uri_port(config: *const SSConfig) -> u16 {
+    config.as_ref().map_or(0, |config| config.port)
+}
+
+/// the tag, null when absent
+///
+/// # Safety
+/// `config` must be null or come from [`ss_uri_parse`]
+#[no_mangle]
+pub unsafe extern "C" fn ss_uri_tag(config: *const SSConfig) -> *mut c_char {
+    with_config(config, |config| config.tag.clone())
+}
+
+/// the raw `plugin` parameter, null when absent
+///
+/// # Safety
+/// `config` must be null or come from [`ss_uri_parse`]
+#[no_mangle]
+pub unsafe extern "C" fn ss_uri_plugin(config: *const SSConfig) -> *mut c_char {
+    with_config(config, |config| config.plugin().map(|p| p.to_string()))
+}
+
+unsafe fn with_config(
+    config: *const SSConfig,
+    f: impl FnOnce(&SSConfig) -> Option<String>,
+) -> *mut c_char {
+    config
+        .as_ref()
+        .and_then(f)
+        .and_then(|s| CString::new(s).ok())
+        .map_or(ptr::null_mut(), CString::into_raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(s: *mut c_char) -> Option<String> {
+        let value = (!s.is_null()).then(|| CStr::from_ptr(s).to_str().unwrap().to_string());
+        ss_uri_string_free(s);
+        value
+    }
+
+    #[test]
+    fn parses_and_serializes_through_the_c_abi() {
+        unsafe {
+            let mut config = ptr::null_mut();
+            let status = ss_uri_parse(
+                c"ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo%20Bar".as_ptr(),
+                SsUriCompat::Spec as u32,
+                &mut config,
+            );
+            assert_eq!(status, SsUriStatus::Ok);
+            assert_eq!(take(ss_uri_method(config)).as_deref(), Some("aes-128-gcm"));
+            assert_eq!(take(ss_uri_password(config)).as_deref(), Some("test"));
+            assert_eq!(take(ss_uri_host(config)).as_deref(), Some("192.168.100.1"));
+            assert_eq!(ss_uri_port(config), 8888);
+            assert_eq!(take(ss_uri_tag(config)).as_deref(), Some("Foo Bar"));
+            assert_eq!(take(ss_uri_plugin(config)), None);
+            assert_eq!(
+                take(ss_uri_to_uri(config, SsUriCompat::Outline as u32)).as_deref(),
+                Some("ss://YWVzLTEyOC1nY206dGVzdA==@192.168.100.1:8888/#Foo%20Bar")
+            );
+            ss_uri_free(config);
+        }
+    }
+
+    #[test]
+    fn reports_errors_as_status_codes() {
+        unsafe {
+            let mut config = ptr::null_mut();
+            assert_eq!(
+                ss_uri_parse(
+                    c"ss://Zm9vOnRlc3Q@192.168.100.1:8888".as_ptr(),
+                    SsUriCompat::Spec as u32,
+                    &mut config
+                ),
+                SsUriStatus::InvalidMethod
+            );
+            assert!(config.is_null());
+            assert_eq!(
+                ss_uri_parse(ptr::null(), SsUriCompat::Spec as u32, &mut config),
+                SsUriStatus::NullPointer
+            );
+            assert_eq!(
+                CStr::from_ptr(ss_uri_status_message(SsUriStatus::InvalidMethod)),
+                c"invalid method"
+            );
+            assert!(ss_uri_to_sip002(ptr::null()).is_null());
+            let uri = c"ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888";
+            assert_eq!(
+                ss_uri_parse(uri.as_ptr(), 6, &mut config),
+                SsUriStatus::InvalidArgument
+            );
+            assert!(config.is_null());
+            ss_uri_parse(uri.as_ptr(), SsUriCompat::Shadowrocket as u32, &mut config);
+            assert!(ss_uri_to_uri(config, u32::MAX).is_null());
+            ss_uri_free(config);
+        }
+    }
+}
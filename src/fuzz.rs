@@ -0,0 +1,128 @@
+//! `Arbitrary` implementations, generated configs survive `to_uri(Compat::Spec)` followed by
+//! `parse`
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+use url::Host;
+
+use crate::{
+    KnownParam, Method, Mode, OutlinePrefix, Plugin, QueryParams, SSConfig, OUTLINE_MAX_PREFIX_LEN,
+};
+
+const PLUGIN_NAMES: [&str; 5] = [
+    "obfs-local",
+    "simple-obfs",
+    "v2ray-plugin",
+    "xray-plugin",
+    "kcptun",
+];
+
+impl<'a> Arbitrary<'a> for Method {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        u.choose(&Method::ALL).copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for Mode {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        u.choose(&Mode::ALL).copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for Plugin {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let name = *u.choose(&PLUGIN_NAMES)?;
+        let options = Vec::<(String, Option<String>)>::arbitrary(u)?
+            .into_iter()
+            .filter(|(key, _)| !key.is_empty())
+            .collect::<Vec<_>>();
+        Ok(Plugin::from_options(
+            name,
+            options.iter().map(|(k, v)| (k.as_str(), v.as_deref())),
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for OutlinePrefix {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let len = u.int_in_range(0..=OUTLINE_MAX_PREFIX_LEN)?;
+        let bytes = u.bytes(len)?;
+        Ok(OutlinePrefix::new(bytes).expect("prefix length is in range"))
+    }
+}
+
+impl<'a> Arbitrary<'a> for SSConfig {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let host = match u.int_in_range(0..=2)? {
+            0 => Host::Ipv4(Ipv4Addr::from(u32::arbitrary(u)?)),
+            1 => Host::Ipv6(Ipv6Addr::from(u128::arbitrary(u)?)),
+            _ => Host::Domain(arbitrary_domain(u)?),
+        };
+        let mut extra = Vec::<(String, String)>::arbitrary(u)?
+            .into_iter()
+            .collect::<QueryParams>();
+        extra.retain(|key, _| {
+            !key.is_empty()
+                && key != "prefix"
+                && key != KnownParam::Mode.key()
+                && !KnownParam::Udp.aliases().contains(&key)
+        });
+        if let Some(plugin) = Option::<Plugin>::arbitrary(u)? {
+            extra.insert("plugin".to_string(), plugin.to_string());
+        }
+        Ok(SSConfig {
+            host,
+            port: u.int_in_range(1..=u16::MAX)?,
+            method: Method::arbitrary(u)?,
+            password: String::arbitrary(u)?,
+            tag: Option::<String>::arbitrary(u)?.filter(|t| !t.is_empty()),
+            tag_raw: None,
+            extra: (!extra.is_empty()).then_some(extra),
+            prefix: Option::<OutlinePrefix>::arbitrary(u)?,
+            mode: Option::<Mode>::arbitrary(u)?,
+        })
+    }
+}
+
+/// lowercase labels ending with a letter, so the domain never reads as an ipv4 address
+fn arbitrary_domain(u: &mut Unstructured) -> Result<String> {
+    const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+    const LABEL: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    let labels = u.int_in_range(1..=4)?;
+    let mut domain = Vec::new();
+    for _ in 0..labels {
+        let len = u.int_in_range(1..=12)?;
+        let label = (0..len)
+            .map(|_| u.choose(LABEL).map(|c| *c as char))
+            .collect::<Result<String>>()?;
+        domain.push(label);
+    }
+    domain.push((*u.choose(LETTERS)? as char).to_string());
+    Ok(domain.join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Rng;
+    use crate::Compat;
+
+    #[test]
+    fn arbitrary_configs_round_trip() {
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+        let mut data = vec![0u8; 512];
+        for _ in 0..2000 {
+            data.fill_with(|| rng.next_u64() as u8);
+            let config = SSConfig::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            let uri = config.to_uri(Compat::Spec);
+            assert_eq!(SSConfig::parse(&uri).as_ref(), Ok(&config), "{uri}");
+            let canonical = config.canonicalize();
+            let uri = canonical.to_uri(Compat::Spec);
+            assert_eq!(
+                SSConfig::parse(&uri).map(|c| c.canonicalize()),
+                Ok(canonical),
+                "{uri}"
+            );
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! country and network of servers from MaxMind GeoLite2 or GeoIP2 databases, for grouping
+//! servers by country and putting flags in front of their tags
+use core::fmt;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::Path;
+
+use maxminddb::{geoip2, MaxMindDbError, Reader};
+
+use crate::{entry, tag, SSConfig, ServerList};
+
+/// where an address is, what the databases don't know is `None`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GeoInfo {
+    pub ip: IpAddr,
+    /// ISO 3166 code, e.g. `JP`
+    pub country: Option<String>,
+    pub asn: Option<u32>,
+    pub as_org: Option<String>,
+}
+
+impl GeoInfo {
+    /// the flag emoji of the country
+    pub fn flag(&self) -> Option<String> {
+        self.country.as_deref().and_then(tag::flag_of)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeoIpError {
+    /// the host didn't resolve to any address
+    Resolve,
+    /// the database is corrupt or not of the expected kind
+    Database,
+}
+impl fmt::Display for GeoIpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for GeoIpError {}
+
+/// a country database, GeoLite2-Country or -City, and optionally an ASN database
+///
+/// domains are looked up with [`SSConfig::resolve`] and placed by their first address, which
+/// blocks
+/// ```no_run
+/// use ss_uri::{GeoIp, SSConfig, ServerList};
+/// let geoip = GeoIp::open("GeoLite2-Country.mmdb")
+///     .and_then(|geoip| geoip.with_asn_db("GeoLite2-ASN.mmdb"))
+///     .unwrap();
+/// let mut list = ["ss://YWVzLTEyOC1nY206dGVzdA@example.com:8388#Tokyo"]
+///     .into_iter()
+///     .map(|uri| SSConfig::parse(uri).unwrap())
+///     .collect::<ServerList>();
+/// list.tag_flags(&geoip);
+/// for (country, servers) in geoip.group_by_country(list) {
+///     println!("{}: {} servers", country.as_deref().unwrap_or("unknown"), servers.len());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct GeoIp {
+    country: Reader<Vec<u8>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoIp {
+    pub fn new(country: Reader<Vec<u8>>, asn: Option<Reader<Vec<u8>>>) -> Self {
+        GeoIp { country, asn }
+    }
+    /// reads the country database at `path` into memory
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, MaxMindDbError> {
+        Ok(Self::new(Reader::open_readfile(path)?, None))
+    }
+    /// reads the ASN database at `path` into memory
+    pub fn with_asn_db(mut self, path: impl AsRef<Path>) -> Result<Self, MaxMindDbError> {
+        self.asn = Some(Reader::open_readfile(path)?);
+        Ok(self)
+    }
+    pub fn lookup_ip(&self, ip: IpAddr) -> Result<GeoInfo, GeoIpError> {
+        let country = decode::<geoip2::Country>(&self.country, ip)?;
+        let asn = match &self.asn {
+            Some(reader) => decode::<geoip2::Asn>(reader, ip)?,
+            None => None,
+        };
+        Ok(GeoInfo {
+            ip,
+            country: country.and_then(|c| c.country.iso_code.map(str::to_string)),
+            asn: asn.as_ref().and_then(|a| a.autonomous_system_number),
+            as_org: asn.and_then(|a| a.autonomous_system_organization.map(str::to_string)),
+        })
+    }
+    pub fn lookup(&self, config: &SSConfig) -> Result<GeoInfo, GeoIpError> {
+        let addrs = config.resolve().map_err(|_| GeoIpError::Resolve)?;
+        let addr = addrs.first().ok_or(GeoIpError::Resolve)?;
+        self.lookup_ip(addr.ip())
+    }
+    /// [`GeoIp::lookup`] of every config, in the same order
+    pub fn annotate(&self, configs: &[SSConfig]) -> Vec<Result<GeoInfo, GeoIpError>> {
+        configs.iter().map(|config| self.lookup(config)).collect()
+    }
+    /// servers by the ISO code of their country, those that couldn't be placed under `None`
+    pub fn group_by_country(
+        &self,
+        configs: impl IntoIterator<Item = SSConfig>,
+    ) -> BTreeMap<Option<String>, ServerList> {
+        let mut groups = BTreeMap::<_, ServerList>::new();
+        for config in configs {
+            let country = self.lookup(&config).ok().and_then(|info| info.country);
+            groups.entry(country).or_default().push(config);
+        }
+        groups
+    }
+}
+
+fn decode<'de, T: serde::Deserialize<'de>>(
+    reader: &'de Reader<Vec<u8>>,
+    ip: IpAddr,
+) -> Result<Option<T>, GeoIpError> {
+    let result = reader.lookup(ip).map_err(|_| GeoIpError::Database)?;
+    result.decode().map_err(|_| GeoIpError::Database)
+}
+
+impl ServerList {
+    /// puts the flag of the country each server is in in front of its tag, tags that already
+    /// carry a flag and servers that couldn't be placed are left alone
+    pub fn tag_flags(&mut self, geoip: &GeoIp) {
+        for config in self.iter_mut() {
+            if config.tag.as_deref().and_then(tag::flag).is_some() {
+                continue;
+            }
+            if let Some(flag) = geoip.lookup(config).ok().and_then(|info| info.flag()) {
+                let tag = format!("{flag} {}", entry::name_or_endpoint(config));
+                *config = config.clone().with_tag(tag);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a database placing 128.0.0.0/1 in japan on AS64496, the record doubles as country and
+    /// asn data, the lower half has no data
+    fn database() -> Reader<Vec<u8>> {
+        // sizes from 29 on take a second byte
+        let string = |s: &str| match s.len() {
+            len @ ..29 => [&[0x40 | len as u8][..], s.as_bytes()].concat(),
+            len => [&[0x40 | 29, len as u8 - 29][..], s.as_bytes()].concat(),
+        };
+        let uint16 = |n: u8| vec![0xa1, n];
+        // one node of two 24 bit records, no data and the data section at offset 0
+        let mut db = vec![0, 0, 1, 0, 0, 17];
+        db.extend([0; 16]);
+        db.push(0xe3);
+        db.extend(string("country"));
+        db.push(0xe1);
+        db.extend(string("iso_code"));
+        db.extend(string("JP"));
+        db.extend(string("autonomous_system_number"));
+        db.extend([0xc2, 0xfb, 0xf0]);
+        db.extend(string("autonomous_system_organization"));
+        db.extend(string("Example"));
+        db.extend(b"\xab\xcd\xefMaxMind.com");
+        db.push(0xe9);
+        for (key, value) in [
+            ("node_count", vec![0xc1, 1]),
+            ("record_size", uint16(24)),
+            ("ip_version", uint16(4)),
+            ("database_type", string("Test")),
+            ("languages", vec![0x00, 0x04]),
+            ("binary_format_major_version", uint16(2)),
+            ("binary_format_minor_version", vec![0xa0]),
+            ("build_epoch", vec![0x00, 0x02]),
+            ("description", vec![0xe0]),
+        ] {
+            db.extend(string(key));
+            db.extend(value);
+        }
+        Reader::from_source(db).unwrap()
+    }
+
+    #[test]
+    fn flags_and_groups_servers_by_country() {
+        let geoip = GeoIp::new(database(), Some(database()));
+        let info = geoip.lookup_ip("192.0.2.1".parse().unwrap()).unwrap();
+        assert_eq!(info.country.as_deref(), Some("JP"));
+        assert_eq!(
+            (info.asn, info.as_org.as_deref()),
+            (Some(64496), Some("Example"))
+        );
+        let unknown = geoip.lookup_ip("10.0.0.1".parse().unwrap()).unwrap();
+        assert_eq!((unknown.country, unknown.asn), (None, None));
+
+        let mut list = [
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.0.2.1:8388#Tokyo",
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.0.2.2:8388",
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.0.2.3:8388#%F0%9F%87%AF%F0%9F%87%B5%20Osaka",
+            "ss://YWVzLTEyOC1nY206dGVzdA@10.0.0.1:8388#LAN",
+        ]
+        .into_iter()
+        .map(|uri| SSConfig::parse(uri).unwrap())
+        .collect::<ServerList>();
+        list.tag_flags(&geoip);
+        let tags = list.iter().map(|c| c.tag.as_deref().unwrap());
+        assert_eq!(
+            tags.collect::<Vec<_>>(),
+            ["🇯🇵 Tokyo", "🇯🇵 192.0.2.2:8388", "🇯🇵 Osaka", "LAN"]
+        );
+        let groups = geoip.group_by_country(list);
+        let sizes = groups.iter().map(|(k, v)| (k.as_deref(), v.len()));
+        assert_eq!(sizes.collect::<Vec<_>>(), [(None, 1), (Some("JP"), 3)]);
+    }
+}
@@ -0,0 +1,72 @@
+use core::fmt;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::SSConfig;
+
+/// everything but rfc 3986 unreserved characters, so passwords survive go's `url.Parse`
+const USER_INFO: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GostExportError {
+    /// gost node urls have no place for SIP003 plugins
+    UnsupportedPlugin,
+}
+impl fmt::Display for GostExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for GostExportError {}
+
+impl SSConfig {
+    /// converts the config to a gost node url with plain user info, as used by `gost -F`
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo").unwrap();
+    /// assert_eq!(config.to_gost_url().unwrap(), "ss://aes-128-gcm:test@192.168.100.1:8888");
+    /// ```
+    pub fn to_gost_url(&self) -> Result<String, GostExportError> {
+        if self.plugin().is_some() {
+            return Err(GostExportError::UnsupportedPlugin);
+        }
+        Ok(format!(
+            "ss://{}:{}@{}:{}",
+            self.method,
+            utf8_percent_encode(&self.password, USER_INFO),
+            Self::get_uri_formatted_host(&self.host),
+            self.port
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_special_characters_of_the_password() {
+        let mut config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888").unwrap();
+        config.password = "p@ss:w/rd%?#-._~ 密".to_string();
+        assert_eq!(
+            config.to_gost_url().unwrap(),
+            "ss://aes-128-gcm:p%40ss%3Aw%2Frd%25%3F%23-._~%20%E5%AF%86@[2001:db8::1]:8888"
+        );
+    }
+
+    #[test]
+    fn rejects_configs_with_plugins() {
+        let config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp",
+        )
+        .unwrap();
+        assert_eq!(
+            config.to_gost_url(),
+            Err(GostExportError::UnsupportedPlugin)
+        );
+    }
+}
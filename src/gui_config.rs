@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{entry, SSConfig, SSParseError};
+
+/// default `timeout` of shadowsocks-windows server entries, in seconds
+pub const GUI_CONFIG_DEFAULT_TIMEOUT: u64 = 5;
+
+/// the `gui-config.json` of shadowsocks-windows and v2rayN, client settings other than the
+/// servers are kept as raw json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuiConfig {
+    pub configs: Vec<GuiServer>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// a server entry of `gui-config.json`, missing strings are written back as empty strings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuiServer {
+    pub server: String,
+    pub server_port: u16,
+    pub password: String,
+    pub method: String,
+    #[serde(default)]
+    pub plugin: String,
+    #[serde(default)]
+    pub plugin_opts: String,
+    #[serde(default)]
+    pub plugin_args: String,
+    #[serde(default)]
+    pub remarks: String,
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_timeout() -> u64 {
+    GUI_CONFIG_DEFAULT_TIMEOUT
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GuiConfigParseError {
+    InvalidJson,
+}
+impl std::fmt::Display for GuiConfigParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for GuiConfigParseError {}
+
+impl GuiConfig {
+    pub fn parse(input: &str) -> Result<Self, GuiConfigParseError> {
+        serde_json::from_str(input).map_err(|_| GuiConfigParseError::InvalidJson)
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("gui config is always serializable")
+    }
+    /// builds a file containing only the given servers
+    /// ```
+    /// use ss_uri::{GuiConfig, SSConfig};
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo").unwrap();
+    /// let gui = GuiConfig::from_configs([&config]);
+    /// assert_eq!(gui.configs[0].remarks, "Foo");
+    /// assert_eq!(gui.to_configs().unwrap(), vec![config]);
+    /// ```
+    pub fn from_configs<'a, I>(configs: I) -> Self
+    where
+        I: IntoIterator<Item = &'a SSConfig>,
+    {
+        Self {
+            configs: configs.into_iter().map(GuiServer::from_config).collect(),
+            other: Map::new(),
+        }
+    }
+    pub fn to_configs(&self) -> Result<Vec<SSConfig>, SSParseError> {
+        self.configs.iter().map(GuiServer::to_config).collect()
+    }
+}
+
+impl GuiServer {
+    pub fn from_config(config: &SSConfig) -> Self {
+        let (plugin, plugin_opts) = entry::plugin_parts(config);
+        Self {
+            server: entry::host_to_string(&config.host),
+            server_port: config.port,
+            password: config.password.clone(),
+            method: config.method.to_string(),
+            plugin: plugin.unwrap_or_default(),
+            plugin_opts: plugin_opts.unwrap_or_default(),
+            plugin_args: String::new(),
+            remarks: config.tag.clone().unwrap_or_default(),
+            timeout: GUI_CONFIG_DEFAULT_TIMEOUT,
+        }
+    }
+    pub fn to_config(&self) -> Result<SSConfig, SSParseError> {
+        entry::build_config(
+            &self.server,
+            self.server_port,
+            &self.method,
+            &self.password,
+            Some(self.remarks.clone()).filter(|r| !r.is_empty()),
+            Some(self.plugin.as_str()),
+            Some(self.plugin_opts.as_str()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use url::Host;
+
+    const GUI_CONFIG: &str = r#"{
+        "configs": [
+            {
+                "server": "2001:db8::1",
+                "server_port": 8388,
+                "password": "barfoo!",
+                "method": "chacha20-ietf-poly1305",
+                "plugin": "obfs-local",
+                "plugin_opts": "obfs=http;obfs-host=www.bing.com",
+                "plugin_args": "",
+                "remarks": "Home",
+                "timeout": 5
+            },
+            {
+                "server": "example.com",
+                "server_port": 443,
+                "password": "p",
+                "method": "aes-256-gcm",
+                "plugin": "",
+                "plugin_opts": "",
+                "remarks": ""
+            }
+        ],
+        "strategy": null,
+        "index": 0,
+        "global": false,
+        "enabled": true,
+        "localPort": 1080
+    }"#;
+
+    #[test]
+    fn can_parse_a_gui_config() {
+        let gui = GuiConfig::parse(GUI_CONFIG).unwrap();
+        let configs = gui.to_configs().unwrap();
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].host, Host::parse("[2001:db8::1]").unwrap());
+        assert_eq!(configs[0].tag, Some("Home".to_string()));
+        assert_eq!(
+            configs[0].plugin().unwrap().to_string(),
+            "obfs-local;obfs=http;obfs-host=www.bing.com"
+        );
+        assert_eq!(configs[1].tag, None);
+        assert_eq!(configs[1].extra, None);
+        assert_eq!(gui.other["localPort"], 1080);
+    }
+
+    #[test]
+    fn gui_configs_round_trip() {
+        let gui = GuiConfig::parse(GUI_CONFIG).unwrap();
+        let mut converted = GuiConfig::from_configs(&gui.to_configs().unwrap());
+        converted.other = gui.other.clone();
+        assert_eq!(converted, gui);
+        assert_eq!(GuiConfig::parse(&gui.to_json()).unwrap(), gui);
+    }
+}
@@ -0,0 +1,78 @@
+use core::fmt;
+
+use crate::SSConfig;
+
+/// what a legacy uri would drop, it only carries the method, password, endpoint and tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LegacyExportError {
+    /// the SIP003 plugin and its options
+    UnsupportedPlugin,
+    /// extra query parameters other than the plugin
+    UnsupportedParams,
+    /// the outline prefix
+    UnsupportedPrefix,
+    /// the tcp/udp mode
+    UnsupportedMode,
+}
+impl fmt::Display for LegacyExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for LegacyExportError {}
+
+impl SSConfig {
+    /// [`SSConfig::to_legacy_base64_encoded`] that fails rather than quietly dropping what a
+    /// legacy uri can't hold
+    /// ```
+    /// use ss_uri::{LegacyExportError, SSConfig};
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo").unwrap();
+    /// assert_eq!(
+    ///     config.to_legacy_checked().unwrap(),
+    ///     "ss://YWVzLTEyOC1nY206dGVzdEAxOTIuMTY4LjEwMC4xOjg4ODg#Foo"
+    /// );
+    /// let config = SSConfig::parse(
+    ///     "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(config.to_legacy_checked(), Err(LegacyExportError::UnsupportedPlugin));
+    /// ```
+    pub fn to_legacy_checked(&self) -> Result<String, LegacyExportError> {
+        if self.plugin().is_some() {
+            return Err(LegacyExportError::UnsupportedPlugin);
+        }
+        let extra = self.extra.iter().flatten();
+        if extra
+            .into_iter()
+            .any(|(k, v)| k != "plugin" || !v.is_empty())
+        {
+            return Err(LegacyExportError::UnsupportedParams);
+        }
+        if self.prefix.is_some() {
+            return Err(LegacyExportError::UnsupportedPrefix);
+        }
+        if self.mode.is_some() {
+            return Err(LegacyExportError::UnsupportedMode);
+        }
+        Ok(self.to_legacy_base64_encoded())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_what_would_be_dropped() {
+        let base = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/";
+        for (query, expected) in [
+            ("?plugin=", None),
+            ("?group=Zm9v", Some(LegacyExportError::UnsupportedParams)),
+            ("?prefix=%16%03", Some(LegacyExportError::UnsupportedPrefix)),
+            ("?mode=udp_only", Some(LegacyExportError::UnsupportedMode)),
+        ] {
+            let config = SSConfig::parse(&format!("{base}{query}")).unwrap();
+            assert_eq!(config.to_legacy_checked().err(), expected, "{query}");
+        }
+    }
+}
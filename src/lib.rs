@@ -1,24 +1,272 @@
 use core::fmt;
-use percent_encoding::{percent_decode_str, NON_ALPHANUMERIC};
-use std::collections::HashMap;
+#[cfg(any(feature = "qr", feature = "qr-decode"))]
+pub use image;
+#[cfg(feature = "maxmind")]
+pub use maxminddb;
+use percent_encoding::{percent_decode_str, percent_encode, AsciiSet, NON_ALPHANUMERIC};
+#[cfg(feature = "qr")]
+pub use qrcode;
+#[cfg(feature = "regex")]
+pub use regex;
+#[cfg(feature = "__fetch")]
+pub use reqwest;
+#[cfg(feature = "schemars")]
+pub use schemars;
+#[cfg(feature = "shadowsocks")]
+pub use shadowsocks;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::net::SocketAddr;
 pub use url;
 use url::{Host, Url};
+mod audit;
+mod borrowed;
+mod canonical;
+#[cfg(feature = "clash")]
+mod clash;
+#[cfg(feature = "clap")]
+mod cli;
+mod compat;
+pub mod conformance;
+mod diff;
+mod display;
+mod entry;
+#[cfg(feature = "__fetch")]
+mod fetch;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+#[cfg(feature = "maxmind")]
+mod geoip;
+mod gost;
+mod gui_config;
+mod legacy;
+mod libev;
+mod lint;
+mod list;
+#[cfg(feature = "lite")]
+mod lite;
+mod loon;
+mod merge;
 mod method;
+mod mode;
+mod multiport;
+mod nekobox;
+#[cfg(feature = "node")]
+pub mod node;
+mod ooc;
+mod options;
+mod outline;
+#[cfg(feature = "rayon")]
+mod par;
+mod params;
+mod plugin;
+#[cfg(feature = "probe")]
+mod probe;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "qr")]
+mod qr;
+#[cfg(feature = "qr-decode")]
+mod qr_decode;
+mod quantumult_x;
+mod query;
+#[cfg(feature = "rand")]
+mod random;
+#[cfg(feature = "resolve")]
+mod resolve;
+#[cfg(feature = "schemars")]
+mod schema;
+mod shadowrocket;
+#[cfg(feature = "shadowsocks")]
+mod shadowsocks_rust;
 mod sip008;
+#[cfg(feature = "sqlx")]
+mod sql;
+mod sslocal;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+mod strength;
+mod subscription;
+mod subscription_info;
+mod surge;
+pub mod tag;
+#[cfg(feature = "regex")]
+mod tag_filter;
+#[cfg(test)]
+pub(crate) mod test_util;
+mod validate;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+mod wasm_fetch;
+#[cfg(feature = "tokio")]
+mod watcher;
+mod xray;
 
-pub use method::{Method, MethodParseError};
+pub use audit::{audit, AuditEntry, AuditReport, Upgrade};
+pub use borrowed::SSConfigRef;
+pub use canonical::equivalent;
+#[cfg(feature = "clash")]
+pub use clash::{parse_clash_proxies, ClashError, ClashHealthCheck, ClashProvider, ClashProxy};
+#[cfg(feature = "clap")]
+pub use cli::{SIP008ConfigValueParser, SSConfigValueParser};
+pub use compat::Compat;
+pub use diff::{diff, ServerChange};
+pub use display::{LegacyDisplay, Sip002Display};
+#[cfg(feature = "__fetch")]
+pub use fetch::*;
+#[cfg(feature = "maxmind")]
+pub use geoip::{GeoInfo, GeoIp, GeoIpError};
+pub use gost::GostExportError;
+pub use gui_config::{GuiConfig, GuiConfigParseError, GuiServer, GUI_CONFIG_DEFAULT_TIMEOUT};
+pub use legacy::LegacyExportError;
+pub use libev::{LibevConfig, LibevParseError};
+pub use lint::{Lint, Severity, KNOWN_PLUGINS, LINT_MAX_TAG_LEN, LINT_MIN_PASSWORD_LEN};
+pub use list::ServerList;
+pub use loon::LoonError;
+pub use merge::{merge, DedupStrategy, MergePolicy, MergeSource};
+pub use method::{Method, MethodParseError, METHOD_TABLE};
+pub use mode::Mode;
+pub use multiport::{group_ports, MultiPortServer};
+pub use nekobox::{NekoboxProfile, NekoboxShadowsocksBean, NEKOBOX_SHADOWSOCKS};
+pub use ooc::*;
+pub use options::{
+    Base64Alphabet, Base64Variant, Limits, ParseOptions, SerializeOptions, Unexpected,
+};
+pub use outline::{
+    parse_outline_dynamic_key, OutlineAccessKey, OutlineAccessKeys, OutlineKeyError, OutlinePrefix,
+    OutlineServerConfig, OUTLINE_DOCUMENTED_PREFIXES, OUTLINE_MAX_PREFIX_LEN,
+};
+#[cfg(feature = "rayon")]
+pub use par::parse_many_par;
+pub use params::KnownParam;
+pub use plugin::Plugin;
+#[cfg(feature = "probe")]
+pub use probe::{ProbeError, TcpProbe};
+#[cfg(feature = "qr-decode")]
+pub use qr_decode::QrDecodeError;
+pub use quantumult_x::QuantumultXError;
+pub use query::{QueryOrder, QueryParams};
+#[cfg(feature = "resolve")]
+pub use resolve::Resolver;
+#[cfg(feature = "shadowsocks")]
+pub use shadowsocks_rust::ShadowsocksConvertError;
 pub use sip008::*;
+pub use strength::{password_entropy, Strength};
+pub use subscription::{Subscription, SubscriptionError, SubscriptionSource};
+pub use subscription_info::{SubscriptionInfo, SubscriptionInfoParseError};
+pub use surge::SurgeError;
+#[cfg(feature = "regex")]
+pub use tag_filter::{FilteredServers, TagFilter};
+pub use validate::ValidationError;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use wasm_fetch::*;
+#[cfg(feature = "tokio")]
+pub use watcher::Sip008Watcher;
+pub use xray::{XrayExportError, XrayOutbound, XrayServer, XraySettings, XRAY_SHADOWSOCKS};
+
+/// what a tag is percent-encoded with, everything but the characters rfc 3986 allows in a
+/// fragment, `+` is escaped too as form encoding reads it as a space
+const FRAGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'!')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')')
+    .remove(b'*')
+    .remove(b',')
+    .remove(b';')
+    .remove(b'=')
+    .remove(b':')
+    .remove(b'@')
+    .remove(b'/')
+    .remove(b'?');
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// configs compare and hash equal regardless of `tag_raw`, it only shapes serialization, for
+/// deduplicating servers that differ only in their tag or extras see [`SSConfig::same_server`]
+#[derive(Clone, Debug)]
 pub struct SSConfig {
+    /// parsed domains are kept in their ascii form, internationalized ones as punycode, see
+    /// [`SSConfig::host_unicode`]
     pub host: Host,
     pub port: u16,
     pub method: Method,
     pub password: String,
     pub tag: Option<String>,
-    pub extra: Option<HashMap<String, String>>,
+    /// the fragment exactly as it was written, serializers reuse it while it still decodes to
+    /// `tag` so opaque identifiers come back byte for byte
+    pub tag_raw: Option<String>,
+    pub extra: Option<QueryParams>,
+    /// outline's salt prefix carried in the `prefix` query parameter
+    pub prefix: Option<OutlinePrefix>,
+    /// whether the server relays udp, read from `mode` or the `udp` flags exporters write and
+    /// written back in the form [`Compat`] asks for
+    pub mode: Option<Mode>,
+}
+impl PartialEq for SSConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.host == other.host
+            && self.port == other.port
+            && self.method == other.method
+            && self.password == other.password
+            && self.tag == other.tag
+            && self.extra == other.extra
+            && self.prefix == other.prefix
+            && self.mode == other.mode
+    }
 }
-#[derive(Debug, PartialEq, Clone, Copy, Hash)]
+impl Eq for SSConfig {}
+/// sorts by tag, configs without one first, then host, port, method, password, extra
+/// parameters, prefix and mode, for listing servers in a stable order
+/// ```
+/// use ss_uri::SSConfig;
+/// let mut configs = [
+///     "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.2:8888#B",
+///     "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8889#A",
+///     "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#A",
+/// ]
+/// .map(|uri| SSConfig::parse(uri).unwrap());
+/// configs.sort();
+/// let ports = configs.iter().map(|c| c.port).collect::<Vec<_>>();
+/// assert_eq!(ports, [8888, 8889, 8888]);
+/// ```
+impl Ord for SSConfig {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.tag
+            .cmp(&other.tag)
+            .then_with(|| self.host.cmp(&other.host))
+            .then_with(|| self.port.cmp(&other.port))
+            .then_with(|| self.method.cmp(&other.method))
+            .then_with(|| self.password.cmp(&other.password))
+            .then_with(|| self.extra.cmp(&other.extra))
+            .then_with(|| self.prefix.cmp(&other.prefix))
+            .then_with(|| self.mode.cmp(&other.mode))
+    }
+}
+impl PartialOrd for SSConfig {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Hash for SSConfig {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.host.hash(state);
+        self.port.hash(state);
+        self.method.hash(state);
+        self.password.hash(state);
+        self.tag.hash(state);
+        self.extra.hash(state);
+        self.prefix.hash(state);
+        self.mode.hash(state);
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum SSParseError {
     InvalidUrl,
     InvalidProtocol,
@@ -26,6 +274,16 @@ pub enum SSParseError {
     InvalidPort,
     InvalidMethod,
     InvalidPassword,
+    InvalidPrefix,
+    /// the fragment isn't valid utf-8 once percent-decoded, only with
+    /// [`ParseOptions::strict_tag`]
+    InvalidTag,
+    /// a path after the port, only with [`ParseOptions::strict`]
+    UnexpectedPath,
+    /// characters that can't appear in an uri, only with [`ParseOptions::strict`]
+    UnexpectedCharacters,
+    /// the input is larger than [`ParseOptions::limits`] allow
+    LimitExceeded,
 }
 impl fmt::Display for SSParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -35,7 +293,100 @@ impl fmt::Display for SSParseError {
 impl std::error::Error for SSParseError {}
 
 impl SSConfig {
-    /// converts SSConfig to legacy base64 shadowsocks uri
+    /// the plugin from the `plugin` extra parameter
+    pub fn plugin(&self) -> Option<Plugin> {
+        self.extra
+            .as_ref()
+            .and_then(|e| e.get("plugin"))
+            .filter(|p| !p.is_empty())
+            .map(|p| Plugin::parse(p))
+    }
+    /// the config with another tag, the fragment it was parsed from is dropped
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo")
+    ///     .unwrap()
+    ///     .with_tag("HK 01")
+    ///     .with_port(443)
+    ///     .with_extra("group", "Asia");
+    /// assert_eq!(config.to_sip002(), "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:443/?group=Asia#HK%2001");
+    /// ```
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self.tag_raw = None;
+        self
+    }
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = password.into();
+        self
+    }
+    /// the config with the `plugin` parameter replaced, `None` removes it
+    pub fn with_plugin(self, plugin: Option<Plugin>) -> Self {
+        match plugin {
+            Some(plugin) => self.with_extra("plugin", plugin.to_string()),
+            None => {
+                let mut config = self;
+                if let Some(extra) = &mut config.extra {
+                    extra.remove("plugin");
+                }
+                config.extra = config.extra.filter(|extra| !extra.is_empty());
+                config
+            }
+        }
+    }
+    /// the config with the query parameter `key` set to `value`, replacing every earlier value,
+    /// outline's prefix lives in [`SSConfig::prefix`] rather than here
+    pub fn with_extra(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.extra
+            .get_or_insert_with(QueryParams::new)
+            .insert(key.into(), value.into());
+        self
+    }
+    /// the host with internationalized domains in punycode, the form clients connect to
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@例え.jp:8888").unwrap();
+    /// assert_eq!(config.host_ascii(), "xn--r8jz45g.jp");
+    /// assert_eq!(config.host_unicode(), "例え.jp");
+    /// ```
+    pub fn host_ascii(&self) -> String {
+        match &self.host {
+            Host::Domain(domain) => Some(url::quirks::domain_to_ascii(domain))
+                .filter(|ascii| !ascii.is_empty())
+                .unwrap_or_else(|| domain.clone()),
+            host => host.to_string(),
+        }
+    }
+    /// the address of the server when the host is an ip address, `None` for domains
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888").unwrap();
+    /// assert_eq!(config.socket_addr(), Some("[2001:db8::1]:8888".parse().unwrap()));
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8888").unwrap();
+    /// assert_eq!(config.socket_addr(), None);
+    /// ```
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        match self.host {
+            Host::Ipv4(ip) => Some(SocketAddr::from((ip, self.port))),
+            Host::Ipv6(ip) => Some(SocketAddr::from((ip, self.port))),
+            Host::Domain(_) => None,
+        }
+    }
+    /// the host with internationalized domains in their unicode form, for display
+    pub fn host_unicode(&self) -> String {
+        match &self.host {
+            Host::Domain(domain) => Some(url::quirks::domain_to_unicode(domain))
+                .filter(|unicode| !unicode.is_empty())
+                .unwrap_or_else(|| domain.clone()),
+            host => host.to_string(),
+        }
+    }
+    /// converts SSConfig to legacy base64 shadowsocks uri, the plugin and other parameters are
+    /// dropped, see [`SSConfig::to_legacy_checked`]
     /// ```
     /// use ss_uri::SSConfig;
     /// use ss_uri::Method;
@@ -46,7 +397,10 @@ impl SSConfig {
     ///     method: Method::BfCfb,
     ///     password: "test".to_string(),
     ///     tag: Some("Foo Bar".to_string()),
+    ///     tag_raw: None,
     ///     extra: None,
+    ///     prefix: None,
+    ///     mode: None,
     /// };
     /// assert_eq!(
     ///     config.to_legacy_base64_encoded(),
@@ -54,17 +408,43 @@ impl SSConfig {
     /// );
     /// ```
     pub fn to_legacy_base64_encoded(&self) -> String {
+        self.to_legacy_base64_with(&SerializeOptions::default())
+    }
+    /// [`SSConfig::to_legacy_base64_encoded`] with the given options, the query options don't
+    /// apply as legacy uris carry no query
+    /// ```
+    /// use ss_uri::{Base64Variant, SSConfig, SerializeOptions};
+    /// let config = SSConfig::parse("ss://YmYtY2ZiOnRlc3RAMTkyLjE2OC4xMDAuMTo4ODg4#Foo")
+    ///     .unwrap()
+    ///     .with_password("secret");
+    /// let options = SerializeOptions {
+    ///     base64: Some(Base64Variant { padded: true, ..Base64Variant::SIP002 }),
+    ///     ..SerializeOptions::default()
+    /// };
+    /// assert_eq!(
+    ///     config.to_legacy_base64_with(&options),
+    ///     "ss://YmYtY2ZiOnNlY3JldEAxOTIuMTY4LjEwMC4xOjg4ODg=#Foo"
+    /// );
+    /// ```
+    pub fn to_legacy_base64_with(&self, options: &SerializeOptions) -> String {
         let SSConfig {
             host,
             port,
             method,
             password,
             tag,
+            tag_raw,
             ..
         } = self;
-        let hash = Self::get_hash(tag);
-        let encoded = base64::encode(format!("{}:{password}@{host}:{port}", method));
-        let encoded = encoded.trim_end_matches('=');
+        let hash = if options.skip_tag {
+            String::new()
+        } else {
+            Self::get_hash(tag, tag_raw, options)
+        };
+        let encoded = options
+            .base64
+            .unwrap_or(Base64Variant::STANDARD_NO_PAD)
+            .encode(format!("{}:{password}@{host}:{port}", method));
 
         format!("ss://{encoded}{hash}")
     }
@@ -79,7 +459,10 @@ impl SSConfig {
     ///     method: Method::Aes128Gcm,
     ///     password: "test".to_string(),
     ///     tag: Some("Foo Bar".to_string()),
+    ///     tag_raw: None,
     ///     extra: None,
+    ///     prefix: None,
+    ///     mode: None,
     /// };
     /// assert_eq!(
     ///     config.to_sip002(),
@@ -87,28 +470,22 @@ impl SSConfig {
     /// );
     /// ```
     pub fn to_sip002(&self) -> String {
-        let SSConfig {
-            host,
-            port,
-            method,
-            password,
-            tag,
-            extra,
-        } = self;
-
-        let user_info = Self::encode_user_info(method, password);
-        let query = match extra {
-            Some(q) => Self::encode_query(q),
-            None => "".to_string(),
+        self.to_sip002_with(&SerializeOptions::default())
+    }
+    /// [`SSConfig::to_sip002`] with the given options, unless they say otherwise the user info
+    /// is written in the standard alphabet and a slash always follows the port
+    pub fn to_sip002_with(&self, options: &SerializeOptions) -> String {
+        let options = SerializeOptions {
+            base64: options.base64.or(Some(Base64Variant::STANDARD_NO_PAD)),
+            slash: options.slash.or(Some(true)),
+            ..options.clone()
         };
-
-        let hash = Self::get_hash(tag);
-
-        let host = Self::get_uri_formatted_host(host);
-        format!("ss://{user_info}@{host}:{port}/{query}{hash}")
+        self.to_uri_with(Compat::Spec, &options)
     }
     /// this is the method you should usually use for parsing shadowsocks uris
     /// parses an string into shadowsocks uri it supports both [sip002](https://shadowsocks.org/en/wiki/SIP002-URI-Scheme.html) and legacy mode if both were invalid returns sip002's error
+    ///
+    /// none of the parsers in this crate panic, whatever the input malformed data ends up as an error
     /// sip002 example:
     /// ```
     ///     use ss_uri::SSConfig;
@@ -138,26 +515,93 @@ impl SSConfig {
     /// assert_eq!(config.extra, None);
     /// ```
     pub fn parse(s: &str) -> Result<Self, SSParseError> {
-        let result = Self::parse_sip002(s);
-        if result.is_ok() {
-            return result;
-        }
-        let legacy_result = Self::parse_legacy_base64(s);
-        if legacy_result.is_ok() {
-            return legacy_result;
-        }
-        result
+        Self::parse_with_options(s, &ParseOptions::default())
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(len = s.len()), err(level = "debug"))
+    )]
     pub fn parse_sip002(s: &str) -> Result<Self, SSParseError> {
-        let s = &Self::remove_unsafe_padding(s);
+        let s = entry::trim_input(s);
+        Self::parse_sip002_unchecked(s).and_then(|c| ParseOptions::default().check(s, c))
+    }
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(len = s.len()), err(level = "debug"))
+    )]
+    pub fn parse_legacy_base64(s: &str) -> Result<Self, SSParseError> {
+        let s = entry::trim_input(s);
+        Self::parse_legacy_base64_unchecked(s).and_then(|c| ParseOptions::default().check(s, c))
+    }
+    /// the uri as it is written, before [`ParseOptions`] get a say
+    fn parse_sip002_unchecked(s: &str) -> Result<Self, SSParseError> {
+        let s = &entry::unescape_host_brackets(s);
+        #[cfg(feature = "lite")]
+        return Self::parse_sip002_lite(s);
+        #[cfg(not(feature = "lite"))]
+        Self::parse_sip002_url(s)
+    }
+    /// the payload is cut out of the raw string, in the host position of an url it would be
+    /// cut short by any `/` of the standard base64 alphabet
+    fn parse_legacy_base64_unchecked(s: &str) -> Result<Self, SSParseError> {
+        let (scheme, rest) = s.split_once("://").ok_or(SSParseError::InvalidUrl)?;
+        if !scheme
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(&b))
+        {
+            return Err(SSParseError::InvalidUrl);
+        }
+        if !scheme
+            .get(..2)
+            .is_some_and(|s| s.eq_ignore_ascii_case("ss"))
+        {
+            return Err(SSParseError::InvalidProtocol);
+        }
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (rest, None),
+        };
+        let encoded = rest
+            .split('?')
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches('/');
+        let encoded = percent_decode_str(encoded).decode_utf8_lossy();
+        let decoded = entry::decode_base64_lenient(&encoded).ok_or(SSParseError::InvalidUrl)?;
+        let (method, remaining) = decoded.split_once(':').ok_or(SSParseError::InvalidUrl)?;
+        let method: Method = method.parse().map_err(|_| SSParseError::InvalidMethod)?;
+        let (password, remaining) = remaining.rsplit_once('@').ok_or(SSParseError::InvalidUrl)?;
+        let (host, port) = remaining.rsplit_once(':').ok_or(SSParseError::InvalidUrl)?;
+        let port = port.parse().map_err(|_| SSParseError::InvalidPort)?;
+
+        Ok(Self {
+            host: Host::parse(host).map_err(|_| SSParseError::InvalidHost)?,
+            port,
+            method,
+            password: password.to_string(),
+            tag: Self::extract_hash(fragment),
+            tag_raw: fragment.map(str::to_string),
+            extra: None,
+            prefix: None,
+            mode: None,
+        })
+    }
+    #[cfg_attr(feature = "lite", allow(dead_code))]
+    fn parse_sip002_url(s: &str) -> Result<Self, SSParseError> {
+        let tag_raw = Self::extract_raw_hash(s);
 
         let url = url::Url::parse(s).map_err(|_| SSParseError::InvalidUrl)?;
         Self::validate_protocol(&url)?;
 
         let host = Self::extract_host(&url)?;
         let port = Self::extract_port(&url)?;
-        let query = Self::extract_query(&url);
+        let mut query = Self::extract_query(&url);
+        let prefix = query
+            .remove("prefix")
+            .map(|p| OutlinePrefix::from_param(&p))
+            .transpose()?;
+        let mode = Mode::take_from(&mut query);
         let (method, password) = Self::extract_method_and_password(url.username())?;
         let tag = Self::extract_hash(url.fragment());
 
@@ -167,40 +611,10 @@ impl SSConfig {
             method,
             password,
             tag,
+            tag_raw,
             extra: if query.is_empty() { None } else { Some(query) },
-        })
-    }
-    pub fn parse_legacy_base64(s: &str) -> Result<Self, SSParseError> {
-        let url = Url::parse(s).map_err(|_| SSParseError::InvalidUrl)?;
-        Self::validate_protocol(&url)?;
-
-        let encoded = url.host_str().ok_or(SSParseError::InvalidUrl)?;
-        let decoded = base64::decode(encoded).map_err(|_| SSParseError::InvalidUrl)?;
-        let decoded_str = String::from_utf8(decoded).map_err(|_| SSParseError::InvalidUrl)?;
-        let decoded_str = decoded_str.trim_end_matches('=');
-
-        let colon_index = decoded_str.find(':').ok_or(SSParseError::InvalidUrl)?;
-        let (method, remaining) = decoded_str.split_at(colon_index);
-        let method: Method = method.parse().map_err(|_| SSParseError::InvalidMethod)?;
-        let remaining = remaining.trim_start_matches(':');
-
-        let at_index = remaining.rfind('@').ok_or(SSParseError::InvalidUrl)?;
-        let (password, remaining) = remaining.split_at(at_index);
-        let remaining = remaining.trim_start_matches('@');
-
-        let (host, port) =
-            remaining.split_at(remaining.rfind(':').ok_or(SSParseError::InvalidUrl)?);
-        let port = port.trim_start_matches(':');
-        eprintln!("{:?}", port);
-        let port = port.parse().map_err(|_| SSParseError::InvalidPort)?;
-
-        Ok(Self {
-            host: Host::parse(host).map_err(|_| SSParseError::InvalidHost)?,
-            port,
-            method,
-            password: password.to_string(),
-            tag: Self::extract_hash(url.fragment()),
-            extra: None,
+            prefix,
+            mode,
         })
     }
     fn validate_protocol(url: &Url) -> Result<(), SSParseError> {
@@ -226,45 +640,29 @@ impl SSConfig {
         Ok(port)
     }
 
+    fn extract_raw_hash(s: &str) -> Option<String> {
+        s.split_once('#').map(|(_, fragment)| fragment.to_string())
+    }
+
     fn extract_hash(fragment: Option<&str>) -> Option<String> {
         fragment.map(|f| percent_decode_str(f).decode_utf8_lossy().to_string())
     }
 
-    fn extract_query(url: &url::Url) -> HashMap<String, String> {
+    fn extract_query(url: &url::Url) -> QueryParams {
         url.query_pairs()
             .map(|(a, b)| (a.to_string(), b.to_string()))
-            .collect::<HashMap<String, String>>()
+            .collect()
     }
 
+    /// some exporters percent-encode the `+`, `/` and `=` of the base64 user info
     fn extract_method_and_password(input: &str) -> Result<(Method, String), SSParseError> {
-        let encoded_part = base64::decode(input).map_err(|_| SSParseError::InvalidPassword)?;
-        let encoded_part =
-            String::from_utf8(encoded_part).map_err(|_| SSParseError::InvalidPassword)?;
-        let encoded_part = encoded_part.split(':').collect::<Vec<&str>>();
-        let method = encoded_part
-            .get(0)
-            .ok_or(SSParseError::InvalidMethod)?
-            .to_string();
+        let input = percent_decode_str(input).decode_utf8_lossy();
+        let decoded = entry::decode_base64_lenient(&input).ok_or(SSParseError::InvalidPassword)?;
+        let (method, password) = decoded
+            .split_once(':')
+            .ok_or(SSParseError::InvalidPassword)?;
         let method = method.parse().map_err(|_| SSParseError::InvalidMethod)?;
-        let password = encoded_part
-            .get(1)
-            .ok_or(SSParseError::InvalidPassword)?
-            .to_string();
-        Ok((method, password))
-    }
-
-    fn remove_unsafe_padding(s: &str) -> String {
-        let s = if s.contains("=@") {
-            let a = s
-                .split("=@")
-                .map(|e| e.trim_matches('='))
-                .collect::<Vec<&str>>()
-                .join("@");
-            a
-        } else {
-            s.into()
-        };
-        s
+        Ok((method, password.to_string()))
     }
 
     fn encode_user_info(method: &Method, password: &str) -> String {
@@ -272,21 +670,65 @@ impl SSConfig {
         let user_info = user_info.trim_end_matches('=');
         user_info.into()
     }
-    fn get_hash(tag: &Option<String>) -> String {
+    /// the raw fragment while it still matches `tag`, otherwise the encoded tag
+    fn raw_hash<'a>(tag: &Option<String>, tag_raw: &'a Option<String>) -> Option<&'a str> {
+        let raw = tag_raw.as_deref()?;
+        let tag = tag.as_deref().filter(|t| !t.is_empty())?;
+        let matches = |raw: &str| percent_decode_str(raw).decode_utf8_lossy() == tag;
+        (matches(raw) || matches(&raw.replace('+', " "))).then_some(raw)
+    }
+    fn get_hash(
+        tag: &Option<String>,
+        tag_raw: &Option<String>,
+        options: &SerializeOptions,
+    ) -> String {
+        if let Some(raw) = Self::raw_hash(tag, tag_raw) {
+            return format!("#{raw}");
+        }
+        let set = match options.aggressive_tag_encoding {
+            true => NON_ALPHANUMERIC,
+            false => FRAGMENT,
+        };
         match tag {
-            Some(t) if !t.is_empty() => format!(
-                "#{}",
-                percent_encoding::percent_encode(t.as_ref(), NON_ALPHANUMERIC)
-            ),
+            Some(t) if !t.is_empty() && options.plus_for_spaces => {
+                let words = t
+                    .split(' ')
+                    .map(|w| percent_encode(w.as_bytes(), set).to_string());
+                format!("#{}", words.collect::<Vec<_>>().join("+"))
+            }
+            Some(t) if !t.is_empty() => format!("#{}", percent_encode(t.as_ref(), set)),
             _ => "".into(),
         }
     }
-    fn encode_query(extra: &HashMap<String, String>) -> String {
+    fn encode_query(
+        extra: Option<&QueryParams>,
+        mode: Option<(&str, &str)>,
+        prefix: Option<&OutlinePrefix>,
+        order: QueryOrder,
+    ) -> String {
         let mut uri_encoded = url::form_urlencoded::Serializer::new(String::new());
-        extra.iter().for_each(|(k, v)| {
+        for (k, v) in extra.map(|e| e.ordered(order)).unwrap_or_default() {
             uri_encoded.append_pair(k, v);
-        });
-        uri_encoded.finish()
+        }
+        if let Some((k, v)) = mode {
+            uri_encoded.append_pair(k, v);
+        }
+        let mut query = uri_encoded.finish();
+        if let Some(prefix) = prefix {
+            if !query.is_empty() {
+                query.push('&');
+            }
+            query.push_str("prefix=");
+            query.extend(percent_encoding::utf8_percent_encode(
+                &prefix.to_param(),
+                NON_ALPHANUMERIC,
+            ));
+        }
+        if query.is_empty() {
+            query
+        } else {
+            format!("?{query}")
+        }
     }
     fn get_uri_formatted_host(host: &Host) -> String {
         match host {
@@ -325,6 +767,55 @@ mod tests {
             assert_eq!(config.tag, Some("Foo Bar".to_string()));
             assert_eq!(config.extra, None);
         }
+        #[test]
+        fn hashes_consistently_with_eq() {
+            let configs = [
+                "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo%20Bar",
+                "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo Bar",
+                "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo",
+            ]
+            .map(|uri| SSConfig::parse(uri).unwrap());
+            let set = configs
+                .into_iter()
+                .collect::<std::collections::HashSet<_>>();
+            assert_eq!(set.len(), 2);
+        }
+        #[test]
+        fn converts_hosts_between_punycode_and_unicode() {
+            let mut config =
+                SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888").unwrap();
+            assert_eq!(config.host_ascii(), "[2001:db8::1]");
+            assert_eq!(config.host_unicode(), "[2001:db8::1]");
+            config.host = Host::Domain("Bücher.example".to_string());
+            assert_eq!(config.host_ascii(), "xn--bcher-kva.example");
+            assert_eq!(config.host_unicode(), "bücher.example");
+        }
+        #[test]
+        fn setters_replace_single_fields() {
+            let original = SSConfig::parse(
+                "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=obfs-local&a=1&a=2#%46oo",
+            )
+            .unwrap();
+            let config = original
+                .clone()
+                .with_password("secret")
+                .with_plugin(None)
+                .with_extra("a", "3");
+            assert_eq!(config.password, "secret");
+            assert_eq!(config.plugin(), None);
+            assert_eq!(
+                config.extra.as_ref().unwrap().as_slice(),
+                [("a".into(), "3".into())]
+            );
+            assert_eq!(config.tag_raw.as_deref(), Some("%46oo"));
+            let config = config.with_extra("a", "").with_plugin(None).with_tag("Foo");
+            assert_eq!(config.tag_raw, None);
+            let plugin = Plugin::parse("v2ray-plugin;tls");
+            assert_eq!(
+                original.with_plugin(Some(plugin.clone())).plugin(),
+                Some(plugin)
+            );
+        }
     }
 
     mod sip002 {
@@ -339,7 +830,10 @@ mod tests {
                 method: Method::Aes128Gcm,
                 password: "test".to_string(),
                 tag: Some("Foo Bar".to_string()),
+                tag_raw: None,
                 extra: None,
+                prefix: None,
+                mode: None,
             };
             assert_eq!(
                 config.to_sip002(),
@@ -355,7 +849,10 @@ mod tests {
                 method: "aes-128-gcm".parse().unwrap(),
                 password: "小洞不补大洞吃苦".into(),
                 tag: Some("Foo Bar".into()),
+                tag_raw: None,
                 extra: None,
+                prefix: None,
+                mode: None,
             };
             assert_eq!(
             config.to_sip002(),
@@ -370,7 +867,10 @@ mod tests {
                 method: "aes-128-gcm".parse().unwrap(),
                 password: "test".into(),
                 tag: Some("Foo Bar".into()),
+                tag_raw: None,
                 extra: None,
+                prefix: None,
+                mode: None,
             };
 
             assert_eq!(
@@ -386,7 +886,10 @@ mod tests {
                 method: Method::BfCfb,
                 password: "test".to_string(),
                 tag: Some("Foo Bar".to_string()),
+                tag_raw: None,
                 extra: None,
+                prefix: None,
+                mode: None,
             };
             assert_eq!(
                 config.to_legacy_base64_encoded(),
@@ -401,7 +904,10 @@ mod tests {
                 method: "bf-cfb".parse().unwrap(),
                 password: "小洞不补大洞吃苦".into(),
                 tag: Some("Foo Bar".into()),
+                tag_raw: None,
                 extra: None,
+                prefix: None,
+                mode: None,
             };
             assert_eq!(
             config.to_legacy_base64_encoded(),
@@ -432,6 +938,128 @@ mod tests {
             assert_eq!((config.tag), Some("Foo Bar".into()));
         }
         #[test]
+        fn can_parse_a_sip002_uri_with_percent_encoded_base64() {
+            let input = "ss://YWVzLTEyOC1nY206cD4%2Fd29yZA%3D%3D@192.168.100.1:8888#Foo%20Bar";
+            let config = SSConfig::parse_sip002(input).unwrap();
+
+            assert_eq!((config.method), ("aes-128-gcm").try_into().unwrap());
+            assert_eq!((config.password), ("p>?word"));
+            assert_eq!((config.host), Host::parse("192.168.100.1").unwrap());
+            assert_eq!((config.port), (8888));
+        }
+        #[test]
+        fn accepts_a_scheme_in_any_case() {
+            let expected =
+                SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo").unwrap();
+            for input in [
+                "SS://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo",
+                "Ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo",
+            ] {
+                assert_eq!(SSConfig::parse(input).unwrap(), expected);
+                assert_eq!(SSConfig::parse_shadowrocket(input).unwrap(), expected);
+                assert_eq!(SSConfigRef::parse(input).unwrap().into_owned(), expected);
+            }
+            let legacy = SSConfig::parse("SS://YmYtY2ZiOnRlc3RAMTkyLjE2OC4xMDAuMTo4ODg4").unwrap();
+            assert_eq!(legacy.method, Method::BfCfb);
+        }
+        #[test]
+        fn ignores_a_byte_order_mark_and_surrounding_whitespace() {
+            let expected =
+                SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo").unwrap();
+            let input = "\u{feff} ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo\u{3000}\r\n";
+            assert_eq!(SSConfig::parse(input).unwrap(), expected);
+            assert_eq!(SSConfig::parse_sip002(input).unwrap(), expected);
+            assert_eq!(SSConfig::parse_shadowrocket(input).unwrap(), expected);
+            let legacy = "\u{feff}ss://YmYtY2ZiOnRlc3RAMTkyLjE2OC4xMDAuMTo4ODg4\n";
+            assert_eq!(SSConfig::parse_legacy_base64(legacy).unwrap().port, 8888);
+        }
+        #[test]
+        fn strict_parsing_flags_paths_and_stray_characters() {
+            let strict = ParseOptions {
+                strict: true,
+                ..ParseOptions::default()
+            };
+            for input in [
+                "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888",
+                "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=a#b/c",
+                "ss://YWVzLTEyOC1nY206cD4/d29yZEAxOTIuMTY4LjEwMC4xOjg4ODg#日本",
+            ] {
+                assert!(
+                    SSConfig::parse_with_options(input, &strict).is_ok(),
+                    "{input}"
+                );
+            }
+            let input = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/a/b?plugin=a";
+            assert_eq!(Unexpected::find(input), Some(Unexpected::Path("/a/b")));
+            let input = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo\n";
+            assert_eq!(
+                SSConfig::parse_with_options(input, &strict),
+                Err(SSParseError::UnexpectedCharacters)
+            );
+        }
+        #[test]
+        fn limits_bound_each_dimension() {
+            let bounded = |limits| ParseOptions {
+                limits,
+                ..ParseOptions::default()
+            };
+            let sip002 = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?a=1&b=2&&c=3#Foo";
+            let legacy = "ss://YWVzLTEyOC1nY206dGVzdEAxOTIuMTY4LjEwMC4xOjg4ODg#Foo";
+            for input in [sip002, legacy] {
+                let options = bounded(Limits::UNTRUSTED);
+                assert!(SSConfig::parse_with_options(input, &options).is_ok());
+            }
+            for (input, limits) in [
+                (
+                    sip002,
+                    Limits {
+                        max_input_len: Some(sip002.len() - 1),
+                        ..Limits::default()
+                    },
+                ),
+                (
+                    legacy,
+                    Limits {
+                        max_decoded_len: Some(32),
+                        ..Limits::default()
+                    },
+                ),
+                (
+                    sip002,
+                    Limits {
+                        max_query_pairs: Some(2),
+                        ..Limits::default()
+                    },
+                ),
+            ] {
+                assert_eq!(
+                    SSConfig::parse_with_options(input, &bounded(limits)),
+                    Err(SSParseError::LimitExceeded),
+                    "{limits:?}"
+                );
+            }
+        }
+        #[test]
+        fn can_parse_percent_encoded_ipv6_brackets() {
+            let expected =
+                SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888/#Foo").unwrap();
+            for input in [
+                "ss://YWVzLTEyOC1nY206dGVzdA@%5B2001:db8::1%5D:8888/#Foo",
+                "ss://YWVzLTEyOC1nY206dGVzdA@%5b2001:db8::1%5d:8888/#Foo",
+            ] {
+                assert_eq!(SSConfig::parse(input).unwrap(), expected);
+            }
+        }
+        #[test]
+        fn keeps_padding_like_sequences_outside_the_user_info() {
+            let input = "ss://YWVzLTEyOC1nY206dGVzdA=@192.168.100.1:8888/?group=a=@b#x=@y";
+            let config = SSConfig::parse_sip002(input).unwrap();
+
+            assert_eq!((config.password), ("test"));
+            assert_eq!((config.extra.unwrap()["group"]), ("a=@b"));
+            assert_eq!((config.tag), Some("x=@y".into()));
+        }
+        #[test]
         fn can_parse_a_valid_sip002_uri_with_ipv6_host() {
             let input = "ss://YWVzLTEyOC1nY206dGVzdA@[2001:0:ce49:7601:e866:efff:62c3:fffe]:8888";
             let config = SSConfig::parse_sip002(input).unwrap();
@@ -468,6 +1096,14 @@ mod tests {
             assert_eq!((config.port), (8888));
         }
         #[test]
+        fn can_parse_a_sip002_uri_with_colons_in_a_url_safe_password() {
+            let input = "ss://Y2hhY2hhMjAtaWV0Zi1wb2x5MTMwNTpwQHNzOnc_Pz4@192.168.100.1:8888";
+            let config = SSConfig::parse_sip002(input).unwrap();
+
+            assert_eq!(config.method, Method::Chacha20IetfPoly1305);
+            assert_eq!(config.password, "p@ss:w??>");
+        }
+        #[test]
         fn can_parse_a_valid_sip002_uri_with_an_arbitrary_query_param() {
             let input = "ss://cmM0LW1kNTpwYXNzd2Q@192.168.100.1:8888/?foo=1";
             let config = SSConfig::parse_sip002(input).unwrap();
@@ -491,6 +1127,62 @@ mod tests {
             );
         }
         #[test]
+        fn can_parse_and_serialize_an_outline_prefix() {
+            let input =
+                "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?prefix=%16%03%01%00%C2%A8%01%01";
+            let config = SSConfig::parse_sip002(input).unwrap();
+            let prefix = config.prefix.as_ref().unwrap();
+
+            assert_eq!(prefix.as_bytes(), b"\x16\x03\x01\x00\xa8\x01\x01");
+            assert_eq!(prefix.documented_name(), Some("TLS ClientHello"));
+            assert_eq!(config.extra, None);
+            assert_eq!(config.to_sip002(), input);
+        }
+        #[test]
+        fn rejects_an_outline_prefix_that_is_not_made_of_bytes() {
+            let input = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?prefix=%E2%82%AC";
+            assert_eq!(
+                SSConfig::parse_sip002(input),
+                Err(SSParseError::InvalidPrefix)
+            );
+        }
+        #[test]
+        fn serializes_extra_parameters_as_a_query() {
+            let input =
+                "ss://cmM0LW1kNTpwYXNzd2Q@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp";
+            let config = SSConfig::parse_sip002(input).unwrap();
+            assert_eq!(config.to_sip002(), input);
+        }
+        #[test]
+        fn reuses_the_raw_fragment_while_the_tag_is_unchanged() {
+            let input = "ss://cmM0LW1kNTpwYXNzd2Q@192.168.100.1:8888/#JP-01_%28IPLC%29%2d";
+            let mut config = SSConfig::parse_sip002(input).unwrap();
+            assert_eq!(config.tag.as_deref(), Some("JP-01_(IPLC)-"));
+            assert_eq!(config.tag_raw.as_deref(), Some("JP-01_%28IPLC%29%2d"));
+            assert_eq!(config.to_sip002(), input);
+            assert_eq!(config.sip002_display().to_string(), input);
+
+            config.tag = Some("JP 02".to_string());
+            assert!(config.to_sip002().ends_with("#JP%2002"));
+        }
+        #[test]
+        fn escapes_only_what_a_fragment_cannot_hold() {
+            let mut config =
+                SSConfig::parse("ss://cmM0LW1kNTpwYXNzd2Q@192.168.100.1:8888").unwrap();
+            config.tag = Some("JP-01_(IPLC) #2+ 100%/月".to_string());
+            let uri = config.to_sip002();
+            assert!(uri.ends_with("#JP-01_(IPLC)%20%232%2B%20100%25/%E6%9C%88"));
+            assert_eq!(config.sip002_display().to_string(), uri);
+            assert_eq!(SSConfig::parse(&uri).unwrap().tag, config.tag);
+        }
+        #[test]
+        fn keeps_the_order_of_extra_parameters() {
+            let input = "ss://cmM0LW1kNTpwYXNzd2Q@192.168.100.1:8888/?z=1&plugin=obfs-local&m=2&a=3&y=4#tag";
+            let config = SSConfig::parse_sip002(input).unwrap();
+            assert_eq!(config.to_sip002(), input);
+            assert_eq!(config.sip002_display().to_string(), input);
+        }
+        #[test]
         fn can_parse_a_valid_sip002_uri_with_the_default_http_port_and_no_plugin_parameters() {
             let input = "ss://cmM0LW1kNTpwYXNzd2Q@192.168.100.1:80";
             let config = SSConfig::parse_sip002(input).unwrap();
@@ -577,5 +1269,103 @@ mod tests {
             assert_eq!((config.port), (8888));
             assert_eq!((config.tag), Some("Foo Bar".into()));
         }
+
+        #[test]
+        fn can_parse_a_legacy_base64_uri_with_slashes_in_the_payload() {
+            let input = "ss://YWVzLTEyOC1nY206cD4/d29yZEAxOTIuMTY4LjEwMC4xOjg4ODg=#Foo%20Bar";
+            let config = SSConfig::parse(input).unwrap();
+            let url_safe = "ss://YWVzLTEyOC1nY206cD4_d29yZEAxOTIuMTY4LjEwMC4xOjg4ODg#Foo%20Bar";
+            assert_eq!(SSConfig::parse(url_safe).unwrap(), config);
+
+            assert_eq!((config.method), Method::Aes128Gcm);
+            assert_eq!((config.password), ("p>?word"));
+            assert_eq!((config.host), Host::parse("192.168.100.1").unwrap());
+            assert_eq!((config.port), (8888));
+            assert_eq!((config.tag), Some("Foo Bar".into()));
+        }
+
+        #[test]
+        fn can_parse_a_line_wrapped_legacy_base64_uri() {
+            let input = "ss://YWVzLTEyOC1nY206cD4/d29yZEAx\r\n OTIuMTY4LjEwMC4xOjg4ODg=#Foo%20Bar";
+            let config = SSConfig::parse(input).unwrap();
+
+            assert_eq!((config.password), ("p>?word"));
+            assert_eq!((config.host), Host::parse("192.168.100.1").unwrap());
+            assert_eq!((config.port), (8888));
+        }
+    }
+
+    mod panic_free {
+        use super::super::*;
+        use crate::test_util::mutate;
+
+        /// inputs that used to or could plausibly hit slicing on the wrong byte
+        const REGRESSIONS: &[&str] = &[
+            "",
+            "ss://",
+            "ss://@",
+            "ss://:@:",
+            "ss://#",
+            "ss://?#?",
+            "ss://é",
+            "ss://6bG8OuOBgkDjgII6w6k",
+            "ss://w6k6w6lAw6k6w6k",
+            "ss://YWVzLTEyOC1nY206@:",
+            "ss://YWVzLTEyOC1nY206dGVzdA@[::1",
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=%FF%FE",
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?prefix=%F0%9F%98%80",
+            "ss://YWVzLTEyOC1nY206dGVzdA==@=@=@",
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#%E6%97",
+            "ss://YmYtY2ZiOnRlc3RAOg",
+            "ss://YmYtY2ZiOnRlc3RAOjk5OTk5OQ",
+            "ss://YmYtY2ZiOnRlc3RAMS4yLjMuNDoxODQ0Njc0NDA3MzcwOTU1MTYxNg",
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:18446744073709551616",
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:-1",
+            "expire=18446744073709551615",
+            "upload=18446744073709551615; download=18446744073709551615; total=18446744073709551616",
+            "a = ss, 1.2.3.4, 18446744073709551616, encrypt-method=aes-128-gcm, password=p",
+            "shadowsocks=1.2.3.4:18446744073709551616, method=aes-128-gcm, password=p, tag=a",
+            "a = Shadowsocks,1.2.3.4,18446744073709551616,aes-128-gcm,\"p\"",
+            r#"{"version":1,"servers":[{"server":"a","server_port":18446744073709551616}]}"#,
+            "shadowsocks=é:é, tag=",
+            "日本 = ss, é, é",
+            "= ,,,",
+            "\\;\\=;=",
+        ];
+
+        fn parse_everything(input: &str) {
+            let _ = SSConfig::parse(input);
+            let _ = SSConfig::parse_sip002(input);
+            let _ = SSConfig::parse_legacy_base64(input);
+            let _ = SSConfigRef::parse(input);
+            for compat in [Compat::Spec, Compat::Outline, Compat::Shadowrocket] {
+                let _ = SSConfig::parse_with(input, compat);
+            }
+            let _ = SSConfig::parse_surge_line(input);
+            let _ = SSConfig::parse_quantumult_x_line(input);
+            let _ = SSConfig::parse_loon_line(input);
+            let _ = Plugin::parse(input).options();
+            let _ = SIP008Config::parse(input);
+            let _ = SubscriptionInfo::parse(input);
+            let _ = Subscription::parse(input.as_bytes());
+            let _ = OutlinePrefix::from_param(input);
+        }
+
+        #[test]
+        fn regressions_do_not_panic() {
+            for input in REGRESSIONS {
+                parse_everything(input);
+            }
+        }
+
+        #[test]
+        fn mutated_uris_do_not_panic() {
+            let seeds = conformance::VALID_URIS
+                .iter()
+                .map(|v| v.uri)
+                .chain(REGRESSIONS.iter().copied())
+                .collect::<Vec<_>>();
+            mutate(&seeds, "é日:@#?=%/[];\\,9", 5000, parse_everything);
+        }
     }
 }
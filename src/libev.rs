@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{entry, KnownParam, Mode, QueryParams, SSConfig, SSParseError};
+
+/// the classic shadowsocks-libev `config.json`, keys that don't describe the server
+/// (`local_port`, `timeout`, ...) are kept as raw json, `mode` among them and read into
+/// [`SSConfig::mode`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibevConfig {
+    pub server: String,
+    pub server_port: u16,
+    pub password: String,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin_opts: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fast_open: Option<bool>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LibevParseError {
+    InvalidJson,
+}
+impl std::fmt::Display for LibevParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for LibevParseError {}
+
+impl LibevConfig {
+    pub fn parse(input: &str) -> Result<Self, LibevParseError> {
+        serde_json::from_str(input).map_err(|_| LibevParseError::InvalidJson)
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("libev config is always serializable")
+    }
+    /// builds a config file out of a config, `fast_open` is taken from [`SSConfig::fast_open`]
+    /// ```
+    /// use ss_uri::{LibevConfig, SSConfig};
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888").unwrap();
+    /// let libev = LibevConfig::from_config(&config);
+    /// assert_eq!(libev.server_port, 8888);
+    /// assert_eq!(libev.to_config().unwrap(), config);
+    /// ```
+    pub fn from_config(config: &SSConfig) -> Self {
+        let (plugin, plugin_opts) = entry::plugin_parts(config);
+        Self {
+            server: entry::host_to_string(&config.host),
+            server_port: config.port,
+            password: config.password.clone(),
+            method: config.method.to_string(),
+            plugin,
+            plugin_opts,
+            fast_open: config.fast_open(),
+            other: config
+                .mode
+                .map(|mode| ("mode".to_string(), Value::from(mode.as_str())))
+                .into_iter()
+                .collect(),
+        }
+    }
+    /// the file carries no name so the tag is left empty
+    pub fn to_config(&self) -> Result<SSConfig, SSParseError> {
+        let mut config = entry::build_config(
+            &self.server,
+            self.server_port,
+            &self.method,
+            &self.password,
+            None,
+            self.plugin.as_deref(),
+            self.plugin_opts.as_deref(),
+        )?;
+        if let Some(fast_open) = self.fast_open {
+            config.extra.get_or_insert_with(QueryParams::new).insert(
+                KnownParam::FastOpen.key().to_string(),
+                fast_open.to_string(),
+            );
+        }
+        config.mode = self
+            .other
+            .get("mode")
+            .and_then(Value::as_str)
+            .and_then(Mode::parse);
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use url::Host;
+
+    #[test]
+    fn can_parse_a_libev_config_file() {
+        let libev = LibevConfig::parse(
+            r#"{
+                "server": "2001:db8::1",
+                "server_port": 8388,
+                "local_port": 1080,
+                "password": "barfoo!",
+                "timeout": 600,
+                "method": "chacha20-ietf-poly1305",
+                "plugin": "obfs-local",
+                "plugin_opts": "obfs=http;obfs-host=www.bing.com",
+                "fast_open": true,
+                "mode": "tcp_and_udp"
+            }"#,
+        )
+        .unwrap();
+        let config = libev.to_config().unwrap();
+        assert_eq!(config.host, Host::parse("[2001:db8::1]").unwrap());
+        assert_eq!(
+            config.plugin().unwrap().to_string(),
+            "obfs-local;obfs=http;obfs-host=www.bing.com"
+        );
+        assert_eq!(config.fast_open(), Some(true));
+        assert_eq!(config.mode, Some(Mode::TcpAndUdp));
+
+        let mut converted = LibevConfig::from_config(&config);
+        assert_eq!(converted.fast_open, Some(true));
+        assert_eq!(converted.other["mode"], "tcp_and_udp");
+        converted.other = libev.other.clone();
+        assert_eq!(converted, libev);
+        assert_eq!(LibevConfig::parse(&libev.to_json()).unwrap(), libev);
+    }
+
+    #[test]
+    fn reads_fast_open_under_any_of_its_keys() {
+        for uri in [
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?tfo=1",
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?fast_open=true",
+        ] {
+            let config = SSConfig::parse(uri).unwrap();
+            assert_eq!(LibevConfig::from_config(&config).fast_open, Some(true));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        assert_eq!(LibevConfig::parse("{}"), Err(LibevParseError::InvalidJson));
+    }
+}
@@ -0,0 +1,138 @@
+//! warnings about configs that work but probably shouldn't be handed out
+use core::fmt;
+
+use crate::{Method, SSConfig};
+
+/// sip003 plugins common enough that [`SSConfig::lint`] doesn't flag them
+pub const KNOWN_PLUGINS: &[&str] = &[
+    "obfs-local",
+    "simple-obfs",
+    "v2ray-plugin",
+    "xray-plugin",
+    "kcptun",
+    "ck-client",
+    "gq-client",
+    "gost-plugin",
+    "qtun-client",
+    "shadow-tls",
+];
+
+/// passwords shorter than this are flagged by [`SSConfig::lint`]
+pub const LINT_MIN_PASSWORD_LEN: usize = 8;
+
+/// tags longer than this in characters are flagged by [`SSConfig::lint`]
+pub const LINT_MAX_TAG_LEN: usize = 64;
+
+/// how much a [`Lint`] matters, ordered from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// unusual but harmless
+    Info,
+    /// weakens the connection or trips up some clients
+    Warning,
+    /// the traffic can be decrypted by an eavesdropper
+    Error,
+}
+
+/// something questionable about a config that still validates
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// a stream cipher without authentication, rc4-md5 and bf-cfb are outright broken
+    DeprecatedCipher(Method),
+    /// a password shorter than [`LINT_MIN_PASSWORD_LEN`]
+    ShortPassword,
+    /// a privileged port other than the ones commonly used to blend in, 53, 80 and 443
+    SuspiciousPort(u16),
+    /// a plugin not in [`KNOWN_PLUGINS`]
+    UnknownPlugin(String),
+    /// a tag longer than [`LINT_MAX_TAG_LEN`], which clients cut off in their server lists
+    LongTag,
+}
+
+impl Lint {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Lint::DeprecatedCipher(Method::Rc4Md5 | Method::BfCfb) => Severity::Error,
+            Lint::DeprecatedCipher(_) | Lint::ShortPassword | Lint::UnknownPlugin(_) => {
+                Severity::Warning
+            }
+            Lint::SuspiciousPort(_) | Lint::LongTag => Severity::Info,
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {:?}", self.severity(), self)
+    }
+}
+
+impl SSConfig {
+    /// questionable choices in the config, for grading what a provider hands out, broken
+    /// invariants are left to [`SSConfig::validate`]
+    /// ```
+    /// use ss_uri::{Lint, Method, SSConfig, Severity};
+    /// let config = SSConfig::parse("ss://cmM0LW1kNTp0ZXN0@192.168.100.1:8888").unwrap();
+    /// let lints = config.lint();
+    /// assert_eq!(lints, [Lint::DeprecatedCipher(Method::Rc4Md5), Lint::ShortPassword]);
+    /// assert_eq!(lints.iter().map(Lint::severity).max(), Some(Severity::Error));
+    /// ```
+    pub fn lint(&self) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        if !self.method.is_aead() {
+            lints.push(Lint::DeprecatedCipher(self.method));
+        }
+        if self.password.chars().count() < LINT_MIN_PASSWORD_LEN {
+            lints.push(Lint::ShortPassword);
+        }
+        if self.port < 1024 && !matches!(self.port, 53 | 80 | 443) {
+            lints.push(Lint::SuspiciousPort(self.port));
+        }
+        if let Some(plugin) = self.plugin() {
+            if !KNOWN_PLUGINS.contains(&plugin.name.as_str()) {
+                lints.push(Lint::UnknownPlugin(plugin.name));
+            }
+        }
+        let tag_len = self.tag.as_deref().map_or(0, |tag| tag.chars().count());
+        if tag_len > LINT_MAX_TAG_LEN {
+            lints.push(Lint::LongTag);
+        }
+        lints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_each_questionable_field() {
+        let uri = "ss://YWVzLTI1Ni1nY206Y29ycmVjdGhvcnNl@example.com:443/?plugin=obfs-local#Foo";
+        let mut config = SSConfig::parse(uri).unwrap();
+        assert_eq!(config.lint(), []);
+        config.method = Method::Aes256Cfb;
+        config.port = 22;
+        config.tag = Some("日".repeat(LINT_MAX_TAG_LEN + 1));
+        config.extra = Some([("plugin".to_string(), "my-plugin".to_string())].into());
+        let lints = config.lint();
+        assert_eq!(
+            lints,
+            [
+                Lint::DeprecatedCipher(Method::Aes256Cfb),
+                Lint::SuspiciousPort(22),
+                Lint::UnknownPlugin("my-plugin".to_string()),
+                Lint::LongTag,
+            ]
+        );
+        let severities = lints.iter().map(Lint::severity).collect::<Vec<_>>();
+        assert_eq!(
+            severities,
+            [
+                Severity::Warning,
+                Severity::Info,
+                Severity::Warning,
+                Severity::Info
+            ]
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! a list of parsed servers with the clean-up steps subscription managers run after parsing
+use core::ops::{Deref, DerefMut, RangeBounds};
+use std::collections::HashSet;
+use std::vec;
+
+use url::Host;
+
+use crate::tag::TagTemplate;
+use crate::{Method, SSConfig};
+
+/// configs in the order they were added, derefs to a slice for indexing and iterating
+/// ```
+/// use ss_uri::{Method, ServerList, SSConfig};
+/// let mut list = [
+///     "ss://YWVzLTEyOC1nY206dGVzdA@b.example.com:8388#B",
+///     "ss://YWVzLTEyOC1nY206dGVzdA@a.example.com:443#A",
+///     "ss://YWVzLTEyOC1nY206dGVzdA@A.example.com:443#A%20again",
+///     "ss://YmYtY2ZiOnRlc3RAMTkyLjE2OC4xMDAuMTo4ODg4#Old",
+/// ]
+/// .into_iter()
+/// .map(|uri| SSConfig::parse(uri).unwrap())
+/// .collect::<ServerList>();
+/// list.dedup();
+/// list.retain_methods(&[Method::Aes128Gcm]);
+/// list.sort_by_key(|config| config.tag.clone());
+/// list.retag(|i, config| format!("{i:02} {}", config.tag.as_deref().unwrap_or_default()));
+/// let tags = list.iter().map(|c| c.tag.as_deref().unwrap()).collect::<Vec<_>>();
+/// assert_eq!(tags, ["01 A", "02 B"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ServerList(Vec<SSConfig>);
+
+impl ServerList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn push(&mut self, config: SSConfig) {
+        self.0.push(config);
+    }
+    pub fn into_vec(self) -> Vec<SSConfig> {
+        self.0
+    }
+    /// keeps the first config for each host and port, domains compared in their normalized
+    /// form as [`SSConfig::endpoint_eq`] does
+    pub fn dedup(&mut self) {
+        let mut seen = HashSet::new();
+        self.0.retain(|config| seen.insert(config.endpoint()));
+    }
+    /// keeps the first of configs `same` considers equal, e.g. [`SSConfig::same_server`] to
+    /// keep servers sharing an endpoint under different credentials
+    pub fn dedup_by(&mut self, mut same: impl FnMut(&SSConfig, &SSConfig) -> bool) {
+        let mut kept: Vec<SSConfig> = Vec::with_capacity(self.0.len());
+        for config in self.0.drain(..) {
+            if !kept.iter().any(|k| same(k, &config)) {
+                kept.push(config);
+            }
+        }
+        self.0 = kept;
+    }
+    /// sorts in the order of [`SSConfig`]'s `Ord`, equal configs keep their order
+    pub fn sort(&mut self) {
+        self.0.sort();
+    }
+    /// sorts by the key, configs with equal keys keep their order
+    pub fn sort_by_key<K: Ord>(&mut self, key: impl FnMut(&SSConfig) -> K) {
+        self.0.sort_by_key(key);
+    }
+    pub fn retain(&mut self, keep: impl FnMut(&SSConfig) -> bool) {
+        self.0.retain(keep);
+    }
+    pub fn retain_methods(&mut self, methods: &[Method]) {
+        self.0.retain(|config| methods.contains(&config.method));
+    }
+    pub fn retain_ports(&mut self, ports: impl RangeBounds<u16>) {
+        self.0.retain(|config| ports.contains(&config.port));
+    }
+    /// keeps the configs whose host passes `keep`, domains are handed over in their normalized
+    /// form
+    pub fn retain_hosts(&mut self, mut keep: impl FnMut(&Host) -> bool) {
+        self.0.retain(|config| keep(&config.endpoint().0));
+    }
+    /// replaces every tag with what `tag` returns for the config, numbering them from 1 in
+    /// list order
+    pub fn retag(&mut self, mut tag: impl FnMut(usize, &SSConfig) -> String) {
+        for (i, config) in self.0.iter_mut().enumerate() {
+            config.tag = Some(tag(i + 1, config));
+            config.tag_raw = None;
+        }
+    }
+    /// [`ServerList::retag`] with a template
+    pub fn retag_with(&mut self, template: &TagTemplate) {
+        template.apply(&mut self.0);
+    }
+}
+
+impl Deref for ServerList {
+    type Target = [SSConfig];
+
+    fn deref(&self) -> &[SSConfig] {
+        &self.0
+    }
+}
+
+impl DerefMut for ServerList {
+    fn deref_mut(&mut self) -> &mut [SSConfig] {
+        &mut self.0
+    }
+}
+
+impl From<Vec<SSConfig>> for ServerList {
+    fn from(configs: Vec<SSConfig>) -> Self {
+        ServerList(configs)
+    }
+}
+
+impl From<ServerList> for Vec<SSConfig> {
+    fn from(list: ServerList) -> Self {
+        list.0
+    }
+}
+
+impl FromIterator<SSConfig> for ServerList {
+    fn from_iter<I: IntoIterator<Item = SSConfig>>(iter: I) -> Self {
+        ServerList(iter.into_iter().collect())
+    }
+}
+
+impl Extend<SSConfig> for ServerList {
+    fn extend<I: IntoIterator<Item = SSConfig>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl<'a> IntoIterator for &'a ServerList {
+    type Item = &'a SSConfig;
+    type IntoIter = std::slice::Iter<'a, SSConfig>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for ServerList {
+    type Item = SSConfig;
+    type IntoIter = vec::IntoIter<SSConfig>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(uris: &[&str]) -> ServerList {
+        uris.iter()
+            .map(|uri| SSConfig::parse(uri).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn dedup_keeps_the_first_of_each_endpoint() {
+        let uris = [
+            "ss://YWVzLTEyOC1nY206dGVzdA@example.com:443#1",
+            "ss://YWVzLTEyOC1nY206b3RoZXI@EXAMPLE.com:443#2",
+            "ss://YWVzLTEyOC1nY206dGVzdA@example.com:8443#3",
+            "ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8:0::1]:443#4",
+            "ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:443#5",
+        ];
+        let tags = |list: &ServerList| {
+            list.iter()
+                .map(|c| c.tag.clone().unwrap())
+                .collect::<Vec<_>>()
+        };
+        let mut deduped = list(&uris);
+        deduped.dedup();
+        assert_eq!(tags(&deduped), ["1", "3", "4"]);
+        let mut deduped = list(&uris);
+        deduped.dedup_by(SSConfig::same_server);
+        assert_eq!(tags(&deduped), ["1", "2", "3", "4"]);
+    }
+
+    #[test]
+    fn filters_by_port_and_host() {
+        let mut servers = list(&[
+            "ss://YWVzLTEyOC1nY206dGVzdA@hk.example.com:443",
+            "ss://YWVzLTEyOC1nY206dGVzdA@JP.example.com:443",
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:80",
+            "ss://YWVzLTEyOC1nY206dGVzdA@jp.example.net:8443",
+        ]);
+        servers.retain_ports(443..);
+        servers.retain_hosts(|host| matches!(host, Host::Domain(d) if d.starts_with("jp.")));
+        assert_eq!(servers.len(), 2);
+        servers.retain_ports(..1024);
+        assert_eq!(servers[0].host_ascii(), "jp.example.com");
+    }
+}
@@ -0,0 +1,262 @@
+//! hand-rolled splitting of `scheme://userinfo@host:port/path?query#fragment`, used instead of
+//! `url::Url` with the `lite` feature
+use std::borrow::Cow;
+use std::net::Ipv6Addr;
+
+use url::Host;
+
+use crate::{Mode, OutlinePrefix, QueryParams, SSConfig, SSParseError};
+
+/// the raw, still percent-encoded components of an uri
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct UriParts<'a> {
+    pub scheme: &'a str,
+    /// everything before the last `@` of the authority
+    pub user_info: Option<&'a str>,
+    /// `None` for uris without an authority, ipv6 addresses keep their brackets
+    pub host: Option<&'a str>,
+    pub port: Option<u16>,
+    pub query: Option<&'a str>,
+    pub fragment: Option<&'a str>,
+}
+
+impl<'a> UriParts<'a> {
+    /// `s` should already be free of tabs and newlines, see [`strip_tabs_and_newlines`]
+    pub fn split(s: &'a str) -> Result<Self, SSParseError> {
+        let s = s.trim_matches(|c: char| c <= ' ');
+        let (scheme, rest) = s.split_once(':').ok_or(SSParseError::InvalidUrl)?;
+        let mut chars = scheme.chars();
+        if !chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            || !chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        {
+            return Err(SSParseError::InvalidUrl);
+        }
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (rest, None),
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (rest, None),
+        };
+        let mut parts = UriParts {
+            scheme,
+            user_info: None,
+            host: None,
+            port: None,
+            query,
+            fragment,
+        };
+        let Some(rest) = rest.strip_prefix("//") else {
+            return Ok(parts);
+        };
+        let authority = rest.split('/').next().unwrap_or_default();
+        let endpoint = match authority.rsplit_once('@') {
+            Some((user_info, endpoint)) => {
+                parts.user_info = Some(user_info);
+                endpoint
+            }
+            None => authority,
+        };
+        let (host, port) = match endpoint.strip_prefix('[') {
+            Some(bracketed) => {
+                let (ip, rest) = bracketed.split_once(']').ok_or(SSParseError::InvalidUrl)?;
+                let port = match rest {
+                    "" => None,
+                    rest => Some(rest.strip_prefix(':').ok_or(SSParseError::InvalidUrl)?),
+                };
+                (&endpoint[..ip.len() + 2], port)
+            }
+            None => match endpoint.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (endpoint, None),
+            },
+        };
+        let valid_host = match host.strip_prefix('[') {
+            Some(ip) => ip
+                .strip_suffix(']')
+                .is_some_and(|ip| ip.parse::<Ipv6Addr>().is_ok()),
+            None => !host.contains([
+                '\0', ' ', '#', '/', ':', '<', '>', '?', '@', '[', '\\', ']', '^',
+            ]),
+        };
+        // `ss://` has no host at all while an empty host next to user info or a port is an error
+        if !valid_host || (host.is_empty() && (parts.user_info.is_some() || port.is_some())) {
+            return Err(SSParseError::InvalidUrl);
+        }
+        parts.host = (!host.is_empty()).then_some(host);
+        // the port ends at the first non-digit, which may only be a backslash starting the path
+        let port = port.unwrap_or_default();
+        let (digits, rest) = port.split_at(port.bytes().take_while(u8::is_ascii_digit).count());
+        if !rest.is_empty() && !rest.starts_with('\\') {
+            return Err(SSParseError::InvalidUrl);
+        }
+        parts.port = match digits {
+            "" => None,
+            digits => Some(digits.parse().map_err(|_| SSParseError::InvalidUrl)?),
+        };
+        Ok(parts)
+    }
+
+    /// whether the scheme is one of the `ss` family, compared case-insensitively
+    pub fn is_ss(&self) -> bool {
+        self.scheme
+            .get(..2)
+            .is_some_and(|s| s.eq_ignore_ascii_case("ss"))
+    }
+
+    /// the part of the user info before the first `:`, what `url` calls the username
+    pub fn username(&self) -> &'a str {
+        let user_info = self.user_info.unwrap_or_default();
+        user_info.split(':').next().unwrap_or_default()
+    }
+
+    /// the host parsed as a domain, ipv4 or ipv6 address
+    pub fn parse_host(&self) -> Result<Option<Host>, SSParseError> {
+        let Some(host) = self.host else {
+            return Ok(None);
+        };
+        let host = match host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+            Some(ip) => Host::Ipv6(
+                ip.parse::<Ipv6Addr>()
+                    .map_err(|_| SSParseError::InvalidUrl)?,
+            ),
+            None => Host::parse(host).map_err(|_| SSParseError::InvalidUrl)?,
+        };
+        Ok(Some(host))
+    }
+}
+
+/// the url parser ignores these anywhere in the input
+fn strip_tabs_and_newlines(s: &str) -> Cow<'_, str> {
+    if s.contains(['\t', '\n', '\r']) {
+        Cow::Owned(s.replace(['\t', '\n', '\r'], ""))
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+impl SSConfig {
+    pub(crate) fn parse_sip002_lite(s: &str) -> Result<Self, SSParseError> {
+        let tag_raw = Self::extract_raw_hash(s);
+        let s = strip_tabs_and_newlines(s);
+        let parts = UriParts::split(&s)?;
+        if !parts.is_ss() {
+            return Err(SSParseError::InvalidProtocol);
+        }
+
+        let host = parts.parse_host()?.ok_or(SSParseError::InvalidHost)?;
+        let port = parts.port.ok_or(SSParseError::InvalidPort)?;
+        let mut query = url::form_urlencoded::parse(parts.query.unwrap_or_default().as_bytes())
+            .map(|(a, b)| (a.into_owned(), b.into_owned()))
+            .collect::<QueryParams>();
+        let prefix = query
+            .remove("prefix")
+            .map(|p| OutlinePrefix::from_param(&p))
+            .transpose()?;
+        let mode = Mode::take_from(&mut query);
+        let (method, password) = Self::extract_method_and_password(parts.username())?;
+        let tag = Self::extract_hash(parts.fragment);
+
+        Ok(SSConfig {
+            host,
+            port,
+            method,
+            password,
+            tag,
+            tag_raw,
+            extra: if query.is_empty() { None } else { Some(query) },
+            prefix,
+            mode,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::{INVALID_URIS, VALID_URIS};
+    use crate::test_util::mutate;
+
+    #[test]
+    fn splits_uris_into_components() {
+        let parts =
+            UriParts::split(" SS://user:pass@[2001:db8::1]:8388/path?plugin=a#tag\n").unwrap();
+        assert_eq!(
+            parts,
+            UriParts {
+                scheme: "SS",
+                user_info: Some("user:pass"),
+                host: Some("[2001:db8::1]"),
+                port: Some(8388),
+                query: Some("plugin=a"),
+                fragment: Some("tag"),
+            }
+        );
+        assert!(parts.is_ss());
+        assert_eq!(parts.username(), "user");
+        assert_eq!(
+            UriParts::split("ss://YmYtY2Zi#a?b").unwrap().host,
+            Some("YmYtY2Zi")
+        );
+        assert_eq!(UriParts::split("ss:opaque").unwrap().host, None);
+    }
+
+    #[test]
+    fn rejects_malformed_authorities() {
+        for uri in [
+            "no scheme",
+            "1ss://a@b:1",
+            "ss://a@b:port",
+            "ss://a@b:99999",
+            "ss://a@[::1",
+            "ss://a@[::1]x",
+            "ss://a@:1",
+        ] {
+            assert_eq!(UriParts::split(uri), Err(SSParseError::InvalidUrl), "{uri}");
+        }
+    }
+
+    #[test]
+    fn agrees_with_the_url_parser() {
+        let seeds = VALID_URIS
+            .iter()
+            .map(|v| v.uri)
+            .chain(INVALID_URIS.iter().map(|v| v.uri))
+            .chain([
+                "ss://YWVzLTEyOC1nY206dGVzdA@EXAMPLE.com:8888",
+                "SS://YWVzLTEyOC1nY206dGVzdA@[::ffff:1.2.3.4]:08888/?prefix=%16%03%01&a=1#x%20y",
+                "ss://YmYtY2ZiOnRlc3RAMTkyLjE2OC4xMDAuMTo4ODg4#Foo Bar",
+            ])
+            .collect::<Vec<_>>();
+        mutate(
+            &seeds,
+            "0123456789abcxyzABC:@#?=%/[]._-+&\\ \t",
+            20000,
+            |input| {
+                // other schemes are rejected either way, though possibly by different checks
+                let scheme = input
+                    .trim_start_matches(|c: char| c <= ' ')
+                    .replace('\t', "");
+                if !scheme
+                    .get(..2)
+                    .is_some_and(|s| s.eq_ignore_ascii_case("ss"))
+                {
+                    assert_eq!(
+                        SSConfig::parse(input).ok(),
+                        SSConfig::parse_sip002_url(input)
+                            .or_else(|_| SSConfig::parse_legacy_base64_unchecked(input))
+                            .ok(),
+                        "{input}"
+                    );
+                    return;
+                }
+                assert_eq!(
+                    SSConfig::parse_sip002_lite(input),
+                    SSConfig::parse_sip002_url(input),
+                    "{input}"
+                );
+            },
+        );
+    }
+}
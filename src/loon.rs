@@ -0,0 +1,164 @@
+use core::fmt;
+
+use crate::{entry, Mode, Plugin, QueryParams, SSConfig, SSParseError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoonError {
+    InvalidLine,
+    UnsupportedType,
+    InvalidConfig(SSParseError),
+    /// loon only understands simple-obfs
+    UnsupportedPlugin,
+}
+impl fmt::Display for LoonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for LoonError {}
+
+impl SSConfig {
+    /// parses a loon proxy line, `obfs-name` options become an `obfs-local` plugin, `udp` is
+    /// read into [`SSConfig::mode`] and other options are kept in the extra parameters
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse_loon_line(
+    ///     r#"Proxy = Shadowsocks,1.2.3.4,443,aes-128-gcm,"password",obfs-name=http,obfs-host=bing.com,udp=true"#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(config.password, "password");
+    /// assert_eq!(config.plugin().unwrap().to_string(), "obfs-local;obfs=http;obfs-host=bing.com");
+    /// ```
+    pub fn parse_loon_line(line: &str) -> Result<SSConfig, LoonError> {
+        let (name, definition) = line.split_once('=').ok_or(LoonError::InvalidLine)?;
+        let fields = entry::split_fields(definition);
+        let (proxy_type, server, port, method, password) = match fields[..] {
+            [proxy_type, server, port, method, password, ..] => {
+                (proxy_type, server, port, method, password)
+            }
+            _ => return Err(LoonError::InvalidLine),
+        };
+        if !proxy_type.eq_ignore_ascii_case("shadowsocks") {
+            return Err(LoonError::UnsupportedType);
+        }
+        let port = port.parse().map_err(|_| LoonError::InvalidLine)?;
+        let mut obfs = Vec::new();
+        let mut extra = QueryParams::new();
+        let pairs = fields[5..]
+            .iter()
+            .map(|field| entry::split_pair(field))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(LoonError::InvalidLine)?;
+        for (key, value) in &pairs {
+            let (key, value) = (*key, value.as_ref());
+            match key {
+                "obfs-name" => obfs.push(("obfs", Some(value))),
+                "obfs-host" | "obfs-uri" => obfs.push((key, Some(value))),
+                key => {
+                    extra.insert(key.to_string(), value.to_string());
+                }
+            }
+        }
+        let plugin = (!obfs.is_empty()).then(|| Plugin::from_options("obfs-local", obfs));
+        let mut config = entry::build_config(
+            &entry::unquote(server),
+            port,
+            &entry::unquote(method),
+            &entry::unquote(password),
+            Some(name.trim().to_string()).filter(|n| !n.is_empty()),
+            plugin.as_ref().map(|p| p.name.as_str()),
+            plugin.as_ref().and_then(|p| p.opts.as_deref()),
+        )
+        .map_err(LoonError::InvalidConfig)?;
+        config.mode = Mode::take_from(&mut extra);
+        if !extra.is_empty() {
+            config
+                .extra
+                .get_or_insert_with(QueryParams::new)
+                .extend(extra);
+        }
+        Ok(config)
+    }
+    /// serializes the config as a loon proxy line, the password is always quoted
+    pub fn to_loon_line(&self) -> Result<String, LoonError> {
+        let mut line = format!(
+            "{} = Shadowsocks,{},{},{},{}",
+            entry::name_or_endpoint(self),
+            entry::host_to_string(&self.host),
+            self.port,
+            self.method,
+            entry::always_quote(&self.password)
+        );
+        if let Some(plugin) = self.plugin() {
+            if !matches!(plugin.name.as_str(), "obfs-local" | "simple-obfs") {
+                return Err(LoonError::UnsupportedPlugin);
+            }
+            for (key, value) in plugin.options() {
+                let value = value.ok_or(LoonError::UnsupportedPlugin)?;
+                let key = if key == "obfs" { "obfs-name" } else { &key };
+                line.push_str(&format!(",{}={}", key, entry::quote(&value)));
+            }
+        }
+        let mut extra = self.extra.iter().flatten().collect::<Vec<_>>();
+        extra.sort();
+        for (key, value) in extra.into_iter().filter(|(key, _)| *key != "plugin") {
+            line.push_str(&format!(",{}={}", key, entry::quote(value)));
+        }
+        if let Some(mode) = self.mode {
+            line.push_str(&format!(",udp={}", mode.udp()));
+        }
+        Ok(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use url::Host;
+
+    #[test]
+    fn can_parse_loon_lines() {
+        let config = SSConfig::parse_loon_line(
+            r#"My Proxy = shadowsocks, 2001:db8::1, 443, chacha20-ietf-poly1305, "p,w=d", fast-open=false"#,
+        )
+        .unwrap();
+        assert_eq!(config.tag, Some("My Proxy".to_string()));
+        assert_eq!(config.host, Host::parse("[2001:db8::1]").unwrap());
+        assert_eq!(config.password, "p,w=d");
+        assert_eq!(config.extra.as_ref().unwrap()["fast-open"], "false");
+    }
+
+    #[test]
+    fn loon_lines_round_trip() {
+        let line = r#"Proxy = Shadowsocks,example.com,443,aes-256-gcm,"pass",obfs-name=tls,obfs-host=bing.com,fast-open=true,udp=true"#;
+        let config = SSConfig::parse_loon_line(line).unwrap();
+        assert_eq!(config.to_loon_line().unwrap(), line);
+    }
+
+    #[test]
+    fn passwords_with_quotes_round_trip() {
+        let mut config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888").unwrap();
+        for password in [r#"a"b,c"#, r#"a\",b"#, r#"\"#] {
+            config.password = password.to_string();
+            let line = config.to_loon_line().unwrap();
+            assert_eq!(SSConfig::parse_loon_line(&line).unwrap().password, password);
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_lines() {
+        assert_eq!(
+            SSConfig::parse_loon_line("Proxy = vmess,example.com,443,auto,\"id\""),
+            Err(LoonError::UnsupportedType)
+        );
+        assert_eq!(
+            SSConfig::parse_loon_line("Proxy = Shadowsocks,example.com,443"),
+            Err(LoonError::InvalidLine)
+        );
+        let config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=v2ray-plugin%3Btls",
+        )
+        .unwrap();
+        assert_eq!(config.to_loon_line(), Err(LoonError::UnsupportedPlugin));
+    }
+}
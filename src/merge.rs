@@ -0,0 +1,166 @@
+//! combining the servers of several providers into one list
+use std::collections::HashSet;
+
+use crate::{entry, SSConfig, ServerList};
+
+/// which configs [`merge`] treats as the same server, the one from the source with the higher
+/// priority is kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DedupStrategy {
+    /// keep every config
+    None,
+    /// same host and port, see [`SSConfig::endpoint_eq`]
+    Endpoint,
+    /// same endpoint, method, password and plugin, see [`SSConfig::same_server`]
+    #[default]
+    SameServer,
+    /// equal once canonicalized, tags included, see [`SSConfig::canonicalize`]
+    Identical,
+}
+
+/// the servers of one provider
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MergeSource {
+    pub name: String,
+    /// sources with a higher priority come first and win deduplication, equal priorities keep
+    /// the order the sources were given in
+    pub priority: i32,
+    pub configs: Vec<SSConfig>,
+}
+
+impl MergeSource {
+    pub fn new(name: impl Into<String>, configs: impl IntoIterator<Item = SSConfig>) -> Self {
+        MergeSource {
+            name: name.into(),
+            priority: 0,
+            configs: configs.into_iter().collect(),
+        }
+    }
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MergePolicy {
+    pub dedup: DedupStrategy,
+    /// when set tags become `<source name><separator><tag>`, untagged configs use their
+    /// endpoint as the tag
+    pub tag_separator: Option<String>,
+}
+
+/// the servers of every source in one list, ordered by source priority and then the order
+/// within each source
+/// ```
+/// use ss_uri::{merge, MergePolicy, MergeSource, SSConfig};
+/// let parse = |uri| SSConfig::parse(uri).unwrap();
+/// let free = MergeSource::new("free", [
+///     parse("ss://YWVzLTEyOC1nY206dGVzdA@a.example.com:8388#HK"),
+///     parse("ss://YWVzLTEyOC1nY206dGVzdA@b.example.com:8388#JP"),
+/// ]);
+/// let paid = MergeSource::new("paid", [
+///     parse("ss://YWVzLTEyOC1nY206dGVzdA@b.example.com:8388#Japan"),
+/// ])
+/// .with_priority(10);
+/// let policy = MergePolicy { tag_separator: Some(" | ".to_string()), ..MergePolicy::default() };
+/// let merged = merge([free, paid], &policy);
+/// let tags = merged.iter().map(|c| c.tag.as_deref().unwrap()).collect::<Vec<_>>();
+/// assert_eq!(tags, ["paid | Japan", "free | HK"]);
+/// ```
+pub fn merge(sources: impl IntoIterator<Item = MergeSource>, policy: &MergePolicy) -> ServerList {
+    let mut sources = sources.into_iter().collect::<Vec<_>>();
+    sources.sort_by_key(|source| std::cmp::Reverse(source.priority));
+    let mut seen = Seen::default();
+    let mut merged = ServerList::new();
+    for source in sources {
+        for mut config in source.configs {
+            if !seen.insert(policy.dedup, &config) {
+                continue;
+            }
+            if let Some(separator) = &policy.tag_separator {
+                let tag = entry::name_or_endpoint(&config);
+                config = config.with_tag(format!("{}{separator}{tag}", source.name));
+            }
+            merged.push(config);
+        }
+    }
+    merged
+}
+
+type ServerKey = (url::Host, u16, crate::Method, String, Option<String>);
+
+#[derive(Default)]
+struct Seen {
+    endpoints: HashSet<(url::Host, u16)>,
+    servers: HashSet<ServerKey>,
+    configs: HashSet<SSConfig>,
+}
+
+impl Seen {
+    /// whether the config is the first of its kind
+    fn insert(&mut self, strategy: DedupStrategy, config: &SSConfig) -> bool {
+        match strategy {
+            DedupStrategy::None => true,
+            DedupStrategy::Endpoint => self.endpoints.insert(config.endpoint()),
+            DedupStrategy::SameServer => {
+                let (host, port) = config.endpoint();
+                let plugin = config.plugin().map(|p| p.normalized().to_string());
+                let key = (host, port, config.method, config.password.clone(), plugin);
+                self.servers.insert(key)
+            }
+            DedupStrategy::Identical => self.configs.insert(config.canonicalize()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_strategy_keeps_the_expected_servers() {
+        let parse = |uri| SSConfig::parse(uri).unwrap();
+        let a = MergeSource::new(
+            "a",
+            [
+                parse("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8388#1"),
+                parse("ss://YWVzLTEyOC1nY206b3RoZXI@example.com:8388#2"),
+            ],
+        );
+        let b = MergeSource::new(
+            "b",
+            [
+                parse("ss://YWVzLTEyOC1nY206dGVzdA@EXAMPLE.com:8388#1"),
+                parse("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8388#3"),
+                parse("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8389"),
+            ],
+        );
+        for (dedup, tags) in [
+            (
+                DedupStrategy::None,
+                &["a/1", "a/2", "b/1", "b/3", "b/example.com:8389"][..],
+            ),
+            (DedupStrategy::Endpoint, &["a/1", "b/example.com:8389"]),
+            (
+                DedupStrategy::SameServer,
+                &["a/1", "a/2", "b/example.com:8389"],
+            ),
+            (
+                DedupStrategy::Identical,
+                &["a/1", "a/2", "b/3", "b/example.com:8389"],
+            ),
+        ] {
+            let policy = MergePolicy {
+                dedup,
+                tag_separator: Some("/".to_string()),
+            };
+            let merged = merge([a.clone(), b.clone()], &policy);
+            let merged = merged.iter().map(|c| c.tag.as_deref().unwrap());
+            assert_eq!(merged.collect::<Vec<_>>(), tags, "{dedup:?}");
+        }
+        let merged = merge([a, b.with_priority(1)], &MergePolicy::default());
+        let merged = merged.iter().map(|c| c.tag.as_deref());
+        assert_eq!(merged.collect::<Vec<_>>(), [Some("1"), None, Some("2")]);
+    }
+}
@@ -2,7 +2,7 @@ use core::fmt;
 use std::{self, str::FromStr};
 
 // encryption method
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 pub enum Method {
     Rc4Md5,
     Aes128Gcm,
@@ -25,6 +25,43 @@ pub enum Method {
     Xchacha20IetfPoly130,
 }
 
+/// every name [`Method`] parses from, the canonical names of [`Method::as_str`] and the aliases
+/// other implementations use
+/// ```
+/// use ss_uri::{Method, METHOD_TABLE};
+/// assert_eq!(METHOD_TABLE.get("AEAD_CHACHA20_POLY1305"), Some(&Method::Chacha20IetfPoly1305));
+/// let aliases = METHOD_TABLE.entries().filter(|(name, method)| **name != method.as_str());
+/// assert_eq!(aliases.count(), 5);
+/// ```
+pub static METHOD_TABLE: phf::Map<&'static str, Method> = phf::phf_map! {
+    "rc4-md5" => Method::Rc4Md5,
+    "aes-128-gcm" => Method::Aes128Gcm,
+    "aes-192-gcm" => Method::Aes192Gcm,
+    "aes-256-gcm" => Method::Aes256Gcm,
+    "aes-128-cfb" => Method::Aes128Cfb,
+    "aes-192-cfb" => Method::Aes192Cfb,
+    "aes-256-cfb" => Method::Aes256Cfb,
+    "aes-128-ctr" => Method::Aes128Ctr,
+    "aes-192-ctr" => Method::Aes192Ctr,
+    "aes-256-ctr" => Method::Aes256Ctr,
+    "camellia-128-cfb" => Method::Camellia128Cfb,
+    "camellia-192-cfb" => Method::Camellia192Cfb,
+    "camellia-256-cfb" => Method::Camellia256Cfb,
+    "bf-cfb" => Method::BfCfb,
+    "chacha20-ietf-poly1305" => Method::Chacha20IetfPoly1305,
+    "salsa20" => Method::Salsa20,
+    "chacha20" => Method::Chacha20,
+    "chacha20-ietf" => Method::Chacha20Ietf,
+    "xchacha20-ietf-poly1305" => Method::Xchacha20IetfPoly130,
+    // go-shadowsocks2 and outline-ss-server
+    "AEAD_AES_128_GCM" => Method::Aes128Gcm,
+    "AEAD_AES_256_GCM" => Method::Aes256Gcm,
+    "AEAD_CHACHA20_POLY1305" => Method::Chacha20IetfPoly1305,
+    // shadowsocks-rust and v2ray
+    "chacha20-poly1305" => Method::Chacha20IetfPoly1305,
+    "xchacha20-poly1305" => Method::Xchacha20IetfPoly130,
+};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum MethodParseError {
     UnknownMethod,
@@ -47,28 +84,10 @@ impl TryFrom<&str> for Method {
     type Error = MethodParseError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match value {
-            "rc4-md5" => Ok(Method::Rc4Md5),
-            "aes-128-gcm" => Ok(Method::Aes128Gcm),
-            "aes-192-gcm" => Ok(Method::Aes192Gcm),
-            "aes-256-gcm" => Ok(Method::Aes256Gcm),
-            "aes-128-cfb" => Ok(Method::Aes128Cfb),
-            "aes-192-cfb" => Ok(Method::Aes192Cfb),
-            "aes-256-cfb" => Ok(Method::Aes256Cfb),
-            "aes-128-ctr" => Ok(Method::Aes128Ctr),
-            "aes-192-ctr" => Ok(Method::Aes192Ctr),
-            "aes-256-ctr" => Ok(Method::Aes256Ctr),
-            "camellia-128-cfb" => Ok(Method::Camellia128Cfb),
-            "camellia-192-cfb" => Ok(Method::Camellia192Cfb),
-            "camellia-256-cfb" => Ok(Method::Camellia256Cfb),
-            "bf-cfb" => Ok(Method::BfCfb),
-            "chacha20-ietf-poly1305" => Ok(Method::Chacha20IetfPoly1305),
-            "salsa20" => Ok(Method::Salsa20),
-            "chacha20" => Ok(Method::Chacha20),
-            "chacha20-ietf" => Ok(Method::Chacha20Ietf),
-            "xchacha20-ietf-poly1305" => Ok(Method::Xchacha20IetfPoly130),
-            _ => Err(MethodParseError::UnknownMethod),
-        }
+        METHOD_TABLE
+            .get(value)
+            .copied()
+            .ok_or(MethodParseError::UnknownMethod)
     }
 }
 
@@ -79,6 +98,28 @@ impl From<Method> for String {
 }
 
 impl Method {
+    /// every supported method
+    pub const ALL: [Method; 19] = [
+        Method::Rc4Md5,
+        Method::Aes128Gcm,
+        Method::Aes192Gcm,
+        Method::Aes256Gcm,
+        Method::Aes128Cfb,
+        Method::Aes192Cfb,
+        Method::Aes256Cfb,
+        Method::Aes128Ctr,
+        Method::Aes192Ctr,
+        Method::Aes256Ctr,
+        Method::Camellia128Cfb,
+        Method::Camellia192Cfb,
+        Method::Camellia256Cfb,
+        Method::BfCfb,
+        Method::Chacha20IetfPoly1305,
+        Method::Salsa20,
+        Method::Chacha20,
+        Method::Chacha20Ietf,
+        Method::Xchacha20IetfPoly130,
+    ];
     pub fn as_str(&self) -> &'static str {
         match self {
             Method::Rc4Md5 => "rc4-md5",
@@ -102,6 +143,23 @@ impl Method {
             Method::Xchacha20IetfPoly130 => "xchacha20-ietf-poly1305",
         }
     }
+    /// whether the method is one of the authenticated ciphers of SIP004, the rest are stream
+    /// ciphers
+    /// ```
+    /// use ss_uri::Method;
+    /// assert!(Method::Aes256Gcm.is_aead());
+    /// assert!(!Method::Aes256Cfb.is_aead());
+    /// ```
+    pub fn is_aead(&self) -> bool {
+        matches!(
+            self,
+            Method::Aes128Gcm
+                | Method::Aes192Gcm
+                | Method::Aes256Gcm
+                | Method::Chacha20IetfPoly1305
+                | Method::Xchacha20IetfPoly130
+        )
+    }
 }
 
 impl std::fmt::Debug for Method {
@@ -123,3 +181,22 @@ impl TryFrom<String> for Method {
         TryFrom::<&str>::try_from(&value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_covers_every_method() {
+        for method in Method::ALL {
+            assert_eq!(method.as_str().parse(), Ok(method));
+        }
+        for (name, method) in METHOD_TABLE.entries() {
+            assert_eq!(Method::try_from(*name), Ok(*method));
+        }
+        assert_eq!(
+            "AES-128-GCM".parse::<Method>(),
+            Err(MethodParseError::UnknownMethod)
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! whether a server relays tcp, udp or both, exporters write it as `mode=tcp_and_udp` like
+//! shadowsocks-rust or as a `udp=true` / `udp-relay=true` flag
+use crate::params::flag;
+use crate::{Compat, KnownParam, QueryParams};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Mode {
+    TcpOnly,
+    UdpOnly,
+    TcpAndUdp,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 3] = [Mode::TcpOnly, Mode::UdpOnly, Mode::TcpAndUdp];
+    /// the name shadowsocks-rust and shadowsocks-libev use in their configs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mode::TcpOnly => "tcp_only",
+            Mode::UdpOnly => "udp_only",
+            Mode::TcpAndUdp => "tcp_and_udp",
+        }
+    }
+    /// ```
+    /// use ss_uri::Mode;
+    /// assert_eq!(Mode::parse("tcp_and_udp"), Some(Mode::TcpAndUdp));
+    /// assert_eq!(Mode::parse("both"), None);
+    /// ```
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.as_str() == s)
+    }
+    /// the mode a `udp` flag stands for, tcp is always relayed
+    pub fn from_udp(udp: bool) -> Self {
+        if udp {
+            Mode::TcpAndUdp
+        } else {
+            Mode::TcpOnly
+        }
+    }
+    pub fn udp(&self) -> bool {
+        *self != Mode::TcpOnly
+    }
+    pub fn tcp(&self) -> bool {
+        *self != Mode::UdpOnly
+    }
+    /// removes the parameters describing the mode from `query`, `mode` wins over the udp flags
+    /// and values that can't be understood stay where they are
+    pub(crate) fn take_from(query: &mut QueryParams) -> Option<Self> {
+        let mode = query
+            .get(KnownParam::Mode.key())
+            .and_then(|mode| Mode::parse(mode));
+        if mode.is_some() {
+            query.remove(KnownParam::Mode.key());
+        }
+        let mut udp = None;
+        for key in KnownParam::Udp.aliases() {
+            if let Some(value) = query.get(key).and_then(|value| flag(value)) {
+                query.remove(key);
+                udp = Some(Mode::from_udp(value));
+            }
+        }
+        mode.or(udp)
+    }
+}
+
+impl Compat {
+    /// the query parameter the client reads the mode from, outline ignores it so it gets none
+    pub(crate) fn mode_param(self, mode: Mode) -> Option<(&'static str, &'static str)> {
+        match self {
+            Compat::Outline => None,
+            Compat::Shadowrocket => Some(("udp", if mode.udp() { "1" } else { "0" })),
+            _ => Some(("mode", mode.as_str())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SSConfig;
+
+    #[test]
+    fn reads_every_spelling() {
+        for (query, mode, left) in [
+            ("mode=tcp_and_udp", Some(Mode::TcpAndUdp), &[][..]),
+            ("mode=udp_only", Some(Mode::UdpOnly), &[]),
+            ("udp=true", Some(Mode::TcpAndUdp), &[]),
+            ("udp-relay=0", Some(Mode::TcpOnly), &[]),
+            ("udp=1&mode=tcp_only", Some(Mode::TcpOnly), &[]),
+            ("udp=yes", None, &[("udp", "yes")]),
+            (
+                "mode=both&udp=true",
+                Some(Mode::TcpAndUdp),
+                &[("mode", "both")],
+            ),
+        ] {
+            let uri = format!("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?{query}");
+            let config = SSConfig::parse(&uri).unwrap();
+            assert_eq!(config.mode, mode, "{query}");
+            let extra = config.extra.iter().flatten();
+            let extra = extra.map(|(k, v)| (k.as_str(), v.as_str()));
+            assert_eq!(extra.collect::<Vec<_>>(), left, "{query}");
+        }
+    }
+
+    #[test]
+    fn writes_the_parameter_each_client_reads() {
+        let config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=obfs-local&udp=true",
+        )
+        .unwrap();
+        assert!(config
+            .to_sip002()
+            .ends_with("/?plugin=obfs-local&mode=tcp_and_udp"));
+        assert!(config
+            .to_uri(Compat::ShadowsocksRust)
+            .ends_with("/?plugin=obfs-local&mode=tcp_and_udp"));
+        assert!(config
+            .to_uri(Compat::Shadowrocket)
+            .ends_with("8888?plugin=obfs-local&udp=1"));
+        assert!(config
+            .to_uri(Compat::Outline)
+            .ends_with("/?plugin=obfs-local"));
+        for compat in [Compat::Spec, Compat::Shadowrocket] {
+            let uri = config.to_uri(compat);
+            assert_eq!(SSConfig::parse_with(&uri, compat), Ok(config.clone()));
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! servers reachable on several ports, the way providers list load-balanced endpoints
+use std::collections::{HashMap, HashSet};
+
+use crate::{Method, SSConfig, SSParseError, ServerList};
+
+/// one server listening on several ports, `config` carries everything but the port
+/// ```
+/// use ss_uri::{group_ports, SSConfig};
+/// let configs = [
+///     "ss://YWVzLTEyOC1nY206dGVzdA@example.com:8001#HK%2001",
+///     "ss://YWVzLTEyOC1nY206dGVzdA@example.com:8002#HK%2002",
+///     "ss://YWVzLTEyOC1nY206dGVzdA@example.com:8003#HK%2003",
+///     "ss://YWVzLTEyOC1nY206dGVzdA@example.com:443#HK%20TLS",
+/// ]
+/// .map(|uri| SSConfig::parse(uri).unwrap());
+/// let groups = group_ports(configs.clone());
+/// assert_eq!(groups.len(), 1);
+/// assert_eq!(groups[0].ports_spec(), "8001-8003,443");
+/// assert_eq!(groups[0].config.tag.as_deref(), Some("HK 01"));
+/// let ports = groups[0].expand().iter().map(|c| c.port).collect::<Vec<_>>();
+/// assert_eq!(ports, [8001, 8002, 8003, 443]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MultiPortServer {
+    /// the first config of the group, its port is the first one
+    pub config: SSConfig,
+    /// in the order they were seen, without repeats
+    pub ports: Vec<u16>,
+}
+
+impl MultiPortServer {
+    /// the server on the ports of a spec such as `443,8000-8010`
+    /// ```
+    /// use ss_uri::{MultiPortServer, SSConfig};
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8388").unwrap();
+    /// let server = MultiPortServer::with_ports_spec(config, "443, 8000-8002").unwrap();
+    /// assert_eq!(server.ports, [443, 8000, 8001, 8002]);
+    /// ```
+    pub fn with_ports_spec(config: SSConfig, spec: &str) -> Result<Self, SSParseError> {
+        let mut ports = Vec::new();
+        let mut seen = HashSet::new();
+        for range in spec.split(',') {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let port = |p: &str| {
+                p.trim()
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|p| *p != 0)
+                    .ok_or(SSParseError::InvalidPort)
+            };
+            let (start, end) = (port(start)?, port(end)?);
+            if start > end {
+                return Err(SSParseError::InvalidPort);
+            }
+            ports.extend((start..=end).filter(|port| seen.insert(*port)));
+        }
+        let config = config.with_port(ports[0]);
+        Ok(MultiPortServer { config, ports })
+    }
+    /// the ports with consecutive runs written as ranges, the form
+    /// [`MultiPortServer::with_ports_spec`] reads
+    pub fn ports_spec(&self) -> String {
+        let mut runs: Vec<(u16, u16)> = Vec::new();
+        for &port in &self.ports {
+            match runs.last_mut() {
+                Some((_, end)) if end.checked_add(1) == Some(port) => *end = port,
+                _ => runs.push((port, port)),
+            }
+        }
+        let runs = runs.into_iter().map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{start}-{end}")
+            }
+        });
+        runs.collect::<Vec<_>>().join(",")
+    }
+    /// a config per port, all sharing the tag and extras of `config`
+    pub fn expand(&self) -> Vec<SSConfig> {
+        self.ports
+            .iter()
+            .map(|&port| self.config.clone().with_port(port))
+            .collect()
+    }
+}
+
+/// groups configs sharing host, method, password and plugin, the first config of each group
+/// is kept as its template and groups come in the order their first config did
+pub fn group_ports(configs: impl IntoIterator<Item = SSConfig>) -> Vec<MultiPortServer> {
+    let mut groups: Vec<MultiPortServer> = Vec::new();
+    let mut index: HashMap<_, usize> = HashMap::new();
+    for config in configs {
+        let key = group_key(&config);
+        match index.get(&key) {
+            Some(&i) => {
+                let ports = &mut groups[i].ports;
+                if !ports.contains(&config.port) {
+                    ports.push(config.port);
+                }
+            }
+            None => {
+                index.insert(key, groups.len());
+                groups.push(MultiPortServer {
+                    ports: vec![config.port],
+                    config,
+                });
+            }
+        }
+    }
+    groups
+}
+
+fn group_key(config: &SSConfig) -> (url::Host, Method, String, Option<String>) {
+    let (host, _) = config.endpoint();
+    let plugin = config.plugin().map(|p| p.normalized().to_string());
+    (host, config.method, config.password.clone(), plugin)
+}
+
+impl ServerList {
+    /// see [`group_ports`]
+    pub fn group_ports(self) -> Vec<MultiPortServer> {
+        group_ports(self)
+    }
+}
+
+impl FromIterator<MultiPortServer> for ServerList {
+    /// every server expanded to a config per port
+    fn from_iter<I: IntoIterator<Item = MultiPortServer>>(iter: I) -> Self {
+        iter.into_iter()
+            .flat_map(|server| server.expand())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_identical_credentials_are_grouped() {
+        let configs = [
+            "ss://YWVzLTEyOC1nY206dGVzdA@Example.com:1",
+            "ss://YWVzLTEyOC1nY206b3RoZXI@example.com:2",
+            "ss://YWVzLTEyOC1nY206dGVzdA@example.com:3/?plugin=obfs-local",
+            "ss://YWVzLTEyOC1nY206dGVzdA@example.com:1",
+            "ss://YWVzLTEyOC1nY206dGVzdA@example.com:2",
+        ]
+        .map(|uri| SSConfig::parse(uri).unwrap());
+        let groups = group_ports(configs);
+        let ports = groups.iter().map(|g| g.ports_spec()).collect::<Vec<_>>();
+        assert_eq!(ports, ["1-2", "2", "3"]);
+        let list = groups.into_iter().collect::<ServerList>();
+        assert_eq!(list.len(), 4);
+    }
+
+    #[test]
+    fn port_specs_round_trip() {
+        let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8388").unwrap();
+        let server = MultiPortServer::with_ports_spec(config.clone(), "65534-65535,1,3-4").unwrap();
+        assert_eq!(server.ports_spec(), "65534-65535,1,3-4");
+        assert_eq!(server.config.port, 65534);
+        for spec in ["", "0", "5-3", "1,,2", "70000"] {
+            assert_eq!(
+                MultiPortServer::with_ports_spec(config.clone(), spec),
+                Err(SSParseError::InvalidPort),
+                "{spec}"
+            );
+        }
+    }
+}
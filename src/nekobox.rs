@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{entry, Plugin, SSConfig, SSParseError};
+
+/// profile type of shadowsocks profiles in NekoBox / nekoray
+pub const NEKOBOX_SHADOWSOCKS: &str = "shadowsocks";
+
+/// a NekoBox / nekoray profile as found in its `profiles/*.json` files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NekoboxProfile {
+    #[serde(rename = "type")]
+    pub profile_type: String,
+    pub bean: NekoboxShadowsocksBean,
+}
+
+/// the shadowsocks specific part of a profile, the plugin is kept as a single
+/// `name;options` string
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NekoboxShadowsocksBean {
+    #[serde(rename = "_v", default)]
+    pub version: u32,
+    #[serde(default)]
+    pub name: String,
+    pub addr: String,
+    pub port: u16,
+    pub method: String,
+    pub pass: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub plugin: String,
+    /// udp over tcp, 1 when enabled
+    #[serde(default)]
+    pub uot: u8,
+}
+
+impl NekoboxProfile {
+    /// builds a profile out of a config, `uot` is taken from the `udp-over-tcp` extra parameter
+    /// ```
+    /// use ss_uri::{NekoboxProfile, SSConfig};
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo").unwrap();
+    /// let profile = NekoboxProfile::from_config(&config);
+    /// assert_eq!(profile.bean.name, "Foo");
+    /// assert_eq!(profile.bean.pass, "test");
+    /// ```
+    pub fn from_config(config: &SSConfig) -> Self {
+        let uot = config
+            .extra
+            .as_ref()
+            .and_then(|e| e.get("udp-over-tcp"))
+            .is_some_and(|v| v == "true" || v == "1");
+        Self {
+            profile_type: NEKOBOX_SHADOWSOCKS.to_string(),
+            bean: NekoboxShadowsocksBean {
+                version: 0,
+                name: config.tag.clone().unwrap_or_default(),
+                addr: entry::host_to_string(&config.host),
+                port: config.port,
+                method: config.method.to_string(),
+                pass: config.password.clone(),
+                plugin: config.plugin().map(|p| p.to_string()).unwrap_or_default(),
+                uot: uot.into(),
+            },
+        }
+    }
+    pub fn to_config(&self) -> Result<SSConfig, SSParseError> {
+        let bean = &self.bean;
+        let plugin = Plugin::parse(&bean.plugin);
+        let mut config = entry::build_config(
+            &bean.addr,
+            bean.port,
+            &bean.method,
+            &bean.pass,
+            Some(bean.name.clone()).filter(|n| !n.is_empty()),
+            Some(plugin.name.as_str()),
+            plugin.opts.as_deref(),
+        )?;
+        if bean.uot != 0 {
+            config
+                .extra
+                .get_or_insert_with(Default::default)
+                .insert("udp-over-tcp".to_string(), "true".to_string());
+        }
+        Ok(config)
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("nekobox profile is always serializable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_export_a_profile_with_a_plugin() {
+        let config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888/?plugin=obfs-local%3Bobfs%3Dhttp&udp-over-tcp=true#Foo",
+        )
+        .unwrap();
+        let profile = NekoboxProfile::from_config(&config);
+        let json: serde_json::Value = serde_json::from_str(&profile.to_json()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "shadowsocks",
+                "bean": {
+                    "_v": 0,
+                    "name": "Foo",
+                    "addr": "2001:db8::1",
+                    "port": 8888,
+                    "method": "aes-128-gcm",
+                    "pass": "test",
+                    "plugin": "obfs-local;obfs=http",
+                    "uot": 1
+                }
+            })
+        );
+        assert_eq!(profile.to_config().unwrap(), config);
+    }
+}
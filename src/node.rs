@@ -0,0 +1,234 @@
+//! Node-API bindings, configs cross the boundary as plain objects and failures are thrown as
+//! `Error`s whose `code` names the variant, e.g. `InvalidMethod`
+//! ```js
+//! const { parse, toUri } = require('./ss-uri.node')
+//! const config = parse('ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo')
+//! console.log(config.password, toUri(config, 'outline'))
+//! ```
+use std::collections::HashMap;
+
+use napi::bindgen_prelude::{Buffer, Error, Result};
+use napi_derive::napi;
+
+use crate::{
+    entry, Compat, Mode, OutlinePrefix, QueryParams, SSConfig, SSParseError, Subscription,
+    SubscriptionError,
+};
+
+/// a shadowsocks config as a plain javascript object
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeConfig {
+    pub method: String,
+    pub password: String,
+    /// ipv6 addresses without brackets
+    pub host: String,
+    pub port: u16,
+    pub tag: Option<String>,
+    /// the raw `plugin` parameter
+    pub plugin: Option<String>,
+    /// query parameters other than `plugin`
+    pub extra: HashMap<String, String>,
+    /// outline's salt prefix in its percent-encoded query form
+    pub prefix: Option<String>,
+    /// `tcp_only`, `udp_only` or `tcp_and_udp`
+    pub mode: Option<String>,
+}
+
+/// a decoded subscription as a plain javascript object
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeSubscription {
+    /// one of `base64`, `plain`, `sip008`, `ssd` or `clash`
+    pub source: String,
+    pub configs: Vec<NodeConfig>,
+    pub name: Option<String>,
+    pub bytes_used: Option<i64>,
+    pub bytes_remaining: Option<i64>,
+    pub expiry: Option<String>,
+}
+
+/// the `code` of thrown errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeErrorCode {
+    Parse(SSParseError),
+    Subscription(SubscriptionError),
+    UnknownCompat,
+}
+impl NodeErrorCode {
+    fn as_str(self) -> &'static str {
+        match self {
+            NodeErrorCode::Parse(SSParseError::InvalidUrl) => "InvalidUrl",
+            NodeErrorCode::Parse(SSParseError::InvalidProtocol) => "InvalidProtocol",
+            NodeErrorCode::Parse(SSParseError::InvalidHost) => "InvalidHost",
+            NodeErrorCode::Parse(SSParseError::InvalidPort) => "InvalidPort",
+            NodeErrorCode::Parse(SSParseError::InvalidMethod) => "InvalidMethod",
+            NodeErrorCode::Parse(SSParseError::InvalidPassword) => "InvalidPassword",
+            NodeErrorCode::Parse(SSParseError::InvalidPrefix) => "InvalidPrefix",
+            NodeErrorCode::Parse(SSParseError::InvalidTag) => "InvalidTag",
+            NodeErrorCode::Parse(SSParseError::UnexpectedPath) => "UnexpectedPath",
+            NodeErrorCode::Parse(SSParseError::UnexpectedCharacters) => "UnexpectedCharacters",
+            NodeErrorCode::Parse(SSParseError::LimitExceeded) => "LimitExceeded",
+            NodeErrorCode::Subscription(SubscriptionError::InvalidEncoding) => "InvalidEncoding",
+            NodeErrorCode::Subscription(SubscriptionError::UnknownFormat) => "UnknownFormat",
+            NodeErrorCode::Subscription(SubscriptionError::InvalidDocument) => "InvalidDocument",
+            NodeErrorCode::Subscription(SubscriptionError::LimitExceeded) => "LimitExceeded",
+            NodeErrorCode::Subscription(SubscriptionError::InvalidConfig(e)) => {
+                NodeErrorCode::Parse(e).as_str()
+            }
+            NodeErrorCode::UnknownCompat => "UnknownCompat",
+        }
+    }
+}
+impl AsRef<str> for NodeErrorCode {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+impl From<SSParseError> for Error<NodeErrorCode> {
+    fn from(err: SSParseError) -> Self {
+        Error::new(NodeErrorCode::Parse(err), err.to_string())
+    }
+}
+impl From<SubscriptionError> for Error<NodeErrorCode> {
+    fn from(err: SubscriptionError) -> Self {
+        Error::new(NodeErrorCode::Subscription(err), err.to_string())
+    }
+}
+
+impl From<&SSConfig> for NodeConfig {
+    fn from(config: &SSConfig) -> Self {
+        let mut extra = config.extra.clone().unwrap_or_default();
+        NodeConfig {
+            method: config.method.to_string(),
+            password: config.password.clone(),
+            host: entry::host_to_string(&config.host),
+            port: config.port,
+            tag: config.tag.clone(),
+            plugin: extra.remove("plugin"),
+            extra: extra.into_iter().collect(),
+            prefix: config.prefix.as_ref().map(OutlinePrefix::to_param),
+            mode: config.mode.map(|mode| mode.as_str().to_string()),
+        }
+    }
+}
+
+impl TryFrom<&NodeConfig> for SSConfig {
+    type Error = SSParseError;
+    fn try_from(config: &NodeConfig) -> std::result::Result<Self, Self::Error> {
+        // objects come in without a usable order, sort them so uris stay stable
+        let mut pairs = config.extra.clone().into_iter().collect::<Vec<_>>();
+        pairs.sort();
+        let mut extra = QueryParams::new();
+        if let Some(plugin) = &config.plugin {
+            extra.insert("plugin".to_string(), plugin.clone());
+        }
+        extra.extend(pairs);
+        Ok(SSConfig {
+            host: entry::parse_host(&config.host)?,
+            port: config.port,
+            method: config
+                .method
+                .parse()
+                .map_err(|_| SSParseError::InvalidMethod)?,
+            password: config.password.clone(),
+            tag: config.tag.clone(),
+            tag_raw: None,
+            extra: (!extra.is_empty()).then_some(extra),
+            prefix: config
+                .prefix
+                .as_deref()
+                .map(OutlinePrefix::from_param)
+                .transpose()?,
+            mode: config.mode.as_deref().and_then(Mode::parse),
+        })
+    }
+}
+
+/// client preset by the names javascript callers use, e.g. `shadowsocks-android`
+fn compat(name: Option<&str>) -> Result<Compat, NodeErrorCode> {
+    match name.unwrap_or("spec") {
+        "spec" => Ok(Compat::Spec),
+        "outline" => Ok(Compat::Outline),
+        "shadowsocks-android" => Ok(Compat::ShadowsocksAndroid),
+        "shadowsocks-windows" => Ok(Compat::ShadowsocksWindows),
+        "shadowsocks-rust" => Ok(Compat::ShadowsocksRust),
+        "shadowrocket" => Ok(Compat::Shadowrocket),
+        name => Err(Error::new(
+            NodeErrorCode::UnknownCompat,
+            format!("unknown client {name:?}"),
+        )),
+    }
+}
+
+/// parses an uri, `compat` names the client whose quirks are accepted
+#[napi]
+pub fn parse(uri: String, compat: Option<String>) -> Result<NodeConfig, NodeErrorCode> {
+    let config = SSConfig::parse_with(&uri, self::compat(compat.as_deref())?)?;
+    Ok(NodeConfig::from(&config))
+}
+
+/// serializes a config as a sip002 uri the given client imports
+#[napi]
+pub fn to_uri(config: NodeConfig, compat: Option<String>) -> Result<String, NodeErrorCode> {
+    let compat = self::compat(compat.as_deref())?;
+    Ok(SSConfig::try_from(&config)?.to_uri(compat))
+}
+
+/// serializes a config as a legacy base64 uri
+#[napi]
+pub fn to_legacy_base64(config: NodeConfig) -> Result<String, NodeErrorCode> {
+    Ok(SSConfig::try_from(&config)?.to_legacy_base64_encoded())
+}
+
+/// detects the format of a subscription body and decodes it
+#[napi]
+pub fn parse_subscription(body: Buffer) -> Result<NodeSubscription, NodeErrorCode> {
+    decode_subscription(&body)
+}
+
+fn decode_subscription(body: &[u8]) -> Result<NodeSubscription, NodeErrorCode> {
+    let subscription = Subscription::parse(body)?;
+    let source = match subscription.source {
+        crate::SubscriptionSource::Base64Uris => "base64",
+        crate::SubscriptionSource::PlainUris => "plain",
+        crate::SubscriptionSource::Sip008 => "sip008",
+        crate::SubscriptionSource::Ssd => "ssd",
+        crate::SubscriptionSource::Clash => "clash",
+    };
+    let bytes = |b: u64| i64::try_from(b).unwrap_or(i64::MAX);
+    Ok(NodeSubscription {
+        source: source.to_string(),
+        configs: subscription.configs.iter().map(NodeConfig::from).collect(),
+        name: subscription.name,
+        bytes_used: subscription.bytes_used.map(bytes),
+        bytes_remaining: subscription.bytes_remaining.map(bytes),
+        expiry: subscription.expiry,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configs_round_trip_through_plain_objects() {
+        let uri =
+            "ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888/?plugin=obfs-local%3Bobfs%3Dhttp#Foo";
+        let config = parse(uri.to_string(), None).unwrap();
+        assert_eq!(config.host, "2001:db8::1");
+        assert_eq!(config.plugin.as_deref(), Some("obfs-local;obfs=http"));
+        assert!(config.extra.is_empty());
+        assert_eq!(to_uri(config, None).unwrap(), uri);
+    }
+
+    #[test]
+    fn errors_carry_the_variant_as_code() {
+        let err = parse("ss://Zm9vOnRlc3Q@192.168.100.1:8888".to_string(), None).unwrap_err();
+        assert_eq!(err.status.as_ref(), "InvalidMethod");
+        let err = parse(String::new(), Some("outlook".to_string())).unwrap_err();
+        assert_eq!(err.status.as_ref(), "UnknownCompat");
+        let err = decode_subscription(b"<html></html>").unwrap_err();
+        assert_eq!(err.status.as_ref(), "UnknownFormat");
+    }
+}
@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{entry, SSConfig, SSParseError};
+
+/// protocol name of shadowsocks servers in an OOC document
+pub const OOC_SHADOWSOCKS: &str = "shadowsocks";
+
+/// api access information of an Open Online Config (OOC v1) provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OocApiToken {
+    pub version: u32,
+    #[serde(alias = "apiUrl")]
+    pub base_url: String,
+    pub secret: String,
+    pub user_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cert_sha256: Option<String>,
+}
+
+/// OOC v1 response document, protocols other than shadowsocks are kept as raw json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OocDocument {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_used: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_remaining: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiry_date: Option<String>,
+    pub protocols: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shadowsocks: Vec<OocShadowsocksServer>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// a shadowsocks server entry of an OOC document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OocShadowsocksServer {
+    pub id: String,
+    pub name: String,
+    pub address: String,
+    pub port: u16,
+    pub method: String,
+    pub password: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin_options: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin_arguments: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OocParseError {
+    InvalidJson,
+    UnsupportedVersion,
+}
+impl std::fmt::Display for OocParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for OocParseError {}
+
+impl OocApiToken {
+    pub fn parse(input: &str) -> Result<Self, OocParseError> {
+        let token: Self = serde_json::from_str(input).map_err(|_| OocParseError::InvalidJson)?;
+        if token.version != 1 {
+            return Err(OocParseError::UnsupportedVersion);
+        }
+        Ok(token)
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("OOC api token is always serializable")
+    }
+    /// url the document is requested from: `{baseUrl}/{secret}/ooc/v1/{userId}`
+    /// ```
+    /// use ss_uri::OocApiToken;
+    /// let token = OocApiToken::parse(r#"{
+    ///     "version": 1,
+    ///     "baseUrl": "https://api.example.com/",
+    ///     "secret": "secret",
+    ///     "userId": "user",
+    ///     "certSha256": "AA:BB"
+    /// }"#).unwrap();
+    /// assert_eq!(token.document_url(), "https://api.example.com/secret/ooc/v1/user");
+    /// ```
+    pub fn document_url(&self) -> String {
+        format!(
+            "{}/{}/ooc/v1/{}",
+            self.base_url.trim_end_matches('/'),
+            self.secret,
+            self.user_id
+        )
+    }
+}
+
+impl OocDocument {
+    pub fn parse(input: &str) -> Result<Self, OocParseError> {
+        serde_json::from_str(input).map_err(|_| OocParseError::InvalidJson)
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("OOC document is always serializable")
+    }
+    /// builds a document advertising only the given shadowsocks servers
+    pub fn from_configs<'a, I>(configs: I) -> Self
+    where
+        I: IntoIterator<Item = (String, &'a SSConfig)>,
+    {
+        Self {
+            username: None,
+            bytes_used: None,
+            bytes_remaining: None,
+            expiry_date: None,
+            protocols: vec![OOC_SHADOWSOCKS.to_string()],
+            shadowsocks: configs
+                .into_iter()
+                .map(|(id, config)| OocShadowsocksServer::from_config(id, config))
+                .collect(),
+            other: Map::new(),
+        }
+    }
+    pub fn to_configs(&self) -> Result<Vec<SSConfig>, SSParseError> {
+        self.shadowsocks
+            .iter()
+            .map(OocShadowsocksServer::to_config)
+            .collect()
+    }
+}
+
+impl OocShadowsocksServer {
+    /// builds a server entry out of a config, the tag is used as the name
+    pub fn from_config(id: impl Into<String>, config: &SSConfig) -> Self {
+        let (plugin_name, plugin_options) = entry::plugin_parts(config);
+        Self {
+            id: id.into(),
+            name: config.tag.clone().unwrap_or_default(),
+            address: entry::host_to_string(&config.host),
+            port: config.port,
+            method: config.method.to_string(),
+            password: config.password.clone(),
+            plugin_name,
+            plugin_version: None,
+            plugin_options,
+            plugin_arguments: None,
+            group: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+    }
+    pub fn to_config(&self) -> Result<SSConfig, SSParseError> {
+        entry::build_config(
+            &self.address,
+            self.port,
+            &self.method,
+            &self.password,
+            Some(self.name.clone()).filter(|n| !n.is_empty()),
+            self.plugin_name.as_deref(),
+            self.plugin_options.as_deref(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use url::Host;
+
+    const DOCUMENT: &str = r#"{
+        "username": "database64128",
+        "bytesUsed": 274877906944,
+        "bytesRemaining": 824633720832,
+        "expiryDate": "2023-12-31T23:59:59Z",
+        "protocols": ["shadowsocks", "trojan"],
+        "shadowsocks": [
+            {
+                "id": "27b8a625-4f4b-4428-9f0f-8a2317db7c79",
+                "name": "ServerName",
+                "address": "2001:db8::1",
+                "port": 8388,
+                "method": "chacha20-ietf-poly1305",
+                "password": "example",
+                "pluginName": "v2ray-plugin",
+                "pluginVersion": "1.0",
+                "pluginOptions": "server;tls;host=github.com",
+                "group": "Name of the group",
+                "owner": "Name of the owner",
+                "tags": ["foo", "bar"]
+            }
+        ],
+        "trojan": [{"id": "x"}]
+    }"#;
+
+    #[test]
+    fn can_parse_an_ooc_document() {
+        let document = OocDocument::parse(DOCUMENT).unwrap();
+        assert_eq!(document.bytes_used, Some(274877906944));
+        assert_eq!(document.shadowsocks[0].tags, ["foo", "bar"]);
+        let configs = document.to_configs().unwrap();
+        assert_eq!(configs[0].host, Host::parse("[2001:db8::1]").unwrap());
+        assert_eq!(configs[0].tag, Some("ServerName".to_string()));
+        assert_eq!(
+            configs[0].extra.as_ref().unwrap().get("plugin").unwrap(),
+            "v2ray-plugin;server;tls;host=github.com"
+        );
+    }
+
+    #[test]
+    fn keeps_other_protocols_when_serializing() {
+        let document = OocDocument::parse(DOCUMENT).unwrap();
+        let reparsed = OocDocument::parse(&document.to_json()).unwrap();
+        assert_eq!(reparsed, document);
+        assert!(reparsed.other.contains_key("trojan"));
+    }
+
+    #[test]
+    fn builds_a_document_out_of_configs() {
+        let config =
+            SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo%20Bar").unwrap();
+        let document = OocDocument::from_configs([("id".to_string(), &config)]);
+        assert_eq!(document.protocols, [OOC_SHADOWSOCKS]);
+        assert_eq!(document.shadowsocks[0].name, "Foo Bar");
+        assert_eq!(document.to_configs().unwrap(), vec![config]);
+    }
+
+    #[test]
+    fn accepts_api_url_as_base_url_alias() {
+        let token = OocApiToken::parse(
+            r#"{"version": 1, "apiUrl": "https://api.example.com", "secret": "s", "userId": "u"}"#,
+        )
+        .unwrap();
+        assert_eq!(token.base_url, "https://api.example.com");
+        assert_eq!(token.cert_sha256, None);
+        assert_eq!(OocApiToken::parse(&token.to_json()).unwrap(), token);
+    }
+}
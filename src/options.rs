@@ -0,0 +1,345 @@
+//! knobs for parsing and serializing that stray from the defaults of [`SSConfig::parse`] and
+//! [`SSConfig::to_uri`]
+use percent_encoding::percent_decode_str;
+
+use crate::{entry, QueryOrder, SSConfig, SSParseError};
+
+/// options for [`SSConfig::parse_with_options`], the default is what [`SSConfig::parse`] does
+/// ```
+/// use ss_uri::{ParseOptions, SSConfig, SSParseError};
+/// let uri = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:0";
+/// assert_eq!(SSConfig::parse(uri), Err(SSParseError::InvalidPort));
+/// let lenient = ParseOptions {
+///     allow_port_zero: true,
+///     ..ParseOptions::default()
+/// };
+/// assert_eq!(SSConfig::parse_with_options(uri, &lenient).unwrap().port, 0);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    /// accept `:0`, which no client can connect to
+    pub allow_port_zero: bool,
+    /// fail with [`SSParseError::InvalidTag`] when the fragment decodes to invalid utf-8
+    /// instead of replacing the bad bytes with U+FFFD
+    /// ```
+    /// use ss_uri::{ParseOptions, SSConfig, SSParseError};
+    /// let uri = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#HK%FF01";
+    /// assert_eq!(SSConfig::parse(uri).unwrap().tag.as_deref(), Some("HK\u{FFFD}01"));
+    /// let strict = ParseOptions {
+    ///     strict_tag: true,
+    ///     ..ParseOptions::default()
+    /// };
+    /// assert_eq!(SSConfig::parse_with_options(uri, &strict), Err(SSParseError::InvalidTag));
+    /// ```
+    pub strict_tag: bool,
+    /// read `+` in the fragment as a space the way form encoding does, for subscription
+    /// generators writing remarks like `HK+01`, `%2B` still decodes to a plus
+    /// ```
+    /// use ss_uri::{ParseOptions, SSConfig};
+    /// let uri = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#HK+01%2B";
+    /// let options = ParseOptions {
+    ///     plus_as_space: true,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let config = SSConfig::parse_with_options(uri, &options).unwrap();
+    /// assert_eq!(config.tag.as_deref(), Some("HK 01+"));
+    /// ```
+    pub plus_as_space: bool,
+    /// reject what the url parser quietly accepts, a path after the port of a sip002 uri and
+    /// characters that can't appear in an uri, [`Unexpected::find`] tells what was found
+    /// ```
+    /// use ss_uri::{ParseOptions, SSConfig, SSParseError};
+    /// let uri = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/servers#Foo";
+    /// assert!(SSConfig::parse(uri).is_ok());
+    /// let strict = ParseOptions {
+    ///     strict: true,
+    ///     ..ParseOptions::default()
+    /// };
+    /// assert_eq!(SSConfig::parse_with_options(uri, &strict), Err(SSParseError::UnexpectedPath));
+    /// ```
+    pub strict: bool,
+    /// bounds checked before anything is decoded, for parsers exposed to untrusted input
+    /// ```
+    /// use ss_uri::{Limits, ParseOptions, SSConfig, SSParseError};
+    /// let uri = format!("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?{}", "a=b&".repeat(100));
+    /// let bounded = ParseOptions {
+    ///     limits: Limits::UNTRUSTED,
+    ///     ..ParseOptions::default()
+    /// };
+    /// assert_eq!(SSConfig::parse_with_options(&uri, &bounded), Err(SSParseError::LimitExceeded));
+    /// ```
+    pub limits: Limits,
+}
+
+/// upper bounds on what [`SSConfig::parse_with_options`] and
+/// [`Subscription::parse_with_options`] read, `None` leaves a dimension unbounded as
+/// [`Limits::default`] does for all of them
+///
+/// [`Subscription::parse_with_options`]: crate::Subscription::parse_with_options
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Limits {
+    /// length of the whole uri in bytes
+    pub max_input_len: Option<usize>,
+    /// bytes the base64 user info or legacy payload can decode to, estimated from its length
+    pub max_decoded_len: Option<usize>,
+    /// number of query parameters
+    pub max_query_pairs: Option<usize>,
+    /// length of a whole subscription body in bytes, and of what its base64 or ssd payload
+    /// decodes to
+    pub max_subscription_len: Option<usize>,
+}
+
+impl Limits {
+    /// generous for any real config yet small enough to bound memory and time per uri
+    pub const UNTRUSTED: Limits = Limits {
+        max_input_len: Some(8 * 1024),
+        max_decoded_len: Some(1024),
+        max_query_pairs: Some(32),
+        max_subscription_len: Some(4 * 1024 * 1024),
+    };
+    /// fails with [`SSParseError::LimitExceeded`] when `s` is beyond any of the limits, cheap
+    /// enough to run before parsing as it only splits `s`
+    pub fn check(&self, s: &str) -> Result<(), SSParseError> {
+        let within = |len: usize, max: Option<usize>| max.is_none_or(|max| len <= max);
+        let rest = s.split_once("://").map_or(s, |(_, rest)| rest);
+        let (rest, _) = rest.split_once('#').unwrap_or((rest, ""));
+        let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+        // the user info of sip002 uris, the whole payload of legacy ones
+        let encoded = rest
+            .split_once('@')
+            .map_or(rest, |(user_info, _)| user_info);
+        let pairs = query.split('&').filter(|pair| !pair.is_empty()).count();
+        if within(s.len(), self.max_input_len)
+            && within(encoded.len() / 4 * 3 + 3, self.max_decoded_len)
+            && within(pairs, self.max_query_pairs)
+        {
+            Ok(())
+        } else {
+            Err(SSParseError::LimitExceeded)
+        }
+    }
+}
+
+/// the part of an uri that strict parsing rejects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unexpected<'a> {
+    /// a sip002 path other than `/`, up to the query or fragment
+    Path(&'a str),
+    /// the first character that can't appear in an uri and everything after it
+    Characters(&'a str),
+}
+
+impl<'a> Unexpected<'a> {
+    /// what makes `s` fail [`ParseOptions::strict`], non-ascii characters are let through as
+    /// clients write tags unencoded
+    /// ```
+    /// use ss_uri::Unexpected;
+    /// let uri = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo\" target=\"_blank\">";
+    /// assert_eq!(Unexpected::find(uri), Some(Unexpected::Characters("\" target=\"_blank\">")));
+    /// ```
+    pub fn find(s: &'a str) -> Option<Self> {
+        if let Some(index) = s.find(|c: char| {
+            c.is_whitespace() || c.is_control() || c == '\u{feff}' || "\"<>\\^`{|}".contains(c)
+        }) {
+            return Some(Unexpected::Characters(&s[index..]));
+        }
+        let rest = s.split_once("://").map_or(s, |(_, rest)| rest);
+        let rest = rest.split(['?', '#']).next().unwrap_or_default();
+        // legacy payloads carry no `@` and may contain `/` of the standard alphabet
+        let (_, endpoint) = rest.rsplit_once('@')?;
+        let path = &endpoint[endpoint.find('/')?..];
+        (path != "/").then_some(Unexpected::Path(path))
+    }
+    fn error(self) -> SSParseError {
+        match self {
+            Unexpected::Path(_) => SSParseError::UnexpectedPath,
+            Unexpected::Characters(_) => SSParseError::UnexpectedCharacters,
+        }
+    }
+}
+
+/// options for [`SSConfig::to_uri_with`], the default is what [`SSConfig::to_uri`] does
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SerializeOptions {
+    pub query_order: QueryOrder,
+    /// write spaces in the tag as `+`, the counterpart of [`ParseOptions::plus_as_space`]
+    /// ```
+    /// use ss_uri::{Compat, SSConfig, SerializeOptions};
+    /// let mut config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888").unwrap();
+    /// config.tag = Some("HK 01+".to_string());
+    /// let options = SerializeOptions {
+    ///     plus_for_spaces: true,
+    ///     ..SerializeOptions::default()
+    /// };
+    /// assert!(config.to_uri_with(Compat::Spec, &options).ends_with("#HK+01%2B"));
+    /// ```
+    pub plus_for_spaces: bool,
+    /// the alphabet and padding of the base64 user info or legacy payload, `None` keeps what
+    /// the client preset, [`SSConfig::to_sip002`] or [`SSConfig::to_legacy_base64_encoded`] use
+    /// ```
+    /// use ss_uri::{Base64Alphabet, Base64Variant, Compat, SSConfig, SerializeOptions};
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdD8-Pz8@192.168.100.1:8888").unwrap();
+    /// let options = SerializeOptions {
+    ///     base64: Some(Base64Variant { alphabet: Base64Alphabet::Standard, padded: true }),
+    ///     ..SerializeOptions::default()
+    /// };
+    /// assert_eq!(
+    ///     config.to_uri_with(Compat::Spec, &options),
+    ///     "ss://YWVzLTEyOC1nY206dGVzdD8+Pz8=@192.168.100.1:8888"
+    /// );
+    /// let options = SerializeOptions {
+    ///     base64: Some(Base64Variant::SIP002),
+    ///     ..SerializeOptions::default()
+    /// };
+    /// assert_eq!(
+    ///     config.to_sip002_with(&options),
+    ///     "ss://YWVzLTEyOC1nY206dGVzdD8-Pz8@192.168.100.1:8888/"
+    /// );
+    /// ```
+    pub base64: Option<Base64Variant>,
+    /// write internationalized domains in their unicode form rather than punycode
+    /// ```
+    /// use ss_uri::{Compat, SSConfig, SerializeOptions};
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@xn--r8jz45g.jp:8888").unwrap();
+    /// let options = SerializeOptions {
+    ///     unicode_host: true,
+    ///     ..SerializeOptions::default()
+    /// };
+    /// assert_eq!(
+    ///     config.to_uri_with(Compat::Spec, &options),
+    ///     "ss://YWVzLTEyOC1nY206dGVzdA@例え.jp:8888"
+    /// );
+    /// ```
+    pub unicode_host: bool,
+    /// whether a slash goes between the port and the query, `None` keeps what the client
+    /// preset or [`SSConfig::to_sip002`] does
+    /// ```
+    /// use ss_uri::{SSConfig, SerializeOptions};
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo").unwrap();
+    /// let options = SerializeOptions {
+    ///     slash: Some(false),
+    ///     ..SerializeOptions::default()
+    /// };
+    /// assert_eq!(config.to_sip002_with(&options), "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo");
+    /// ```
+    pub slash: Option<bool>,
+    /// leave out the fragment
+    pub skip_tag: bool,
+    /// leave out the query, plugin and every other parameter included
+    /// ```
+    /// use ss_uri::{Compat, SSConfig, SerializeOptions};
+    /// let config =
+    ///     SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=obfs-local#Foo").unwrap();
+    /// let options = SerializeOptions {
+    ///     skip_query: true,
+    ///     skip_tag: true,
+    ///     ..SerializeOptions::default()
+    /// };
+    /// assert_eq!(config.to_uri_with(Compat::Spec, &options), "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888");
+    /// ```
+    pub skip_query: bool,
+    /// percent-encode every character of the tag but ascii letters and digits, for clients
+    /// that choke on the punctuation rfc 3986 allows in a fragment
+    /// ```
+    /// use ss_uri::{Compat, SSConfig, SerializeOptions};
+    /// let mut config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888").unwrap();
+    /// config.tag = Some("JP-01_(IPLC)".to_string());
+    /// assert!(config.to_uri(Compat::Spec).ends_with("#JP-01_(IPLC)"));
+    /// let options = SerializeOptions {
+    ///     aggressive_tag_encoding: true,
+    ///     ..SerializeOptions::default()
+    /// };
+    /// assert!(config.to_uri_with(Compat::Spec, &options).ends_with("#JP%2D01%5F%28IPLC%29"));
+    /// ```
+    pub aggressive_tag_encoding: bool,
+}
+
+/// the two base64 alphabets found in the wild, either is accepted when parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Base64Alphabet {
+    /// `+` and `/`, what most clients write
+    Standard,
+    /// `-` and `_`, what SIP002 asks for
+    UrlSafe,
+}
+
+/// how base64 output is written, some older clients only import padded base64
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Base64Variant {
+    pub alphabet: Base64Alphabet,
+    pub padded: bool,
+}
+
+impl Base64Variant {
+    /// url-safe and unpadded, as SIP002 specifies
+    pub const SIP002: Self = Base64Variant {
+        alphabet: Base64Alphabet::UrlSafe,
+        padded: false,
+    };
+    /// standard and unpadded, what [`SSConfig::to_sip002`] and
+    /// [`SSConfig::to_legacy_base64_encoded`] write
+    pub const STANDARD_NO_PAD: Self = Base64Variant {
+        alphabet: Base64Alphabet::Standard,
+        padded: false,
+    };
+    pub(crate) fn encode(self, input: impl AsRef<[u8]>) -> String {
+        let config = match (self.alphabet, self.padded) {
+            (Base64Alphabet::Standard, true) => base64::STANDARD,
+            (Base64Alphabet::Standard, false) => base64::STANDARD_NO_PAD,
+            (Base64Alphabet::UrlSafe, true) => base64::URL_SAFE,
+            (Base64Alphabet::UrlSafe, false) => base64::URL_SAFE_NO_PAD,
+        };
+        base64::encode_config(input, config)
+    }
+}
+
+impl ParseOptions {
+    /// applies the options to a config parsed out of `s`
+    pub(crate) fn check(&self, s: &str, mut config: SSConfig) -> Result<SSConfig, SSParseError> {
+        if config.port == 0 && !self.allow_port_zero {
+            return Err(SSParseError::InvalidPort);
+        }
+        if let Some(raw) = config.tag_raw.as_deref().filter(|_| self.plus_as_space) {
+            config.tag = SSConfig::extract_hash(Some(&raw.replace('+', " ")));
+        }
+        // a lossy tag without replacement characters was valid to begin with
+        let replaced = config
+            .tag
+            .as_deref()
+            .is_some_and(|t| t.contains('\u{FFFD}'));
+        if self.strict_tag && replaced {
+            let fragment = s.split_once('#').map_or("", |(_, f)| f);
+            percent_decode_str(fragment)
+                .decode_utf8()
+                .map_err(|_| SSParseError::InvalidTag)?;
+        }
+        Ok(config)
+    }
+}
+
+impl SSConfig {
+    /// [`SSConfig::parse`] with the given options
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(len = s.len()), err(level = "debug"))
+    )]
+    pub fn parse_with_options(s: &str, options: &ParseOptions) -> Result<Self, SSParseError> {
+        options.limits.check(s)?;
+        if let Some(unexpected) = Unexpected::find(s).filter(|_| options.strict) {
+            return Err(unexpected.error());
+        }
+        let s = entry::trim_input(s);
+        let result = Self::parse_sip002_unchecked(s).and_then(|c| options.check(s, c));
+        if result.is_ok() {
+            return result;
+        }
+        #[cfg(feature = "tracing")]
+        tracing::trace!("not a sip002 uri, trying legacy base64");
+        let legacy_result =
+            Self::parse_legacy_base64_unchecked(s).and_then(|c| options.check(s, c));
+        if legacy_result.is_ok() {
+            return legacy_result;
+        }
+        result
+    }
+}
@@ -0,0 +1,464 @@
+use core::fmt;
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+
+use crate::{entry, Method, SSConfig, SSParseError, Sip008Document, Sip008Server, SIP008_VERSION};
+
+/// characters escaped by javascript's `encodeURIComponent`, which is what outline uses
+const URI_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'!')
+    .remove(b'~')
+    .remove(b'*')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')');
+
+/// ciphers outline clients are able to use
+pub const OUTLINE_METHODS: &[Method] = &[
+    Method::Chacha20IetfPoly1305,
+    Method::Aes128Gcm,
+    Method::Aes192Gcm,
+    Method::Aes256Gcm,
+];
+
+/// server config returned by outline dynamic access keys in json form
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OutlineServerConfig {
+    pub server: String,
+    pub server_port: u16,
+    pub password: String,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+}
+
+/// longest prefix outline accepts, it has to fit into the salt
+pub const OUTLINE_MAX_PREFIX_LEN: usize = 16;
+
+/// prefixes recommended by outline's prefix disguise documentation
+pub const OUTLINE_DOCUMENTED_PREFIXES: &[(&str, &[u8])] = &[
+    ("HTTP request", b"POST "),
+    ("HTTP response", b"HTTP/1.1 "),
+    ("DNS-over-TCP request", b"\x05\xdc\x5f\xe0\x01\x20"),
+    ("TLS ClientHello", b"\x16\x03\x01\x00\xa8\x01\x01"),
+    ("TLS Application Data", b"\x13\x03\x03\x3f"),
+    ("TLS ServerHello", b"\x16\x03\x03\x40\x00\x02"),
+    ("SSH", b"SSH-2.0\r\n"),
+];
+
+/// bytes outline puts in front of the salt to disguise the connection as another protocol
+///
+/// in uris and json the prefix is a string whose code points are the bytes, so `0xa8`
+/// travels as `U+00A8` which is percent-encoded as `%C2%A8`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OutlinePrefix(Vec<u8>);
+
+impl OutlinePrefix {
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Result<Self, SSParseError> {
+        let bytes = bytes.into();
+        if bytes.len() > OUTLINE_MAX_PREFIX_LEN {
+            return Err(SSParseError::InvalidPrefix);
+        }
+        Ok(Self(bytes))
+    }
+    /// decodes the (already percent-decoded) string form used by outline
+    pub fn from_param(value: &str) -> Result<Self, SSParseError> {
+        let bytes = value
+            .chars()
+            .map(|c| u8::try_from(c).map_err(|_| SSParseError::InvalidPrefix))
+            .collect::<Result<Vec<u8>, _>>()?;
+        Self::new(bytes)
+    }
+    /// encodes the prefix into the string form used by outline, before percent-encoding
+    pub fn to_param(&self) -> String {
+        self.0.iter().map(|b| char::from(*b)).collect()
+    }
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+    /// name of the documented prefix this one is equal to
+    pub fn documented_name(&self) -> Option<&'static str> {
+        OUTLINE_DOCUMENTED_PREFIXES
+            .iter()
+            .find(|(_, bytes)| *bytes == self.0.as_slice())
+            .map(|(name, _)| *name)
+    }
+    pub fn is_documented(&self) -> bool {
+        self.documented_name().is_some()
+    }
+}
+
+/// response of the outline manager api `GET /access-keys`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineAccessKeys {
+    pub access_keys: Vec<OutlineAccessKey>,
+}
+
+/// an access key as listed by the outline manager api
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineAccessKey {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub password: String,
+    pub port: u16,
+    pub method: String,
+    pub access_url: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutlineKeyError {
+    InvalidJson,
+    InvalidConfig(SSParseError),
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutlineExportError {
+    UnsupportedMethod,
+    UnsupportedPlugin,
+}
+impl fmt::Display for OutlineExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for OutlineExportError {}
+
+impl fmt::Display for OutlineKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for OutlineKeyError {}
+
+impl OutlineServerConfig {
+    pub fn from_config(config: &SSConfig) -> Self {
+        Self {
+            server: entry::host_to_string(&config.host),
+            server_port: config.port,
+            password: config.password.clone(),
+            method: config.method.to_string(),
+            prefix: config.prefix.as_ref().map(OutlinePrefix::to_param),
+        }
+    }
+    pub fn to_config(&self) -> Result<SSConfig, SSParseError> {
+        let mut config = entry::build_config(
+            &self.server,
+            self.server_port,
+            &self.method,
+            &self.password,
+            None,
+            None,
+            None,
+        )?;
+        config.prefix = self
+            .prefix
+            .as_deref()
+            .map(OutlinePrefix::from_param)
+            .transpose()?;
+        Ok(config)
+    }
+}
+
+impl OutlineAccessKeys {
+    /// parses either the `{"accessKeys": [...]}` response or a bare array of keys
+    pub fn parse(input: &str) -> Result<Self, OutlineKeyError> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Response {
+            Wrapped(OutlineAccessKeys),
+            Bare(Vec<OutlineAccessKey>),
+        }
+        match serde_json::from_str(input).map_err(|_| OutlineKeyError::InvalidJson)? {
+            Response::Wrapped(keys) => Ok(keys),
+            Response::Bare(access_keys) => Ok(Self { access_keys }),
+        }
+    }
+    /// converts every key, see [`OutlineAccessKey::to_config`]
+    /// ```
+    /// use ss_uri::OutlineAccessKeys;
+    /// let keys = OutlineAccessKeys::parse(r#"{"accessKeys": [{
+    ///     "id": "0",
+    ///     "name": "Alice",
+    ///     "password": "test",
+    ///     "port": 8888,
+    ///     "method": "aes-128-gcm",
+    ///     "accessUrl": "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?outline=1"
+    /// }]}"#).unwrap();
+    /// let configs = keys.to_configs().unwrap();
+    /// assert_eq!(configs[0].tag, Some("Alice".to_string()));
+    /// assert_eq!(keys.to_sip008().unwrap().servers[0].id, "0");
+    /// ```
+    pub fn to_configs(&self) -> Result<Vec<SSConfig>, OutlineKeyError> {
+        self.access_keys
+            .iter()
+            .map(OutlineAccessKey::to_config)
+            .collect()
+    }
+    /// builds a SIP008 document out of the keys, key ids become server ids
+    pub fn to_sip008(&self) -> Result<Sip008Document, OutlineKeyError> {
+        let servers = self
+            .access_keys
+            .iter()
+            .map(|key| Ok(Sip008Server::from_config(key.id.clone(), &key.to_config()?)))
+            .collect::<Result<Vec<_>, OutlineKeyError>>()?;
+        Ok(Sip008Document {
+            version: SIP008_VERSION,
+            servers,
+            bytes_used: None,
+            bytes_remaining: None,
+        })
+    }
+}
+
+impl OutlineAccessKey {
+    /// the host comes from `accessUrl` while the credentials and port come from the key fields,
+    /// a non empty name becomes the tag
+    pub fn to_config(&self) -> Result<SSConfig, OutlineKeyError> {
+        let mut config =
+            SSConfig::parse(&self.access_url).map_err(OutlineKeyError::InvalidConfig)?;
+        config.method = self
+            .method
+            .parse()
+            .map_err(|_| OutlineKeyError::InvalidConfig(SSParseError::InvalidMethod))?;
+        config.password = self.password.clone();
+        config.port = self.port;
+        if !self.name.is_empty() {
+            config.tag = Some(self.name.clone());
+        }
+        Ok(config)
+    }
+}
+
+impl SSConfig {
+    /// serializes the config as an access key outline clients import
+    ///
+    /// outline only speaks the AEAD ciphers in [`OUTLINE_METHODS`] and has no plugin support so
+    /// those are rejected. the query and the fragment are encoded the way `encodeURIComponent`
+    /// does, extra parameters are sorted by key and `outline=1` is emitted first when `marker` is set.
+    /// the mode is kept as a `mode` parameter and the raw fragment is reused while it decodes to
+    /// the tag without reading `+` as a space, which outline wouldn't.
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo%20Bar").unwrap();
+    /// assert_eq!(
+    ///     config.to_outline_key(true).unwrap(),
+    ///     "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?outline=1#Foo%20Bar"
+    /// );
+    /// ```
+    pub fn to_outline_key(&self, marker: bool) -> Result<String, OutlineExportError> {
+        if !OUTLINE_METHODS.contains(&self.method) {
+            return Err(OutlineExportError::UnsupportedMethod);
+        }
+        let mut params = Vec::new();
+        if marker {
+            params.push(("outline".to_string(), "1".to_string()));
+        }
+        let mode = self.mode.map(|mode| ("mode", mode.as_str()));
+        let mut extra = self
+            .extra
+            .iter()
+            .flatten()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .chain(mode)
+            .collect::<Vec<_>>();
+        extra.sort();
+        for (key, value) in extra {
+            match key {
+                "plugin" => return Err(OutlineExportError::UnsupportedPlugin),
+                "outline" if marker => {}
+                _ => params.push((key.to_string(), value.to_string())),
+            }
+        }
+        if let Some(prefix) = &self.prefix {
+            params.push(("prefix".to_string(), prefix.to_param()));
+        }
+
+        let query = params
+            .iter()
+            .map(|(k, v)| {
+                format!(
+                    "{}={}",
+                    utf8_percent_encode(k, URI_COMPONENT),
+                    utf8_percent_encode(v, URI_COMPONENT)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        let query = if query.is_empty() {
+            query
+        } else {
+            format!("?{query}")
+        };
+        let hash = match (&self.tag, &self.tag_raw) {
+            (Some(tag), Some(raw))
+                if !tag.is_empty() && percent_decode_str(raw).decode_utf8_lossy() == *tag =>
+            {
+                format!("#{raw}")
+            }
+            (Some(tag), _) if !tag.is_empty() => {
+                format!("#{}", utf8_percent_encode(tag, URI_COMPONENT))
+            }
+            _ => String::new(),
+        };
+        Ok(format!(
+            "ss://{}@{}:{}/{query}{hash}",
+            Self::encode_user_info(&self.method, &self.password),
+            Self::get_uri_formatted_host(&self.host),
+            self.port
+        ))
+    }
+}
+
+/// normalizes the body served for an outline dynamic access key into a config, outline
+/// servers answer either with a single ss:// uri or with a json [`OutlineServerConfig`]
+/// ```
+/// use ss_uri::parse_outline_dynamic_key;
+/// let config = parse_outline_dynamic_key(r#"{
+///     "server": "example.com",
+///     "server_port": 443,
+///     "password": "test",
+///     "method": "chacha20-ietf-poly1305"
+/// }"#).unwrap();
+/// assert_eq!(config.port, 443);
+///
+/// let config = parse_outline_dynamic_key("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888\n").unwrap();
+/// assert_eq!(config.password, "test");
+/// ```
+pub fn parse_outline_dynamic_key(body: &str) -> Result<SSConfig, OutlineKeyError> {
+    let body = body.trim();
+    if body.starts_with('{') {
+        let server: OutlineServerConfig =
+            serde_json::from_str(body).map_err(|_| OutlineKeyError::InvalidJson)?;
+        server.to_config().map_err(OutlineKeyError::InvalidConfig)
+    } else {
+        SSConfig::parse(body).map_err(OutlineKeyError::InvalidConfig)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Method, Mode};
+    use url::Host;
+
+    #[test]
+    fn can_parse_a_json_dynamic_key_with_prefix() {
+        let config = parse_outline_dynamic_key(
+            r#"{"server": "2001:db8::1", "server_port": 443, "password": "test", "method": "aes-256-gcm", "prefix": "\u0016\u0003\u0001"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.host, Host::parse("[2001:db8::1]").unwrap());
+        assert_eq!(config.method, Method::Aes256Gcm);
+        assert_eq!(config.prefix.as_ref().unwrap().as_bytes(), b"\x16\x03\x01");
+        assert_eq!(
+            OutlineServerConfig::from_config(&config)
+                .to_config()
+                .unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn validates_outline_prefixes() {
+        assert!(OutlinePrefix::new(*b"POST ").unwrap().is_documented());
+        assert!(!OutlinePrefix::new(*b"GET ").unwrap().is_documented());
+        assert_eq!(
+            OutlinePrefix::new([0; OUTLINE_MAX_PREFIX_LEN + 1]),
+            Err(SSParseError::InvalidPrefix)
+        );
+        let prefix = OutlinePrefix::from_param("\u{5}\u{dc}_\u{e0}\u{1} ").unwrap();
+        assert_eq!(prefix.documented_name(), Some("DNS-over-TCP request"));
+        assert_eq!(prefix.to_param(), "\u{5}\u{dc}_\u{e0}\u{1} ");
+    }
+
+    #[test]
+    fn can_import_outline_manager_access_keys() {
+        let keys = OutlineAccessKeys::parse(
+            r#"[
+                {"id": "0", "name": "", "password": "test", "port": 8888, "method": "aes-128-gcm",
+                 "accessUrl": "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?outline=1", "dataLimit": {"bytes": 1}},
+                {"id": "1", "name": "Bob", "password": "other", "port": 9999, "method": "chacha20-ietf-poly1305",
+                 "accessUrl": "ss://Y2hhY2hhMjAtaWV0Zi1wb2x5MTMwNTpvdGhlcg@192.168.100.1:9999/?outline=1"}
+            ]"#,
+        )
+        .unwrap();
+        let configs = keys.to_configs().unwrap();
+        assert_eq!(configs[0].tag, None);
+        assert_eq!(configs[1].tag, Some("Bob".to_string()));
+        assert_eq!(configs[1].method, Method::Chacha20IetfPoly1305);
+        assert_eq!(configs[1].host, Host::parse("192.168.100.1").unwrap());
+
+        let document = keys.to_sip008().unwrap();
+        assert_eq!(document.servers[1].id, "1");
+        assert_eq!(document.servers[1].remarks, Some("Bob".to_string()));
+    }
+
+    #[test]
+    fn exports_outline_access_keys() {
+        let mut config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888/?prefix=POST%20#JP-01_(IPLC)",
+        )
+        .unwrap();
+        assert_eq!(
+            config.to_outline_key(false).unwrap(),
+            "ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888/?prefix=POST%20#JP-01_(IPLC)"
+        );
+        assert_eq!(
+            config.to_outline_key(true).unwrap(),
+            "ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888/?outline=1&prefix=POST%20#JP-01_(IPLC)"
+        );
+        let reparsed = SSConfig::parse(&config.to_outline_key(false).unwrap()).unwrap();
+        assert_eq!(reparsed, config);
+
+        let with_mode = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?mode=udp_only&z=1#%4A%50+01",
+        )
+        .unwrap();
+        assert_eq!(with_mode.mode, Some(Mode::UdpOnly));
+        let key = with_mode.to_outline_key(true).unwrap();
+        assert_eq!(
+            key,
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?outline=1&mode=udp_only&z=1#%4A%50+01"
+        );
+        let reparsed = SSConfig::parse(&key).unwrap();
+        assert_eq!(reparsed.mode, with_mode.mode);
+        assert_eq!(reparsed.tag, Some("JP+01".to_string()));
+
+        config.method = Method::Rc4Md5;
+        assert_eq!(
+            config.to_outline_key(true),
+            Err(OutlineExportError::UnsupportedMethod)
+        );
+        let config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp",
+        )
+        .unwrap();
+        assert_eq!(
+            config.to_outline_key(true),
+            Err(OutlineExportError::UnsupportedPlugin)
+        );
+    }
+
+    #[test]
+    fn reports_invalid_dynamic_keys() {
+        assert_eq!(
+            parse_outline_dynamic_key("{not json"),
+            Err(OutlineKeyError::InvalidJson)
+        );
+        assert_eq!(
+            parse_outline_dynamic_key(
+                r#"{"server": "example.com", "server_port": 443, "password": "test", "method": "rot13"}"#
+            ),
+            Err(OutlineKeyError::InvalidConfig(SSParseError::InvalidMethod))
+        );
+    }
+}
@@ -0,0 +1,42 @@
+use rayon::prelude::*;
+
+use crate::{SSConfig, SSParseError};
+
+/// parses every uri with [`SSConfig::parse`] on the rayon thread pool, results keep the order
+/// of the input
+/// ```
+/// let results = ss_uri::parse_many_par(&[
+///     "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo",
+///     "vmess://abc",
+/// ]);
+/// assert_eq!(results[0].as_ref().unwrap().tag.as_deref(), Some("Foo"));
+/// assert!(results[1].is_err());
+/// ```
+pub fn parse_many_par<S: AsRef<str> + Sync>(uris: &[S]) -> Vec<Result<SSConfig, SSParseError>> {
+    uris.par_iter()
+        .map(|uri| SSConfig::parse(uri.as_ref()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preserves_input_order() {
+        let uris = (0..10_000)
+            .map(|i| match i % 7 {
+                0 => format!("ss://invalid{i}"),
+                _ => format!("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:{}", i + 1),
+            })
+            .collect::<Vec<_>>();
+        let results = parse_many_par(&uris);
+        assert_eq!(results.len(), uris.len());
+        for (i, result) in results.iter().enumerate() {
+            match result {
+                Ok(config) => assert_eq!(usize::from(config.port), i + 1),
+                Err(_) => assert_eq!(i % 7, 0),
+            }
+        }
+    }
+}
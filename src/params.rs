@@ -0,0 +1,183 @@
+//! typed access to the query parameters clients commonly write, anything else stays untyped in
+//! [`SSConfig::extra`] and is serialized as it was parsed
+use std::borrow::Cow;
+
+use crate::{entry, SSConfig};
+
+/// a query parameter with a known meaning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum KnownParam {
+    /// the SIP003 plugin, see [`SSConfig::plugin`]
+    Plugin,
+    /// the name of the group the server is listed under
+    Group,
+    /// whether the server relays udp, `true` or `false`, read into [`SSConfig::mode`]
+    Udp,
+    /// `tcp_only`, `udp_only` or `tcp_and_udp`, read into [`SSConfig::mode`]
+    Mode,
+    /// whether TCP fast open should be used, `true` or `false`
+    FastOpen,
+    /// marks keys handed out by outline servers
+    Outline,
+}
+
+impl KnownParam {
+    pub const ALL: [KnownParam; 6] = [
+        KnownParam::Plugin,
+        KnownParam::Group,
+        KnownParam::Udp,
+        KnownParam::Mode,
+        KnownParam::FastOpen,
+        KnownParam::Outline,
+    ];
+    /// the key this crate writes
+    pub fn key(&self) -> &'static str {
+        match self {
+            KnownParam::Plugin => "plugin",
+            KnownParam::Group => "group",
+            KnownParam::Udp => "udp",
+            KnownParam::Mode => "mode",
+            KnownParam::FastOpen => "tfo",
+            KnownParam::Outline => "outline",
+        }
+    }
+    /// the keys read as this parameter, the one of [`KnownParam::key`] first
+    pub fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            KnownParam::Plugin => &["plugin"],
+            KnownParam::Group => &["group"],
+            KnownParam::Udp => &["udp", "udp-relay"],
+            KnownParam::Mode => &["mode"],
+            KnownParam::FastOpen => &["tfo", "fast-open", "fast_open"],
+            KnownParam::Outline => &["outline"],
+        }
+    }
+    /// the parameter `key` stands for
+    /// ```
+    /// use ss_uri::KnownParam;
+    /// assert_eq!(KnownParam::from_key("udp-relay"), Some(KnownParam::Udp));
+    /// assert_eq!(KnownParam::from_key("UDP"), None);
+    /// ```
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|param| param.aliases().contains(&key))
+    }
+}
+
+impl SSConfig {
+    /// the last value of a known parameter under any of its keys
+    pub fn known_param(&self, param: KnownParam) -> Option<&str> {
+        self.extra
+            .iter()
+            .flatten()
+            .rev()
+            .find(|(key, _)| param.aliases().contains(&key.as_str()))
+            .map(|(_, value)| value.as_str())
+    }
+    /// the group name, decoded when it was written in base64 as several windows clients do
+    /// ```
+    /// use ss_uri::{Compat, SSConfig};
+    /// let uri = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?group=6aaZ5rivIEhL";
+    /// let config = SSConfig::parse(uri).unwrap();
+    /// assert_eq!(config.group().as_deref(), Some("香港 HK"));
+    /// let config = config.with_group("Asia");
+    /// assert!(config.to_uri(Compat::Spec).ends_with("/?group=Asia"));
+    /// assert!(config.to_uri(Compat::ShadowsocksWindows).ends_with("/?group=QXNpYQ"));
+    /// ```
+    pub fn group(&self) -> Option<Cow<'_, str>> {
+        let group = self.known_param(KnownParam::Group)?;
+        Some(match decode_group(group) {
+            Some(decoded) => Cow::Owned(decoded),
+            None => Cow::Borrowed(group),
+        })
+    }
+    /// the config with the group set to `group` as plain text, see [`SSConfig::group`]
+    pub fn with_group(self, group: impl Into<String>) -> Self {
+        self.with_extra(KnownParam::Group.key(), group)
+    }
+    /// whether [`SSConfig::mode`] includes udp, `None` when no parameter said
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config =
+    ///     SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?udp-relay=true&group=HK")
+    ///         .unwrap();
+    /// assert_eq!(config.udp(), Some(true));
+    /// assert_eq!(config.group().as_deref(), Some("HK"));
+    /// assert_eq!(config.fast_open(), None);
+    /// ```
+    pub fn udp(&self) -> Option<bool> {
+        self.mode.map(|mode| mode.udp())
+    }
+    /// `true` and `1` read as true, `false` and `0` as false, anything else as `None`
+    pub fn fast_open(&self) -> Option<bool> {
+        self.known_param(KnownParam::FastOpen).and_then(flag)
+    }
+    /// the parameters without a known meaning, in the order they were parsed in
+    pub fn unknown_params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.extra
+            .iter()
+            .flatten()
+            .filter(|(key, _)| KnownParam::from_key(key).is_none())
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+/// base64 that decodes to printable text, short values and plain words decode to control
+/// characters or invalid utf-8 and are taken as they are
+fn decode_group(value: &str) -> Option<String> {
+    if value.len() < 4
+        || !value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"+/-_=".contains(&b))
+    {
+        return None;
+    }
+    entry::decode_base64_lenient(value).filter(|decoded| !decoded.chars().any(char::is_control))
+}
+
+pub(crate) fn flag(value: &str) -> Option<bool> {
+    match value {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_params_survive_alongside_known_ones() {
+        let uri = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?group=a&x=1&fast-open=0&udp=yes&group=b&outline=1";
+        let config = SSConfig::parse(uri).unwrap();
+        assert_eq!(config.group().as_deref(), Some("b"));
+        assert_eq!(config.fast_open(), Some(false));
+        assert_eq!(config.udp(), None);
+        assert_eq!(config.known_param(KnownParam::Outline), Some("1"));
+        assert_eq!(config.unknown_params().collect::<Vec<_>>(), [("x", "1")]);
+        assert_eq!(config.to_sip002(), uri);
+        for param in KnownParam::ALL {
+            assert_eq!(param.aliases()[0], param.key());
+            assert_eq!(KnownParam::from_key(param.key()), Some(param));
+        }
+    }
+
+    #[test]
+    fn only_printable_base64_groups_are_decoded() {
+        for (raw, group) in [
+            ("SEs", "SEs"),
+            ("Asia", "Asia"),
+            ("test", "test"),
+            ("AAAA", "AAAA"),
+            ("5pel5pys", "日本"),
+            ("5pel5pys==", "日本"),
+            ("Provider%20A", "Provider A"),
+        ] {
+            let uri = format!("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?group={raw}");
+            let config = SSConfig::parse(&uri).unwrap();
+            assert_eq!(config.group().as_deref(), Some(group), "{raw}");
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use core::fmt;
+use std::str::FromStr;
+
+/// a SIP003 plugin as carried in the `plugin` query parameter, e.g. `obfs-local;obfs=http`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Plugin {
+    pub name: String,
+    /// raw SIP003 option string, still escaped
+    pub opts: Option<String>,
+}
+
+impl Plugin {
+    pub fn new(name: impl Into<String>, opts: Option<String>) -> Self {
+        Self {
+            name: name.into(),
+            opts: opts.filter(|o| !o.is_empty()),
+        }
+    }
+    /// splits the plugin parameter at the first unescaped `;`
+    /// ```
+    /// use ss_uri::Plugin;
+    /// let plugin = Plugin::parse("obfs-local;obfs=http;obfs-host=example.com");
+    /// assert_eq!(plugin.name, "obfs-local");
+    /// assert_eq!(plugin.option("obfs-host"), Some(Some("example.com".to_string())));
+    /// ```
+    pub fn parse(value: &str) -> Self {
+        let name = split_unescaped(value, ';')[0];
+        let opts = value[name.len()..].strip_prefix(';').map(str::to_string);
+        Self::new(name, opts)
+    }
+    /// builds a plugin out of unescaped options, `None` values are emitted as bare flags
+    pub fn from_options<'a, I>(name: impl Into<String>, options: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, Option<&'a str>)>,
+    {
+        let opts = options
+            .into_iter()
+            .map(|(key, value)| match value {
+                Some(value) => format!("{}={}", escape(key), escape(value)),
+                None => escape(key),
+            })
+            .collect::<Vec<_>>()
+            .join(";");
+        Self::new(name, Some(opts))
+    }
+    /// unescaped options in order, flags without a value have `None` as value
+    pub fn options(&self) -> Vec<(String, Option<String>)> {
+        let opts = match &self.opts {
+            Some(opts) => opts,
+            None => return Vec::new(),
+        };
+        split_unescaped(opts, ';')
+            .into_iter()
+            .filter(|o| !o.is_empty())
+            .map(|option| {
+                let key = split_unescaped(option, '=')[0];
+                let value = option[key.len()..].strip_prefix('=').map(unescape);
+                (unescape(key), value)
+            })
+            .collect()
+    }
+    /// the same options escaped only where needed and without empty entries
+    pub(crate) fn normalized(&self) -> Self {
+        let options = self.options();
+        Self::from_options(
+            self.name.as_str(),
+            options.iter().map(|(k, v)| (k.as_str(), v.as_deref())),
+        )
+    }
+    /// value of the last option named `key`, `Some(None)` for flags
+    pub fn option(&self, key: &str) -> Option<Option<String>> {
+        self.options()
+            .into_iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+}
+
+impl fmt::Display for Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.opts {
+            Some(opts) => write!(f, "{};{}", self.name, opts),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl FromStr for Plugin {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Plugin::parse(s))
+    }
+}
+
+fn split_unescaped(input: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in input.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == separator => {
+                parts.push(&input[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+fn escape(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        if matches!(c, '\\' | ';' | '=') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn unescape(input: &str) -> String {
+    let mut unescaped = String::with_capacity(input.len());
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_plugin_options_with_flags_and_escapes() {
+        let plugin = Plugin::parse(r"v2ray-plugin;tls;host=example.com;path=/a\;b\=c");
+        assert_eq!(plugin.name, "v2ray-plugin");
+        assert_eq!(
+            plugin.options(),
+            vec![
+                ("tls".to_string(), None),
+                ("host".to_string(), Some("example.com".to_string())),
+                ("path".to_string(), Some("/a;b=c".to_string())),
+            ]
+        );
+        assert_eq!(plugin.option("tls"), Some(None));
+        assert_eq!(plugin.option("mux"), None);
+    }
+
+    #[test]
+    fn plugin_round_trips_through_options() {
+        let plugin =
+            Plugin::from_options("v2ray-plugin", [("tls", None), ("path", Some("/a;b=c"))]);
+        assert_eq!(plugin.to_string(), r"v2ray-plugin;tls;path=/a\;b\=c");
+        assert_eq!(Plugin::parse(&plugin.to_string()), plugin);
+        assert_eq!(Plugin::parse("obfs-local").opts, None);
+    }
+}
@@ -0,0 +1,158 @@
+//! tcp connect latency of servers, what "test latency" buttons of clients measure
+use core::fmt;
+use std::io;
+use std::net::{SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+
+use crate::SSConfig;
+
+/// a successful connect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TcpProbe {
+    /// the address that answered, the first of the resolved ones that did
+    pub addr: SocketAddr,
+    /// time the tcp handshake took
+    pub latency: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProbeError {
+    /// the host didn't resolve to any address
+    Resolve,
+    TimedOut,
+    Refused,
+    Unreachable,
+    /// the config has no plugin, only with [`SSConfig::probe_tcp_via`]
+    NoPlugin,
+    Other,
+}
+impl fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for ProbeError {}
+
+impl From<io::Error> for ProbeError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::TimedOut => ProbeError::TimedOut,
+            io::ErrorKind::ConnectionRefused => ProbeError::Refused,
+            io::ErrorKind::HostUnreachable | io::ErrorKind::NetworkUnreachable => {
+                ProbeError::Unreachable
+            }
+            _ => ProbeError::Other,
+        }
+    }
+}
+
+impl SSConfig {
+    /// connects to the server and reports how long it took, domains are looked up with
+    /// [`SSConfig::resolve`] first and every address gets `timeout`, blocks until done
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8388").unwrap();
+    /// match config.probe_tcp(Duration::from_secs(3)) {
+    ///     Ok(probe) => println!("{} ms via {}", probe.latency.as_millis(), probe.addr),
+    ///     Err(err) => println!("{err}"),
+    /// }
+    /// ```
+    pub fn probe_tcp(&self, timeout: Duration) -> Result<TcpProbe, ProbeError> {
+        let addrs = self.resolve().map_err(|_| ProbeError::Resolve)?;
+        let mut result = Err(ProbeError::Resolve);
+        for addr in addrs {
+            let start = Instant::now();
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(_) => {
+                    return Ok(TcpProbe {
+                        addr,
+                        latency: start.elapsed(),
+                    })
+                }
+                Err(err) => result = Err(err.into()),
+            }
+        }
+        result
+    }
+    /// [`SSConfig::probe_tcp`] against the local address a running SIP003 plugin listens on,
+    /// which tells whether the plugin is up rather than how far the server is
+    pub fn probe_tcp_via(
+        &self,
+        plugin_addr: SocketAddr,
+        timeout: Duration,
+    ) -> Result<TcpProbe, ProbeError> {
+        if self.plugin().is_none() {
+            return Err(ProbeError::NoPlugin);
+        }
+        let start = Instant::now();
+        TcpStream::connect_timeout(&plugin_addr, timeout)?;
+        Ok(TcpProbe {
+            addr: plugin_addr,
+            latency: start.elapsed(),
+        })
+    }
+    /// [`SSConfig::probe_tcp`] on tokio, for probing many servers at once
+    pub async fn probe_tcp_async(&self, timeout: Duration) -> Result<TcpProbe, ProbeError> {
+        let addrs = match self.socket_addr() {
+            Some(addr) => vec![addr],
+            None => tokio::net::lookup_host((self.host_ascii(), self.port))
+                .await
+                .map_err(|_| ProbeError::Resolve)?
+                .collect(),
+        };
+        let mut result = Err(ProbeError::Resolve);
+        for addr in addrs {
+            let start = Instant::now();
+            match tokio::time::timeout(timeout, tokio::net::TcpStream::connect(addr)).await {
+                Ok(Ok(_)) => {
+                    return Ok(TcpProbe {
+                        addr,
+                        latency: start.elapsed(),
+                    })
+                }
+                Ok(Err(err)) => result = Err(err.into()),
+                Err(_) => result = Err(ProbeError::TimedOut),
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn measures_listening_servers_and_reports_refusals() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let uri = format!("ss://YWVzLTEyOC1nY206dGVzdA@{addr}");
+        let config = SSConfig::parse(&uri).unwrap();
+        let timeout = Duration::from_secs(5);
+        assert_eq!(config.probe_tcp(timeout).unwrap().addr, addr);
+        assert_eq!(
+            config.probe_tcp_via(addr, timeout),
+            Err(ProbeError::NoPlugin)
+        );
+        let with_plugin = config.clone().with_extra("plugin", "obfs-local");
+        assert_eq!(with_plugin.probe_tcp_via(addr, timeout).unwrap().addr, addr);
+        drop(listener);
+        assert_eq!(config.probe_tcp(timeout), Err(ProbeError::Refused));
+    }
+
+    #[tokio::test]
+    async fn probes_asynchronously() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let uri = format!("ss://YWVzLTEyOC1nY206dGVzdA@localhost:{port}");
+        let config = SSConfig::parse(&uri).unwrap();
+        let probe = config
+            .probe_tcp_async(Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(probe.addr.ip().is_loopback() && probe.addr.port() == port);
+    }
+}
@@ -0,0 +1,212 @@
+//! python bindings, built into the `ss_uri` extension module with `maturin build --features python`
+//! ```python
+//! import ss_uri
+//! config = ss_uri.parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo")
+//! assert config.password == "test"
+//! print(config.to_uri("outline"))
+//! ```
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::{Compat, SSConfig, Subscription, SubscriptionSource};
+
+create_exception!(
+    ss_uri,
+    ParseError,
+    PyValueError,
+    "the input isn't a valid shadowsocks uri"
+);
+create_exception!(
+    ss_uri,
+    SubscriptionError,
+    PyValueError,
+    "the subscription body couldn't be decoded"
+);
+
+/// a parsed shadowsocks config, exposed to python as `ss_uri.Config`
+#[pyclass(name = "Config", module = "ss_uri", frozen, eq, skip_from_py_object)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyConfig {
+    config: SSConfig,
+}
+
+/// a decoded subscription, exposed to python as `ss_uri.Subscription`
+#[pyclass(
+    name = "Subscription",
+    module = "ss_uri",
+    frozen,
+    get_all,
+    skip_from_py_object
+)]
+#[derive(Debug, Clone)]
+pub struct PySubscription {
+    /// one of `base64`, `plain`, `sip008`, `ssd` or `clash`
+    source: &'static str,
+    configs: Vec<PyConfig>,
+    name: Option<String>,
+    bytes_used: Option<u64>,
+    bytes_remaining: Option<u64>,
+    expiry: Option<String>,
+}
+
+/// client preset by the names python callers use, e.g. `shadowsocks-android`
+fn compat(name: &str) -> PyResult<Compat> {
+    match name {
+        "spec" => Ok(Compat::Spec),
+        "outline" => Ok(Compat::Outline),
+        "shadowsocks-android" => Ok(Compat::ShadowsocksAndroid),
+        "shadowsocks-windows" => Ok(Compat::ShadowsocksWindows),
+        "shadowsocks-rust" => Ok(Compat::ShadowsocksRust),
+        "shadowrocket" => Ok(Compat::Shadowrocket),
+        _ => Err(PyValueError::new_err(format!("unknown client {name:?}"))),
+    }
+}
+
+fn source_name(source: SubscriptionSource) -> &'static str {
+    match source {
+        SubscriptionSource::Base64Uris => "base64",
+        SubscriptionSource::PlainUris => "plain",
+        SubscriptionSource::Sip008 => "sip008",
+        SubscriptionSource::Ssd => "ssd",
+        SubscriptionSource::Clash => "clash",
+    }
+}
+
+#[pymethods]
+impl PyConfig {
+    /// parses an uri, `compat` names the client whose quirks are accepted
+    #[staticmethod]
+    #[pyo3(signature = (uri, compat = "spec"))]
+    fn parse(uri: &str, compat: &str) -> PyResult<Self> {
+        SSConfig::parse_with(uri, self::compat(compat)?)
+            .map(|config| PyConfig { config })
+            .map_err(|e| ParseError::new_err(e.to_string()))
+    }
+    #[getter]
+    fn method(&self) -> &'static str {
+        self.config.method.as_str()
+    }
+    #[getter]
+    fn password(&self) -> &str {
+        &self.config.password
+    }
+    /// ipv6 addresses in brackets
+    #[getter]
+    fn host(&self) -> String {
+        self.config.host.to_string()
+    }
+    #[getter]
+    fn port(&self) -> u16 {
+        self.config.port
+    }
+    #[getter]
+    fn tag(&self) -> Option<&str> {
+        self.config.tag.as_deref()
+    }
+    /// the raw `plugin` parameter
+    #[getter]
+    fn plugin(&self) -> Option<String> {
+        self.config.plugin().map(|p| p.to_string())
+    }
+    /// `tcp_only`, `udp_only` or `tcp_and_udp`
+    #[getter]
+    fn mode(&self) -> Option<&'static str> {
+        self.config.mode.map(|mode| mode.as_str())
+    }
+    /// the extra query parameters in the order they appeared
+    #[getter]
+    fn extra<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for (key, value) in self.config.extra.iter().flatten() {
+            dict.set_item(key, value)?;
+        }
+        Ok(dict)
+    }
+    fn to_sip002(&self) -> String {
+        self.config.to_sip002()
+    }
+    #[pyo3(signature = (compat = "spec"))]
+    fn to_uri(&self, compat: &str) -> PyResult<String> {
+        Ok(self.config.to_uri(self::compat(compat)?))
+    }
+    fn to_legacy_base64(&self) -> String {
+        self.config.to_legacy_base64_encoded()
+    }
+    fn __str__(&self) -> String {
+        self.config.to_uri(Compat::Spec)
+    }
+    fn __repr__(&self) -> String {
+        format!("Config({:?})", self.config.to_uri(Compat::Spec))
+    }
+}
+
+/// parses an uri, `compat` names the client whose quirks are accepted
+#[pyfunction]
+#[pyo3(signature = (uri, compat = "spec"))]
+fn parse(uri: &str, compat: &str) -> PyResult<PyConfig> {
+    PyConfig::parse(uri, compat)
+}
+
+/// detects the format of a subscription body and decodes it
+#[pyfunction]
+fn parse_subscription(body: &[u8]) -> PyResult<PySubscription> {
+    let subscription =
+        Subscription::parse(body).map_err(|e| SubscriptionError::new_err(e.to_string()))?;
+    Ok(PySubscription {
+        source: source_name(subscription.source),
+        configs: subscription
+            .configs
+            .into_iter()
+            .map(|config| PyConfig { config })
+            .collect(),
+        name: subscription.name,
+        bytes_used: subscription.bytes_used,
+        bytes_remaining: subscription.bytes_remaining,
+        expiry: subscription.expiry,
+    })
+}
+
+#[pymodule]
+fn ss_uri(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyConfig>()?;
+    m.add_class::<PySubscription>()?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_subscription, m)?)?;
+    m.add("ParseError", m.py().get_type::<ParseError>())?;
+    m.add("SubscriptionError", m.py().get_type::<SubscriptionError>())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_parsing_and_serialization() {
+        let config = parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo", "spec").unwrap();
+        assert_eq!(config.method(), "aes-128-gcm");
+        assert_eq!(config.host(), "192.168.100.1");
+        assert_eq!(config.tag(), Some("Foo"));
+        assert_eq!(
+            config.to_uri("outline").unwrap(),
+            "ss://YWVzLTEyOC1nY206dGVzdA==@192.168.100.1:8888/#Foo"
+        );
+        assert!(config.to_uri("outlook").is_err());
+        assert!(parse("ss://aes-128-gcm:test@192.168.100.1:8888", "spec").is_err());
+        assert!(parse(
+            "ss://aes-128-gcm:test@192.168.100.1:8888",
+            "shadowsocks-rust"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn wraps_subscriptions() {
+        let subscription =
+            parse_subscription(b"ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo\n").unwrap();
+        assert_eq!(subscription.source, "plain");
+        assert_eq!(subscription.configs.len(), 1);
+    }
+}
@@ -0,0 +1,73 @@
+//! qr codes of sip002 uris, the way mobile clients import servers
+use image::{GrayImage, Luma};
+use qrcode::render::{svg, unicode};
+use qrcode::types::QrError;
+use qrcode::QrCode;
+
+use crate::{Compat, SSConfig};
+
+impl SSConfig {
+    /// the qr code of [`SSConfig::to_uri`] with [`Compat::Spec`], failing only for uris too long
+    /// to fit in a qr code
+    pub fn to_qr(&self) -> Result<QrCode, QrError> {
+        QrCode::new(self.to_uri(Compat::Spec))
+    }
+
+    /// the qr code as a grayscale image with a quiet zone, each module a few pixels wide
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo").unwrap();
+    /// let image = config.to_qr_image().unwrap();
+    /// assert_eq!(image.width(), image.height());
+    /// ```
+    pub fn to_qr_image(&self) -> Result<GrayImage, QrError> {
+        Ok(self.to_qr()?.render::<Luma<u8>>().build())
+    }
+
+    /// the qr code as a standalone svg document
+    pub fn to_qr_svg(&self) -> Result<String, QrError> {
+        Ok(self.to_qr()?.render::<svg::Color>().build())
+    }
+
+    /// the qr code drawn with unicode half blocks, two modules per character, for printing to
+    /// a terminal with a dark background
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo").unwrap();
+    /// println!("{}", config.to_qr_unicode().unwrap());
+    /// ```
+    pub fn to_qr_unicode(&self) -> Result<String, QrError> {
+        Ok(self
+            .to_qr()?
+            .render::<unicode::Dense1x2>()
+            .dark_color(unicode::Dense1x2::Light)
+            .light_color(unicode::Dense1x2::Dark)
+            .build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_every_output() {
+        let config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=obfs-local#Foo",
+        )
+        .unwrap();
+        let width = config.to_qr().unwrap().width();
+        let image = config.to_qr_image().unwrap();
+        assert_eq!(image.width() % (width as u32 + 8), 0);
+        assert!(config.to_qr_svg().unwrap().contains("<svg"));
+        let rows = config.to_qr_unicode().unwrap().lines().count();
+        assert_eq!(rows, (width + 8).div_ceil(2));
+    }
+
+    #[test]
+    fn long_uris_dont_fit() {
+        let mut config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888").unwrap();
+        config.password = "a".repeat(4000);
+        assert_eq!(config.to_qr().err(), Some(QrError::DataTooLong));
+    }
+}
@@ -0,0 +1,91 @@
+//! reading configs back out of qr code screenshots and photos
+use core::fmt;
+
+use image::GrayImage;
+use rqrr::PreparedImage;
+
+use crate::{SSConfig, SSParseError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QrDecodeError {
+    /// the bytes aren't a png or jpeg image
+    InvalidImage,
+    /// no qr code was found in the image
+    NotFound,
+    /// qr codes were found but none could be read
+    Unreadable,
+    /// the qr code holds something other than a shadowsocks uri
+    InvalidConfig(SSParseError),
+}
+impl fmt::Display for QrDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for QrDecodeError {}
+
+impl SSConfig {
+    /// finds the qr code in a png or jpeg image and parses the uri it holds, when the image
+    /// shows several codes the first one holding a config wins
+    pub fn from_qr_image(bytes: &[u8]) -> Result<Self, QrDecodeError> {
+        let image = image::load_from_memory(bytes).map_err(|_| QrDecodeError::InvalidImage)?;
+        Self::from_qr_luma(image.to_luma8())
+    }
+
+    /// [`SSConfig::from_qr_image`] for an image that is already decoded
+    pub fn from_qr_luma(image: GrayImage) -> Result<Self, QrDecodeError> {
+        let mut image = PreparedImage::prepare(image);
+        let grids = image.detect_grids();
+        if grids.is_empty() {
+            return Err(QrDecodeError::NotFound);
+        }
+        let mut error = QrDecodeError::Unreadable;
+        for grid in grids {
+            let content = match grid.decode() {
+                Ok((_, content)) => content,
+                Err(_) => continue,
+            };
+            match SSConfig::parse(&content) {
+                Ok(config) => return Ok(config),
+                Err(e) if error == QrDecodeError::Unreadable => {
+                    error = QrDecodeError::InvalidConfig(e)
+                }
+                Err(_) => {}
+            }
+        }
+        Err(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "qr")]
+    #[test]
+    fn reads_back_rendered_codes() {
+        use std::io::Cursor;
+
+        let config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp#日本",
+        )
+        .unwrap();
+        let mut png = Vec::new();
+        config
+            .to_qr_image()
+            .unwrap()
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        assert_eq!(SSConfig::from_qr_image(&png), Ok(config));
+    }
+
+    #[test]
+    fn reports_what_went_wrong() {
+        assert_eq!(
+            SSConfig::from_qr_image(b"not an image"),
+            Err(QrDecodeError::InvalidImage)
+        );
+        let blank = GrayImage::from_pixel(64, 64, image::Luma([255]));
+        assert_eq!(SSConfig::from_qr_luma(blank), Err(QrDecodeError::NotFound));
+    }
+}
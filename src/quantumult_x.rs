@@ -0,0 +1,247 @@
+use core::fmt;
+
+use crate::{entry, Mode, Plugin, QueryParams, SSConfig, SSParseError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuantumultXError {
+    InvalidLine,
+    UnsupportedType,
+    InvalidConfig(SSParseError),
+    /// quantumult x only understands simple-obfs and websocket v2ray-plugin
+    UnsupportedPlugin,
+}
+impl fmt::Display for QuantumultXError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for QuantumultXError {}
+
+impl SSConfig {
+    /// parses a quantumult x server line, `obfs=http`/`tls` become an `obfs-local` plugin and
+    /// `obfs=ws`/`wss` a `v2ray-plugin` one, `udp-relay` is read into [`SSConfig::mode`]
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse_quantumult_x_line(
+    ///     "shadowsocks=example.com:443, method=aes-128-gcm, password=pwd, obfs=wss, obfs-host=example.com, obfs-uri=/ws, tag=Sample",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(config.tag, Some("Sample".to_string()));
+    /// assert_eq!(config.plugin().unwrap().to_string(), "v2ray-plugin;tls;host=example.com;path=/ws");
+    /// ```
+    pub fn parse_quantumult_x_line(line: &str) -> Result<SSConfig, QuantumultXError> {
+        let fields = entry::split_fields(line);
+        let (proxy_type, endpoint) =
+            entry::split_pair(fields[0]).ok_or(QuantumultXError::InvalidLine)?;
+        if proxy_type != "shadowsocks" {
+            return Err(QuantumultXError::UnsupportedType);
+        }
+        let (server, port) = endpoint
+            .rsplit_once(':')
+            .ok_or(QuantumultXError::InvalidLine)?;
+        let server = server
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .unwrap_or(server);
+        let port = port.parse().map_err(|_| QuantumultXError::InvalidLine)?;
+        let mut method = None;
+        let mut password = None;
+        let mut tag = None;
+        let mut obfs = None;
+        let mut obfs_host = None;
+        let mut obfs_uri = None;
+        let mut extra = QueryParams::new();
+        let pairs = fields[1..]
+            .iter()
+            .map(|field| entry::split_pair(field))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(QuantumultXError::InvalidLine)?;
+        for (key, value) in &pairs {
+            let (key, value) = (*key, value.as_ref());
+            match key {
+                "method" => method = Some(value),
+                "password" => password = Some(value),
+                "tag" => tag = Some(value.to_string()),
+                "obfs" => obfs = Some(value),
+                "obfs-host" => obfs_host = Some(value),
+                "obfs-uri" => obfs_uri = Some(value),
+                "udp-relay" => {
+                    extra.insert("udp".to_string(), value.to_string());
+                }
+                key => {
+                    extra.insert(key.to_string(), value.to_string());
+                }
+            }
+        }
+        let plugin = match obfs {
+            None => None,
+            Some(mode @ ("http" | "tls")) => Some(Plugin::from_options(
+                "obfs-local",
+                [
+                    Some(("obfs", Some(mode))),
+                    obfs_host.map(|host| ("obfs-host", Some(host))),
+                    obfs_uri.map(|uri| ("obfs-uri", Some(uri))),
+                ]
+                .into_iter()
+                .flatten(),
+            )),
+            Some(mode @ ("ws" | "wss")) => Some(Plugin::from_options(
+                "v2ray-plugin",
+                [
+                    (mode == "wss").then_some(("tls", None)),
+                    obfs_host.map(|host| ("host", Some(host))),
+                    obfs_uri.map(|uri| ("path", Some(uri))),
+                ]
+                .into_iter()
+                .flatten(),
+            )),
+            Some(_) => return Err(QuantumultXError::UnsupportedPlugin),
+        };
+        let mut config = entry::build_config(
+            server,
+            port,
+            method.ok_or(QuantumultXError::InvalidLine)?,
+            password.ok_or(QuantumultXError::InvalidLine)?,
+            tag.filter(|t| !t.is_empty()),
+            plugin.as_ref().map(|p| p.name.as_str()),
+            plugin.as_ref().and_then(|p| p.opts.as_deref()),
+        )
+        .map_err(QuantumultXError::InvalidConfig)?;
+        config.mode = Mode::take_from(&mut extra);
+        if !extra.is_empty() {
+            config
+                .extra
+                .get_or_insert_with(QueryParams::new)
+                .extend(extra);
+        }
+        Ok(config)
+    }
+    /// serializes the config as a quantumult x server line, the tag is emitted last
+    pub fn to_quantumult_x_line(&self) -> Result<String, QuantumultXError> {
+        let mut line = format!(
+            "shadowsocks={}:{}, method={}, password={}",
+            SSConfig::get_uri_formatted_host(&self.host),
+            self.port,
+            self.method,
+            entry::quote(&self.password)
+        );
+        if let Some(plugin) = self.plugin() {
+            line.push_str(&quantumult_x_obfs(&plugin)?);
+        }
+        let mut extra = self.extra.iter().flatten().collect::<Vec<_>>();
+        extra.sort();
+        for (key, value) in extra {
+            let key = match key.as_str() {
+                "plugin" => continue,
+                "udp" => "udp-relay",
+                key => key,
+            };
+            line.push_str(&format!(", {}={}", key, entry::quote(value)));
+        }
+        if let Some(mode) = self.mode {
+            line.push_str(&format!(", udp-relay={}", mode.udp()));
+        }
+        line.push_str(&format!(
+            ", tag={}",
+            entry::quote(&entry::name_or_endpoint(self))
+        ));
+        Ok(line)
+    }
+}
+
+fn quantumult_x_obfs(plugin: &Plugin) -> Result<String, QuantumultXError> {
+    let mut mode = None;
+    let mut host = None;
+    let mut uri = None;
+    match plugin.name.as_str() {
+        "obfs-local" | "simple-obfs" => {
+            for (key, value) in plugin.options() {
+                match (key.as_str(), value) {
+                    ("obfs", Some(obfs)) => mode = Some(obfs),
+                    ("obfs-host", Some(obfs_host)) => host = Some(obfs_host),
+                    ("obfs-uri", Some(obfs_uri)) => uri = Some(obfs_uri),
+                    _ => return Err(QuantumultXError::UnsupportedPlugin),
+                }
+            }
+        }
+        "v2ray-plugin" => {
+            mode = Some("ws".to_string());
+            for (key, value) in plugin.options() {
+                match (key.as_str(), value) {
+                    ("tls", None) => mode = Some("wss".to_string()),
+                    ("host", Some(ws_host)) => host = Some(ws_host),
+                    ("path", Some(path)) => uri = Some(path),
+                    ("mode", Some(ws)) if ws == "websocket" => {}
+                    ("mux", _) => {}
+                    _ => return Err(QuantumultXError::UnsupportedPlugin),
+                }
+            }
+        }
+        _ => return Err(QuantumultXError::UnsupportedPlugin),
+    }
+    let mut options = format!(
+        ", obfs={}",
+        mode.ok_or(QuantumultXError::UnsupportedPlugin)?
+    );
+    for (key, value) in [("obfs-host", host), ("obfs-uri", uri)] {
+        if let Some(value) = value {
+            options.push_str(&format!(", {}={}", key, entry::quote(&value)));
+        }
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use url::Host;
+
+    #[test]
+    fn can_parse_quantumult_x_lines() {
+        let config = SSConfig::parse_quantumult_x_line(
+            r#"shadowsocks=[2001:db8::1]:443, method=chacha20-ietf-poly1305, password="p,w=d", obfs=http, obfs-host=bing.com, obfs-uri=/resource/file, fast-open=false, udp-relay=false, tag=Sample-A"#,
+        )
+        .unwrap();
+        assert_eq!(config.host, Host::parse("[2001:db8::1]").unwrap());
+        assert_eq!(config.password, "p,w=d");
+        assert_eq!(
+            config.plugin().unwrap().to_string(),
+            "obfs-local;obfs=http;obfs-host=bing.com;obfs-uri=/resource/file"
+        );
+        let extra = config.extra.as_ref().unwrap();
+        assert_eq!(extra["fast-open"], "false");
+        assert!(!extra.contains_key("udp"));
+        assert_eq!(config.mode, Some(Mode::TcpOnly));
+    }
+
+    #[test]
+    fn quantumult_x_lines_round_trip() {
+        for line in [
+            r#"shadowsocks=[2001:db8::1]:443, method=aes-128-gcm, password="p,w", obfs=tls, obfs-host=bing.com, fast-open=true, tag="a, b""#,
+            "shadowsocks=example.com:80, method=aes-256-gcm, password=pwd, obfs=ws, obfs-uri=/ws, udp-relay=true, tag=ws",
+        ] {
+            let config = SSConfig::parse_quantumult_x_line(line).unwrap();
+            assert_eq!(config.to_quantumult_x_line().unwrap(), line);
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_lines() {
+        assert_eq!(
+            SSConfig::parse_quantumult_x_line("vmess=example.com:443, method=none, tag=v"),
+            Err(QuantumultXError::UnsupportedType)
+        );
+        assert_eq!(
+            SSConfig::parse_quantumult_x_line("shadowsocks=example.com, method=aes-128-gcm"),
+            Err(QuantumultXError::InvalidLine)
+        );
+        let config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=v2ray-plugin%3Bmode%3Dquic",
+        )
+        .unwrap();
+        assert_eq!(
+            config.to_quantumult_x_line(),
+            Err(QuantumultXError::UnsupportedPlugin)
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//! the extra query parameters of a config, kept in the order they were parsed in and with
+//! repeated keys intact
+use core::ops::Index;
+use std::{iter, slice, vec};
+
+/// query parameters other than `prefix`, serialized in insertion order so uris come out the
+/// same way they went in, a key may appear more than once
+/// ```
+/// use ss_uri::SSConfig;
+/// let uri = "ss://YWVzLTEyOC1nY206dGVzdA@example.com:8888/?plugin=obfs-local&group=a&b=2&group=b";
+/// let config = SSConfig::parse(uri).unwrap();
+/// let extra = config.extra.as_ref().unwrap();
+/// assert_eq!(extra.keys().collect::<Vec<_>>(), ["plugin", "group", "b", "group"]);
+/// assert_eq!(extra.get_all("group").collect::<Vec<_>>(), ["a", "b"]);
+/// assert_eq!(config.to_sip002(), uri);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QueryParams(Vec<(String, String)>);
+
+/// the order query parameters are written in, see [`SSConfig::to_uri_ordered`]
+///
+/// [`SSConfig::to_uri_ordered`]: crate::SSConfig::to_uri_ordered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum QueryOrder {
+    /// the order they were parsed or inserted in
+    #[default]
+    Insertion,
+    /// `plugin` first as most clients write it, the rest sorted by key, repeated keys keep their
+    /// relative order
+    Sorted,
+}
+
+type Pair<'a> = (&'a String, &'a String);
+pub type Iter<'a> =
+    iter::Map<slice::Iter<'a, (String, String)>, fn(&'a (String, String)) -> Pair<'a>>;
+
+fn pair((key, value): &(String, String)) -> Pair<'_> {
+    (key, value)
+}
+
+impl QueryParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// the first value of `key`
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+    /// every value of `key` in order
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a String> + 'a {
+        self.0.iter().filter(move |(k, _)| k == key).map(|(_, v)| v)
+    }
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+    /// sets `key` to a single value, replacing the first occurrence in place and dropping the
+    /// others, or appends it when the key is new
+    pub fn insert(&mut self, key: String, value: String) -> Option<String> {
+        let Some(index) = self.0.iter().position(|(k, _)| *k == key) else {
+            self.0.push((key, value));
+            return None;
+        };
+        let old = std::mem::replace(&mut self.0[index].1, value);
+        let mut position = 0;
+        self.0.retain(|(k, _)| {
+            let keep = position <= index || *k != key;
+            position += 1;
+            keep
+        });
+        Some(old)
+    }
+    /// adds another value for `key` after the existing ones
+    pub fn append(&mut self, key: String, value: String) {
+        self.0.push((key, value));
+    }
+    /// removes every value of `key`, returning the first
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.0.iter().position(|(k, _)| k == key)?;
+        let first = self.0.remove(index).1;
+        self.0.retain(|(k, _)| k != key);
+        Some(first)
+    }
+    pub fn retain(&mut self, mut f: impl FnMut(&str, &str) -> bool) {
+        self.0.retain(|(k, v)| f(k, v));
+    }
+    pub fn iter(&self) -> Iter<'_> {
+        self.0.iter().map(pair)
+    }
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.0.iter().map(|(k, _)| k)
+    }
+    pub fn as_slice(&self) -> &[(String, String)] {
+        &self.0
+    }
+    /// reorders the pairs the way [`QueryOrder::Sorted`] writes them
+    pub fn sort(&mut self) {
+        self.0
+            .sort_by(|(a, _), (b, _)| sort_key(a).cmp(&sort_key(b)));
+    }
+    pub(crate) fn ordered(&self, order: QueryOrder) -> Vec<Pair<'_>> {
+        let mut pairs = self.iter().collect::<Vec<_>>();
+        if order == QueryOrder::Sorted {
+            pairs.sort_by(|(a, _), (b, _)| sort_key(a).cmp(&sort_key(b)));
+        }
+        pairs
+    }
+}
+
+fn sort_key(key: &str) -> (bool, &str) {
+    (key != "plugin", key)
+}
+
+impl Index<&str> for QueryParams {
+    type Output = String;
+
+    fn index(&self, key: &str) -> &String {
+        self.get(key).expect("no such query parameter")
+    }
+}
+
+/// keeps every pair, including repeated keys
+impl FromIterator<(String, String)> for QueryParams {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        let mut params = Self::new();
+        params.extend(iter);
+        params
+    }
+}
+
+impl Extend<(String, String)> for QueryParams {
+    fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl<const N: usize> From<[(String, String); N]> for QueryParams {
+    fn from(pairs: [(String, String); N]) -> Self {
+        pairs.into_iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a QueryParams {
+    type Item = Pair<'a>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for QueryParams {
+    type Item = (String, String);
+    type IntoIter = vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_insertion_order() {
+        let mut params = [("b", "1"), ("a", "2"), ("b", "3")]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .into_iter()
+            .collect::<QueryParams>();
+        assert_eq!(params.keys().collect::<Vec<_>>(), ["b", "a", "b"]);
+        assert_eq!(params["b"], "1");
+        assert_eq!(params.get_all("b").collect::<Vec<_>>(), ["1", "3"]);
+        params.append("c".to_string(), "4".to_string());
+        assert_eq!(params.remove("a"), Some("2".to_string()));
+        assert_eq!(
+            params.insert("b".to_string(), "5".to_string()),
+            Some("1".to_string())
+        );
+        assert_eq!(
+            params.iter().collect::<Vec<_>>(),
+            [
+                (&"b".to_string(), &"5".to_string()),
+                (&"c".to_string(), &"4".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn sorts_with_plugin_first() {
+        let mut params = [("b", "1"), ("plugin", "p"), ("a", "2"), ("b", "0")]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .into_iter()
+            .collect::<QueryParams>();
+        let sorted = params
+            .ordered(QueryOrder::Sorted)
+            .into_iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>();
+        assert_eq!(sorted, ["plugin=p", "a=2", "b=1", "b=0"]);
+        params.sort();
+        assert_eq!(params.keys().collect::<Vec<_>>(), ["plugin", "a", "b", "b"]);
+    }
+}
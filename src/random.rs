@@ -0,0 +1,113 @@
+//! random configs for stress testing and examples
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use url::Host;
+
+use crate::{Method, SSConfig};
+
+const PASSWORD_CHARS: &[u8] =
+    b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!#$%&*+-./:=?@^_~";
+
+const TAG_WORDS: &[&str] = &[
+    "HK",
+    "JP",
+    "SG",
+    "US",
+    "DE",
+    "香港",
+    "日本",
+    "Россия",
+    "Türkiye",
+    "🇭🇰",
+    "🚀",
+    "#1",
+    "50%",
+    "&",
+];
+
+impl SSConfig {
+    /// a config that passes [`SSConfig::validate`] and survives a round trip through
+    /// [`SSConfig::to_uri`], the host is an ipv4 address, an ipv6 address or a domain, the
+    /// password is 8 to 32 printable ascii characters and the tag, if any, mixes ascii, unicode
+    /// and characters that need escaping
+    /// ```
+    /// use rand::SeedableRng;
+    /// use ss_uri::{Compat, SSConfig};
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    /// let config = SSConfig::random(&mut rng);
+    /// assert_eq!(config.validate(), Ok(()));
+    /// assert_eq!(SSConfig::parse(&config.to_uri(Compat::Spec)), Ok(config));
+    /// ```
+    pub fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let password_len = rng.gen_range(8..=32);
+        let password = (0..password_len)
+            .map(|_| char::from(*PASSWORD_CHARS.choose(rng).unwrap()))
+            .collect();
+        let tag = rng.gen_bool(0.8).then(|| {
+            let words = rng.gen_range(1..=3);
+            (0..words)
+                .map(|_| *TAG_WORDS.choose(rng).unwrap())
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+        SSConfig {
+            host: random_host(rng),
+            port: rng.gen_range(1..=u16::MAX),
+            method: *Method::ALL.choose(rng).unwrap(),
+            password,
+            tag,
+            tag_raw: None,
+            extra: None,
+            prefix: None,
+            mode: None,
+        }
+    }
+
+    /// [`SSConfig::random`] using the thread local generator
+    pub fn gen_random() -> Self {
+        Self::random(&mut rand::thread_rng())
+    }
+}
+
+fn random_host<R: Rng + ?Sized>(rng: &mut R) -> Host {
+    match rng.gen_range(0..3) {
+        0 => Host::Ipv4(Ipv4Addr::from(rng.gen_range(1..=u32::MAX))),
+        1 => Host::Ipv6(Ipv6Addr::from(rng.gen_range(1..=u128::MAX))),
+        _ => {
+            let labels = rng.gen_range(1..=3);
+            let mut domain = (0..labels).map(|_| random_label(rng)).collect::<Vec<_>>();
+            // a letter last keeps the url parser from reading the domain as an ipv4 address
+            domain.push(["com", "net", "org", "io"].choose(rng).unwrap().to_string());
+            Host::Domain(domain.join("."))
+        }
+    }
+}
+
+fn random_label<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let len = rng.gen_range(1..=12);
+    (0..len)
+        .map(|_| char::from(*b"abcdefghijklmnopqrstuvwxyz0123456789".choose(rng).unwrap()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::Compat;
+
+    #[test]
+    fn random_configs_validate_and_round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..1000 {
+            let config = SSConfig::random(&mut rng);
+            assert_eq!(config.validate(), Ok(()), "{config:?}");
+            let uri = config.to_uri(Compat::Spec);
+            assert_eq!(SSConfig::parse(&uri), Ok(config), "{uri}");
+        }
+    }
+}
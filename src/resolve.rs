@@ -0,0 +1,56 @@
+//! turning the host of a config into addresses to connect to
+use std::future::Future;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+
+use crate::SSConfig;
+
+/// an asynchronous dns lookup for [`SSConfig::resolved_socket_addrs`], implement it on top of
+/// whatever runtime or resolver the application already uses
+/// ```
+/// use std::{io, net::SocketAddr};
+/// use ss_uri::{Resolver, SSConfig};
+///
+/// struct Tokio;
+/// impl Resolver for Tokio {
+///     async fn lookup(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+///         Ok(tokio::net::lookup_host((host, port)).await?.collect())
+///     }
+/// }
+///
+/// # #[tokio::main] async fn main() {
+/// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@localhost:8888").unwrap();
+/// let addrs = config.resolved_socket_addrs(&Tokio).await.unwrap();
+/// assert!(addrs.iter().all(|addr| addr.ip().is_loopback() && addr.port() == 8888));
+/// # }
+/// ```
+pub trait Resolver {
+    /// the addresses of `host`, combined with `port`
+    fn lookup(&self, host: &str, port: u16) -> impl Future<Output = io::Result<Vec<SocketAddr>>>;
+}
+
+impl SSConfig {
+    /// the addresses of the server, ip hosts are returned as they are and domains are looked up
+    /// with the system resolver, which blocks
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888").unwrap();
+    /// assert_eq!(config.resolve().unwrap(), ["192.168.100.1:8888".parse().unwrap()]);
+    /// ```
+    pub fn resolve(&self) -> io::Result<Vec<SocketAddr>> {
+        match self.socket_addr() {
+            Some(addr) => Ok(vec![addr]),
+            None => Ok((self.host_ascii(), self.port).to_socket_addrs()?.collect()),
+        }
+    }
+    /// [`SSConfig::resolve`] with the lookup left to `resolver`
+    pub async fn resolved_socket_addrs<R: Resolver>(
+        &self,
+        resolver: &R,
+    ) -> io::Result<Vec<SocketAddr>> {
+        match self.socket_addr() {
+            Some(addr) => Ok(vec![addr]),
+            None => resolver.lookup(&self.host_ascii(), self.port).await,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! json schemas of the documents this crate reads and writes, the serde types derive
+//! `JsonSchema` themselves
+use std::borrow::Cow;
+
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+
+use crate::SSConfig;
+
+/// configs travel as their SIP002 uri, the form [`SSConfig::to_sip002`] writes and
+/// [`SSConfig::parse`] reads
+/// ```
+/// use ss_uri::SSConfig;
+/// let schema = schemars::schema_for!(SSConfig);
+/// assert_eq!(schema.get("type").unwrap(), "string");
+/// assert_eq!(schema.get("pattern").unwrap(), "^ss://");
+/// ```
+impl JsonSchema for SSConfig {
+    fn inline_schema() -> bool {
+        true
+    }
+    fn schema_name() -> Cow<'static, str> {
+        "SSConfig".into()
+    }
+    fn schema_id() -> Cow<'static, str> {
+        "ss_uri::SSConfig".into()
+    }
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "format": "uri",
+            "pattern": "^ss://",
+            "description": "a shadowsocks SIP002 or legacy base64 uri",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Sip008Document;
+
+    #[test]
+    fn optional_fields_are_not_required() {
+        let schema = serde_json::to_value(schemars::schema_for!(Sip008Document)).unwrap();
+        assert_eq!(
+            schema["required"],
+            serde_json::json!(["version", "servers"])
+        );
+        let server = &schema["$defs"]["Sip008Server"];
+        let required = server["required"].as_array().unwrap();
+        assert!(required
+            .iter()
+            .all(|field| field != "plugin" && field != "remarks"));
+        assert_eq!(server["properties"]["server_port"]["maximum"], 65535);
+    }
+}
@@ -0,0 +1,133 @@
+use percent_encoding::percent_decode_str;
+
+use crate::{entry, Compat, Mode, QueryParams, SSConfig, SSParseError};
+
+impl SSConfig {
+    /// parses the uris shadowrocket produces and accepts, on top of sip002 and legacy uris this
+    /// tolerates padded url-safe base64, unencoded spaces in the remarks, a `remarks` query
+    /// parameter instead of a fragment and query parameters appended to the fragment
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse_shadowrocket(
+    ///     "ss://YWVzLTEyOC1nY206dGVzdEAxOTIuMTY4LjEwMC4xOjg4ODg=?remarks=My Server&plugin=obfs-local;obfs=http",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(config.tag, Some("My Server".to_string()));
+    /// assert_eq!(config.plugin().unwrap().to_string(), "obfs-local;obfs=http");
+    /// ```
+    pub fn parse_shadowrocket(s: &str) -> Result<Self, SSParseError> {
+        let rest =
+            entry::strip_scheme(entry::trim_input(s), "ss").ok_or(SSParseError::InvalidProtocol)?;
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (rest, None),
+        };
+        let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let mut extra = parse_query(query);
+        let mut tag = extra.remove("remarks");
+        if let Some(fragment) = fragment {
+            let (fragment, fragment_query) = fragment.split_once('?').unwrap_or((fragment, ""));
+            // options after the tag override the ones in the query
+            for (key, value) in parse_query(fragment_query) {
+                extra.insert(key, value);
+            }
+            tag = Self::extract_hash(Some(fragment));
+        }
+        let rest = rest.trim_end_matches('/');
+
+        let (user_info, endpoint) = match rest.rsplit_once('@') {
+            Some((user_info, endpoint)) => (decode_user_info(user_info)?, endpoint.to_string()),
+            None => {
+                let decoded = entry::decode_base64_lenient(rest).ok_or(SSParseError::InvalidUrl)?;
+                let (user_info, endpoint) =
+                    decoded.rsplit_once('@').ok_or(SSParseError::InvalidUrl)?;
+                (user_info.to_string(), endpoint.to_string())
+            }
+        };
+        let (method, password) = user_info
+            .split_once(':')
+            .ok_or(SSParseError::InvalidPassword)?;
+        let (host, port) = endpoint.rsplit_once(':').ok_or(SSParseError::InvalidPort)?;
+        let port = port.parse().map_err(|_| SSParseError::InvalidPort)?;
+
+        let mut config = entry::build_config(
+            host,
+            port,
+            method,
+            password,
+            tag.filter(|t| !t.is_empty()),
+            None,
+            None,
+        )?;
+        config.mode = Mode::take_from(&mut extra);
+        config.extra = (!extra.is_empty()).then_some(extra);
+        Ok(config)
+    }
+    /// serializes the config the way shadowrocket imports it reliably, padded base64 user info
+    /// and no slash between the port and the query
+    pub fn to_shadowrocket(&self) -> String {
+        self.to_uri(Compat::Shadowrocket)
+    }
+}
+
+fn parse_query(query: &str) -> QueryParams {
+    url::form_urlencoded::parse(query.as_bytes())
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .filter(|(k, _)| !k.is_empty())
+        .collect()
+}
+
+/// base64 user info with either alphabet, with or without padding, or plain `method:password`
+fn decode_user_info(user_info: &str) -> Result<String, SSParseError> {
+    let user_info = percent_decode_str(user_info).decode_utf8_lossy();
+    match entry::decode_base64_lenient(&user_info) {
+        Some(decoded) if decoded.contains(':') => Ok(decoded),
+        _ if user_info.contains(':') => Ok(user_info.to_string()),
+        _ => Err(SSParseError::InvalidPassword),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Method;
+    use url::Host;
+
+    #[test]
+    fn can_parse_shadowrocket_quirks() {
+        let config = SSConfig::parse_shadowrocket(
+            "ss://YWVzLTEyOC1nY206dGVzdD8-Pz8=@[2001:db8::1]:8888#My Server?plugin=obfs-local;obfs=http",
+        )
+        .unwrap();
+        assert_eq!(config.method, Method::Aes128Gcm);
+        assert_eq!(config.password, "test?>??");
+        assert_eq!(config.host, Host::parse("[2001:db8::1]").unwrap());
+        assert_eq!(config.tag, Some("My Server".to_string()));
+        assert_eq!(config.plugin().unwrap().to_string(), "obfs-local;obfs=http");
+    }
+
+    #[test]
+    fn accepts_spec_uris() {
+        for uri in [
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp#Foo%20Bar",
+            "ss://YmYtY2ZiOnRlc3RAMTkyLjE2OC4xMDAuMTo4ODg4#Foo Bar",
+        ] {
+            let expected = SSConfig::parse(uri).unwrap();
+            assert_eq!(SSConfig::parse_shadowrocket(uri).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn shadowrocket_uris_round_trip() {
+        let config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp#Foo%20Bar",
+        )
+        .unwrap();
+        let uri = config.to_shadowrocket();
+        assert_eq!(
+            uri,
+            "ss://YWVzLTEyOC1nY206dGVzdA==@192.168.100.1:8888?plugin=obfs-local%3Bobfs%3Dhttp#Foo%20Bar"
+        );
+        assert_eq!(SSConfig::parse_shadowrocket(&uri).unwrap(), config);
+    }
+}
@@ -0,0 +1,143 @@
+//! conversions to and from the server configs of shadowsocks-rust, the `shadowsocks` crate
+//! `shadowsocks-service` builds on
+use core::fmt;
+use std::net::{IpAddr, SocketAddr};
+
+use shadowsocks::config::{Mode as ShadowsocksMode, ServerAddr, ServerConfig};
+use shadowsocks::crypto::CipherKind;
+use shadowsocks::plugin::PluginConfig;
+use url::Host;
+
+use crate::{entry, Mode, QueryParams, SSConfig, SSParseError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShadowsocksConvertError {
+    /// the method isn't one shadowsocks knows or its cipher feature isn't enabled
+    UnsupportedMethod,
+    /// the password isn't a base64 key of the right length, as the 2022 methods require
+    InvalidKey,
+}
+impl fmt::Display for ShadowsocksConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for ShadowsocksConvertError {}
+
+/// a server config ready to run, parameters shadowsocks has no place for are dropped
+/// ```
+/// use ss_uri::shadowsocks::ServerConfig;
+/// use ss_uri::SSConfig;
+/// let config = SSConfig::parse(
+///     "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp#Foo",
+/// )
+/// .unwrap();
+/// let server = ServerConfig::try_from(&config).unwrap();
+/// assert_eq!(server.addr().to_string(), "192.168.100.1:8888");
+/// assert_eq!(server.plugin().unwrap().plugin_opts.as_deref(), Some("obfs=http"));
+/// assert_eq!(SSConfig::try_from(&server).unwrap(), config);
+/// ```
+impl TryFrom<&SSConfig> for ServerConfig {
+    type Error = ShadowsocksConvertError;
+    fn try_from(config: &SSConfig) -> Result<Self, Self::Error> {
+        let method = config
+            .method
+            .as_str()
+            .parse::<CipherKind>()
+            .map_err(|_| ShadowsocksConvertError::UnsupportedMethod)?;
+        let addr = match config.host {
+            Host::Ipv4(ip) => ServerAddr::SocketAddr(SocketAddr::new(ip.into(), config.port)),
+            Host::Ipv6(ip) => ServerAddr::SocketAddr(SocketAddr::new(ip.into(), config.port)),
+            Host::Domain(_) => ServerAddr::DomainName(config.host_ascii(), config.port),
+        };
+        let mut server = ServerConfig::new(addr, config.password.as_str(), method)
+            .map_err(|_| ShadowsocksConvertError::InvalidKey)?;
+        if let Some(plugin) = config.plugin() {
+            server.set_plugin(PluginConfig {
+                plugin: plugin.name,
+                plugin_opts: plugin.opts,
+                plugin_args: Vec::new(),
+                plugin_mode: ShadowsocksMode::TcpOnly,
+            });
+        }
+        if let Some(tag) = &config.tag {
+            server.set_remarks(tag.as_str());
+        }
+        if let Some(mode) = config.mode {
+            server.set_mode(match mode {
+                Mode::TcpOnly => ShadowsocksMode::TcpOnly,
+                Mode::UdpOnly => ShadowsocksMode::UdpOnly,
+                Mode::TcpAndUdp => ShadowsocksMode::TcpAndUdp,
+            });
+        }
+        Ok(server)
+    }
+}
+
+/// tcp and udp, the mode shadowsocks defaults to, is left unset
+impl TryFrom<&ServerConfig> for SSConfig {
+    type Error = SSParseError;
+    fn try_from(server: &ServerConfig) -> Result<Self, Self::Error> {
+        let (host, port) = match server.addr() {
+            ServerAddr::SocketAddr(addr) => match addr.ip() {
+                IpAddr::V4(ip) => (Host::Ipv4(ip), addr.port()),
+                IpAddr::V6(ip) => (Host::Ipv6(ip), addr.port()),
+            },
+            ServerAddr::DomainName(domain, port) => (entry::parse_host(domain)?, *port),
+        };
+        let extra = server.plugin().map(|plugin| {
+            let value = match &plugin.plugin_opts {
+                Some(opts) => format!("{};{opts}", plugin.plugin),
+                None => plugin.plugin.clone(),
+            };
+            QueryParams::from([("plugin".to_string(), value)])
+        });
+        Ok(SSConfig {
+            host,
+            port,
+            method: server
+                .method()
+                .to_string()
+                .parse()
+                .map_err(|_| SSParseError::InvalidMethod)?,
+            password: server.password().to_string(),
+            tag: server.remarks().map(str::to_string),
+            tag_raw: None,
+            extra,
+            prefix: None,
+            mode: match server.mode() {
+                ShadowsocksMode::TcpOnly => Some(Mode::TcpOnly),
+                ShadowsocksMode::UdpOnly => Some(Mode::UdpOnly),
+                ShadowsocksMode::TcpAndUdp => None,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_endpoints_modes_and_keys() {
+        let config =
+            SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8388/?mode=udp_only")
+                .unwrap();
+        let server = ServerConfig::try_from(&config).unwrap();
+        assert_eq!(server.addr().to_string(), "[2001:db8::1]:8388");
+        assert!(!server.mode().enable_tcp());
+        assert_eq!(SSConfig::try_from(&server).unwrap(), config);
+
+        let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@例え.jp:8388").unwrap();
+        let server = ServerConfig::try_from(&config).unwrap();
+        assert_eq!(server.addr().host(), "xn--r8jz45g.jp");
+
+        // stream ciphers sit behind a feature of shadowsocks this crate doesn't enable
+        let mut config = config;
+        config.method = crate::Method::BfCfb;
+        assert_eq!(
+            ServerConfig::try_from(&config).err(),
+            Some(ShadowsocksConvertError::UnsupportedMethod)
+        );
+    }
+}
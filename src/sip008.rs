@@ -1,7 +1,11 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
 use url::Url;
 
+use crate::{entry, SSConfig, SSParseError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SIP008Config {
     pub location: String,
     pub cert_finger_print: Option<String>,
@@ -19,7 +23,8 @@ pub enum SIP008ParseError {
 impl SIP008Config {
     // parses shadowsocks SIP008 https://shadowsocks.org/en/wiki/SIP008-Online-Configuration-Delivery.html
     pub fn parse(input: &str) -> Result<Self, SIP008ParseError> {
-        let url = Url::parse(input).map_err(|_| SIP008ParseError::InvalidUrl)?;
+        let input = entry::unescape_host_brackets(input);
+        let url = Url::parse(&input).map_err(|_| SIP008ParseError::InvalidUrl)?;
         Self::validate_protocol(&url)?;
         let params = url::form_urlencoded::parse(url.fragment().unwrap_or("").as_ref())
             .map(|(a, b)| (a.to_string(), b.to_string()))
@@ -43,11 +48,297 @@ impl SIP008Config {
     }
 }
 
+/// latest SIP008 document version understood by this crate
+pub const SIP008_VERSION: u32 = 1;
+
+/// online configuration document as described by SIP008
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Sip008Document {
+    pub version: u32,
+    pub servers: Vec<Sip008Server>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_used: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_remaining: Option<u64>,
+}
+
+/// a single server entry of a SIP008 document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Sip008Server {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remarks: Option<String>,
+    pub server: String,
+    pub server_port: u16,
+    pub password: String,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin_opts: Option<String>,
+}
+
+/// a difference between two documents, servers are matched by id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sip008Change {
+    Added(Sip008Server),
+    Removed(Sip008Server),
+    Modified {
+        old: Sip008Server,
+        new: Sip008Server,
+    },
+}
+
+/// what [`Sip008Document::merge`] does when both documents contain different servers with the same id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sip008ConflictPolicy {
+    KeepExisting,
+    KeepIncoming,
+    Reject,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Sip008MergeError {
+    Conflict(String),
+}
+impl std::fmt::Display for Sip008MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for Sip008MergeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sip008DocumentError {
+    InvalidJson,
+    UnsupportedVersion,
+}
+impl std::fmt::Display for Sip008DocumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for Sip008DocumentError {}
+
+impl Sip008Document {
+    /// parses a SIP008 json document, only version 1 is supported
+    /// ```
+    /// use ss_uri::Sip008Document;
+    /// let document = Sip008Document::parse(r#"{
+    ///     "version": 1,
+    ///     "servers": [{
+    ///         "id": "27b8a625-4f4b-4428-9f0f-8a2317db7c79",
+    ///         "remarks": "Name of the server",
+    ///         "server": "example.com",
+    ///         "server_port": 8388,
+    ///         "password": "example",
+    ///         "method": "chacha20-ietf-poly1305"
+    ///     }]
+    /// }"#).unwrap();
+    /// let configs = document.to_configs().unwrap();
+    /// assert_eq!(configs[0].port, 8388);
+    /// assert_eq!(configs[0].tag, Some("Name of the server".to_string()));
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, Sip008DocumentError> {
+        let document: Self =
+            serde_json::from_str(input).map_err(|_| Sip008DocumentError::InvalidJson)?;
+        if document.version != SIP008_VERSION {
+            return Err(Sip008DocumentError::UnsupportedVersion);
+        }
+        Ok(document)
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("SIP008 document is always serializable")
+    }
+    pub fn to_configs(&self) -> Result<Vec<SSConfig>, SSParseError> {
+        self.servers.iter().map(Sip008Server::to_config).collect()
+    }
+    /// lists modified and added servers in the order of `new` followed by the removed ones
+    /// ```
+    /// use ss_uri::{Sip008Change, Sip008Document};
+    /// let old = Sip008Document::parse(r#"{"version": 1, "servers": []}"#).unwrap();
+    /// let new = Sip008Document::parse(r#"{"version": 1, "servers": [{
+    ///     "id": "a", "server": "example.com", "server_port": 8388,
+    ///     "password": "example", "method": "aes-256-gcm"
+    /// }]}"#).unwrap();
+    /// let changes = Sip008Document::diff(&old, &new);
+    /// assert!(matches!(&changes[..], [Sip008Change::Added(s)] if s.id == "a"));
+    /// ```
+    pub fn diff(old: &Sip008Document, new: &Sip008Document) -> Vec<Sip008Change> {
+        let old_by_id = old
+            .servers
+            .iter()
+            .map(|s| (s.id.as_str(), s))
+            .collect::<HashMap<_, _>>();
+        let new_by_id = new
+            .servers
+            .iter()
+            .map(|s| (s.id.as_str(), s))
+            .collect::<HashMap<_, _>>();
+
+        let mut changes = Vec::new();
+        for server in &new.servers {
+            match old_by_id.get(server.id.as_str()) {
+                None => changes.push(Sip008Change::Added(server.clone())),
+                Some(old) if *old != server => changes.push(Sip008Change::Modified {
+                    old: (*old).clone(),
+                    new: server.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for server in &old.servers {
+            if !new_by_id.contains_key(server.id.as_str()) {
+                changes.push(Sip008Change::Removed(server.clone()));
+            }
+        }
+        changes
+    }
+    /// combines the servers of two documents, identical duplicates are collapsed and
+    /// different servers sharing an id are resolved using `policy`. traffic counters are summed
+    pub fn merge(
+        &self,
+        other: &Sip008Document,
+        policy: Sip008ConflictPolicy,
+    ) -> Result<Sip008Document, Sip008MergeError> {
+        let mut servers = self.servers.clone();
+        for server in &other.servers {
+            match servers.iter_mut().find(|s| s.id == server.id) {
+                None => servers.push(server.clone()),
+                Some(existing) if existing == server => {}
+                Some(existing) => match policy {
+                    Sip008ConflictPolicy::KeepExisting => {}
+                    Sip008ConflictPolicy::KeepIncoming => *existing = server.clone(),
+                    Sip008ConflictPolicy::Reject => {
+                        return Err(Sip008MergeError::Conflict(server.id.clone()))
+                    }
+                },
+            }
+        }
+        let sum = |a: Option<u64>, b: Option<u64>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.saturating_add(b)),
+            (a, b) => a.or(b),
+        };
+        Ok(Sip008Document {
+            version: self.version.max(other.version),
+            servers,
+            bytes_used: sum(self.bytes_used, other.bytes_used),
+            bytes_remaining: sum(self.bytes_remaining, other.bytes_remaining),
+        })
+    }
+}
+
+impl Sip008Server {
+    /// builds a server entry out of a config, the plugin is taken from the `plugin` extra parameter
+    pub fn from_config(id: impl Into<String>, config: &SSConfig) -> Self {
+        let (plugin, plugin_opts) = entry::plugin_parts(config);
+        Self {
+            id: id.into(),
+            remarks: config.tag.clone(),
+            server: entry::host_to_string(&config.host),
+            server_port: config.port,
+            password: config.password.clone(),
+            method: config.method.to_string(),
+            plugin,
+            plugin_opts,
+        }
+    }
+    pub fn to_config(&self) -> Result<SSConfig, SSParseError> {
+        entry::build_config(
+            &self.server,
+            self.server_port,
+            &self.method,
+            &self.password,
+            self.remarks.clone(),
+            self.plugin.as_deref(),
+            self.plugin_opts.as_deref(),
+        )
+    }
+}
+
+/// client side view of an online configuration, merging fetched documents into a stable list
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sip008State {
+    document: Option<Sip008Document>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sip008UpdateError {
+    UnsupportedVersion,
+    /// two servers of the document share an id, so there is no telling which one is meant
+    DuplicateId,
+}
+impl std::fmt::Display for Sip008UpdateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for Sip008UpdateError {}
+
+impl Sip008State {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn document(&self) -> Option<&Sip008Document> {
+        self.document.as_ref()
+    }
+    pub fn servers(&self) -> &[Sip008Server] {
+        self.document.as_ref().map_or(&[], |d| &d.servers)
+    }
+    /// applies a freshly fetched document
+    ///
+    /// documents with a version other than [`SIP008_VERSION`] or with two servers sharing an id
+    /// are rejected and leave the state untouched, sip008 has no revision counter so the order of updates is left to the caller.
+    /// otherwise the update is authoritative: servers missing from it are dropped and changed
+    /// servers are replaced, but servers already known keep their position so the list does not
+    /// reshuffle, new servers are appended in order.
+    /// ```
+    /// use ss_uri::{Sip008Document, Sip008State};
+    /// let mut state = Sip008State::new();
+    /// let update = Sip008Document::parse(r#"{"version": 1, "servers": []}"#).unwrap();
+    /// state.apply_update(update).unwrap();
+    /// assert!(state.servers().is_empty());
+    /// ```
+    pub fn apply_update(&mut self, update: Sip008Document) -> Result<(), Sip008UpdateError> {
+        if update.version != SIP008_VERSION {
+            return Err(Sip008UpdateError::UnsupportedVersion);
+        }
+        let mut incoming = update
+            .servers
+            .iter()
+            .map(|s| (s.id.as_str(), s))
+            .collect::<HashMap<_, _>>();
+        if incoming.len() != update.servers.len() {
+            return Err(Sip008UpdateError::DuplicateId);
+        }
+        let Some(current) = self.document.take() else {
+            self.document = Some(update);
+            return Ok(());
+        };
+        let mut servers = current
+            .servers
+            .iter()
+            .filter_map(|s| incoming.remove(s.id.as_str()).cloned())
+            .collect::<Vec<_>>();
+        servers.extend(
+            update
+                .servers
+                .iter()
+                .filter(|s| incoming.contains_key(s.id.as_str()))
+                .cloned(),
+        );
+        self.document = Some(Sip008Document { servers, ..update });
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use percent_encoding::{percent_encode, NON_ALPHANUMERIC};
-    use url::Url;
+    use url::{Host, Url};
 
     #[test]
     fn can_parse_a_valid_ssconf_uri_with_domain_name_and_extras() {
@@ -88,6 +379,19 @@ mod tests {
         assert_eq!(online_config.cert_finger_print, None);
     }
 
+    #[test]
+    fn can_parse_an_ssconf_uri_with_percent_encoded_ipv6_brackets() {
+        let input = "ssconf://%5B2001:db8::1%5D:8081/secret#certFp=AA:BB";
+        let online_config = SIP008Config::parse(input).unwrap();
+        assert_eq!(online_config.location, "https://[2001:db8::1]:8081/secret");
+    }
+
+    #[test]
+    fn can_parse_an_uppercase_ssconf_scheme() {
+        let online_config = SIP008Config::parse("SSCONF://my.domain.com/secret").unwrap();
+        assert_eq!(online_config.location, "https://my.domain.com:443/secret");
+    }
+
     #[test]
     fn can_parse_a_valid_ssconf_uri_with_ipv4_address() {
         let input = "ssconf://1.2.3.4/secret/long/path#certFp=AA:BB:CC:DD:EE:FF&other=param";
@@ -127,4 +431,198 @@ mod tests {
         assert_eq!(online_config.cert_finger_print, Some("&=?:%".to_string()));
         assert_eq!(online_config.http_method, Some("GET".to_string()));
     }
+
+    #[test]
+    fn can_parse_a_sip008_document_with_plugins() {
+        let input = r#"{
+            "version": 1,
+            "servers": [
+                {
+                    "id": "27b8a625-4f4b-4428-9f0f-8a2317db7c79",
+                    "remarks": "Name of the server",
+                    "server": "example.com",
+                    "server_port": 8388,
+                    "password": "example",
+                    "method": "chacha20-ietf-poly1305",
+                    "plugin": "xxx",
+                    "plugin_opts": "xxxxx"
+                },
+                {
+                    "id": "7842c068-c667-41f2-8f7d-04feece3cb67",
+                    "server": "2001:db8::1",
+                    "server_port": 8389,
+                    "password": "example",
+                    "method": "aes-256-gcm"
+                }
+            ],
+            "bytes_used": 274877906944,
+            "bytes_remaining": 824633720832
+        }"#;
+        let document = Sip008Document::parse(input).unwrap();
+        assert_eq!(document.bytes_used, Some(274877906944));
+        let configs = document.to_configs().unwrap();
+        assert_eq!(configs[0].host, Host::parse("example.com").unwrap());
+        assert_eq!(
+            configs[0].extra.as_ref().unwrap().get("plugin").unwrap(),
+            "xxx;xxxxx"
+        );
+        assert_eq!(configs[1].host, Host::parse("[2001:db8::1]").unwrap());
+        assert_eq!(configs[1].tag, None);
+        assert_eq!(configs[1].extra, None);
+    }
+
+    #[test]
+    fn rejects_a_sip008_document_with_unknown_version() {
+        let input = r#"{"version": 2, "servers": []}"#;
+        assert_eq!(
+            Sip008Document::parse(input),
+            Err(Sip008DocumentError::UnsupportedVersion)
+        );
+        assert_eq!(
+            Sip008Document::parse("not json"),
+            Err(Sip008DocumentError::InvalidJson)
+        );
+    }
+
+    #[test]
+    fn sip008_server_round_trips_through_ss_config() {
+        let config = SSConfig::parse(
+            "ss://cmM0LW1kNTpwYXNzd2Q@[2001:db8::1]:8888/?plugin=obfs-local%3Bobfs%3Dhttp#Foo",
+        )
+        .unwrap();
+        let server = Sip008Server::from_config("id", &config);
+        assert_eq!(server.server, "2001:db8::1");
+        assert_eq!(server.plugin, Some("obfs-local".to_string()));
+        assert_eq!(server.plugin_opts, Some("obfs=http".to_string()));
+        assert_eq!(server.to_config().unwrap(), config);
+    }
+
+    fn server(id: &str, port: u16) -> Sip008Server {
+        Sip008Server {
+            id: id.to_string(),
+            remarks: None,
+            server: "example.com".to_string(),
+            server_port: port,
+            password: "p".to_string(),
+            method: "aes-256-gcm".to_string(),
+            plugin: None,
+            plugin_opts: None,
+        }
+    }
+
+    fn document(version: u32, servers: Vec<Sip008Server>) -> Sip008Document {
+        Sip008Document {
+            version,
+            servers,
+            bytes_used: None,
+            bytes_remaining: None,
+        }
+    }
+
+    #[test]
+    fn sip008_state_merges_updates_keeping_known_servers_in_place() {
+        let mut state = Sip008State::new();
+        state
+            .apply_update(document(
+                1,
+                vec![server("a", 1), server("b", 2), server("c", 3)],
+            ))
+            .unwrap();
+        state
+            .apply_update(document(
+                1,
+                vec![server("d", 4), server("c", 5), server("a", 1)],
+            ))
+            .unwrap();
+        let servers = state.servers();
+        assert_eq!(
+            servers.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(),
+            ["a", "c", "d"]
+        );
+        assert_eq!(servers[1].server_port, 5);
+    }
+
+    #[test]
+    fn sip008_state_rejects_unknown_versions() {
+        let mut state = Sip008State::new();
+        state
+            .apply_update(document(1, vec![server("a", 1)]))
+            .unwrap();
+        assert_eq!(
+            state.apply_update(document(0, vec![])),
+            Err(Sip008UpdateError::UnsupportedVersion)
+        );
+        assert_eq!(
+            state.apply_update(document(2, vec![])),
+            Err(Sip008UpdateError::UnsupportedVersion)
+        );
+        assert_eq!(state.servers().len(), 1);
+    }
+
+    #[test]
+    fn sip008_state_rejects_duplicate_ids() {
+        let mut state = Sip008State::new();
+        assert_eq!(
+            state.apply_update(document(1, vec![server("a", 1), server("a", 2)])),
+            Err(Sip008UpdateError::DuplicateId)
+        );
+        assert_eq!(state.document(), None);
+        state
+            .apply_update(document(1, vec![server("a", 1)]))
+            .unwrap();
+        for duplicate in ["a", "b"] {
+            assert_eq!(
+                state.apply_update(document(
+                    1,
+                    vec![server(duplicate, 2), server("c", 3), server(duplicate, 4)],
+                )),
+                Err(Sip008UpdateError::DuplicateId)
+            );
+        }
+        assert_eq!(state.servers(), [server("a", 1)]);
+    }
+
+    #[test]
+    fn diffs_documents_by_server_id() {
+        let old = document(1, vec![server("a", 1), server("b", 2)]);
+        let new = document(1, vec![server("a", 3), server("c", 4)]);
+        assert_eq!(
+            Sip008Document::diff(&old, &new),
+            vec![
+                Sip008Change::Modified {
+                    old: server("a", 1),
+                    new: server("a", 3)
+                },
+                Sip008Change::Added(server("c", 4)),
+                Sip008Change::Removed(server("b", 2)),
+            ]
+        );
+        assert!(Sip008Document::diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn merges_documents_according_to_the_conflict_policy() {
+        let mut first = document(1, vec![server("a", 1), server("b", 2)]);
+        first.bytes_used = Some(10);
+        let second = document(1, vec![server("b", 2), server("a", 3), server("c", 4)]);
+
+        let merged = first
+            .merge(&second, Sip008ConflictPolicy::KeepExisting)
+            .unwrap();
+        assert_eq!(
+            merged.servers,
+            vec![server("a", 1), server("b", 2), server("c", 4)]
+        );
+        assert_eq!(merged.bytes_used, Some(10));
+
+        let merged = first
+            .merge(&second, Sip008ConflictPolicy::KeepIncoming)
+            .unwrap();
+        assert_eq!(merged.servers[0], server("a", 3));
+
+        assert_eq!(
+            first.merge(&second, Sip008ConflictPolicy::Reject),
+            Err(Sip008MergeError::Conflict("a".to_string()))
+        );
+    }
 }
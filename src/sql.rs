@@ -0,0 +1,82 @@
+//! sqlx column type, configs are stored as text in their canonical SIP002 form so equal configs
+//! compare equal in queries
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::{Database, Decode, Encode, Type};
+
+use crate::SSConfig;
+
+/// any text column of a database whose strings sqlx handles
+impl<DB: Database> Type<DB> for SSConfig
+where
+    String: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as Type<DB>>::type_info()
+    }
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as Type<DB>>::compatible(ty)
+    }
+}
+
+/// writes [`SSConfig::to_sip002`] of the [`SSConfig::canonicalize`]d config
+impl<'q, DB: Database> Encode<'q, DB> for SSConfig
+where
+    String: Encode<'q, DB>,
+{
+    fn encode_by_ref(&self, buf: &mut DB::ArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        self.canonicalize().to_sip002().encode(buf)
+    }
+}
+
+/// reads any uri [`SSConfig::parse`] accepts, failing with its [`SSParseError`]
+///
+/// [`SSParseError`]: crate::SSParseError
+impl<'r, DB: Database> Decode<'r, DB> for SSConfig
+where
+    &'r str: Decode<'r, DB>,
+{
+    fn decode(value: DB::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        let uri = <&str as Decode<DB>>::decode(value)?;
+        Ok(SSConfig::parse(uri)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::{Connection, SqliteConnection};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn stores_the_canonical_uri() {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE servers (config TEXT NOT NULL)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        let config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA==@Example.com:8388/?b=1&plugin=obfs-local%3B%3Bobfs%3Dhttp&a=2#%46oo",
+        )
+        .unwrap();
+        sqlx::query("INSERT INTO servers VALUES (?)")
+            .bind(&config)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        let (stored,): (String,) = sqlx::query_as("SELECT config FROM servers")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(
+            stored,
+            "ss://YWVzLTEyOC1nY206dGVzdA@example.com:8388/?plugin=obfs-local%3Bobfs%3Dhttp&a=2&b=1#Foo"
+        );
+        let (found,): (SSConfig,) = sqlx::query_as("SELECT config FROM servers WHERE config = ?")
+            .bind(&config)
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(found, config.canonicalize());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{Mode, SSConfig};
+
+impl SSConfig {
+    /// arguments describing the server for shadowsocks-rust's `sslocal`, the local listener
+    /// (`-b`) is left to the caller. passwords starting with `-` are passed as `--password=`
+    /// so they aren't taken for a flag, udp is turned on with `-u` or `-U` following
+    /// [`SSConfig::mode`]
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse(
+    ///     "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(
+    ///     config.to_sslocal_args(),
+    ///     [
+    ///         "-s", "192.168.100.1:8888", "-m", "aes-128-gcm", "-k", "test",
+    ///         "--plugin", "obfs-local", "--plugin-opts", "obfs=http",
+    ///     ]
+    /// );
+    /// ```
+    pub fn to_sslocal_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "-s".to_string(),
+            format!("{}:{}", Self::get_uri_formatted_host(&self.host), self.port),
+            "-m".to_string(),
+            self.method.to_string(),
+        ];
+        if self.password.starts_with('-') {
+            args.push(format!("--password={}", self.password));
+        } else {
+            args.push("-k".to_string());
+            args.push(self.password.clone());
+        }
+        match self.mode {
+            Some(Mode::UdpOnly) => args.push("-u".to_string()),
+            Some(Mode::TcpAndUdp) => args.push("-U".to_string()),
+            Some(Mode::TcpOnly) | None => {}
+        }
+        if let Some(plugin) = self.plugin() {
+            args.push("--plugin".to_string());
+            args.push(plugin.name);
+            if let Some(opts) = plugin.opts {
+                args.push("--plugin-opts".to_string());
+                args.push(opts);
+            }
+        }
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_ipv6_servers_and_odd_passwords_as_single_arguments() {
+        let mut config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888").unwrap();
+        config.password = "-k with spaces".to_string();
+        assert_eq!(
+            config.to_sslocal_args(),
+            [
+                "-s",
+                "[2001:db8::1]:8888",
+                "-m",
+                "aes-128-gcm",
+                "--password=-k with spaces"
+            ]
+        );
+    }
+
+    #[test]
+    fn turns_udp_on_following_the_mode() {
+        let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888").unwrap();
+        let flags = |mode| {
+            let mut config = config.clone();
+            config.mode = mode;
+            config
+                .to_sslocal_args()
+                .into_iter()
+                .skip(6)
+                .collect::<Vec<_>>()
+        };
+        assert!(flags(None).is_empty());
+        assert!(flags(Some(Mode::TcpOnly)).is_empty());
+        assert_eq!(flags(Some(Mode::UdpOnly)), ["-u"]);
+        assert_eq!(flags(Some(Mode::TcpAndUdp)), ["-U"]);
+    }
+}
@@ -0,0 +1,100 @@
+//! proptest strategies for property testing code that handles shadowsocks uris, every
+//! generated [`SSConfig`] survives `to_uri(Compat::Spec)` followed by `parse`
+//! ```
+//! use proptest::prelude::*;
+//! use ss_uri::{strategies, Compat, SSConfig};
+//! proptest!(|(config in strategies::config())| {
+//!     let uri = config.to_uri(Compat::Spec);
+//!     prop_assert_eq!(SSConfig::parse(&uri), Ok(config));
+//! });
+//! ```
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use proptest::prelude::*;
+use url::Host;
+
+use crate::{Method, Plugin, SSConfig};
+
+/// ipv4, ipv6 and lowercase domain hosts
+pub fn host() -> impl Strategy<Value = Host> {
+    prop_oneof![
+        any::<u32>().prop_map(|ip| Host::Ipv4(Ipv4Addr::from(ip))),
+        any::<u128>().prop_map(|ip| Host::Ipv6(Ipv6Addr::from(ip))),
+        "([a-z0-9]{1,12}\\.){0,3}[a-z][a-z0-9]{0,11}".prop_map(Host::Domain),
+    ]
+}
+
+pub fn port() -> impl Strategy<Value = u16> {
+    1..=u16::MAX
+}
+
+pub fn method() -> impl Strategy<Value = Method> {
+    proptest::sample::select(Method::ALL.to_vec())
+}
+
+/// passwords biased towards characters that trip up uri handling
+pub fn password() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[:@%/?#&=+ ]{0,8}",
+        "[a-zA-Z0-9:@%]{0,32}",
+        "\\PC{0,16}",
+        any::<String>(),
+    ]
+}
+
+/// non-empty tags, including unicode and characters that need percent encoding
+pub fn tag() -> impl Strategy<Value = String> {
+    prop_oneof!["[a-zA-Z0-9 #%?&]{1,16}", "\\PC{1,16}"]
+}
+
+pub fn plugin() -> impl Strategy<Value = Plugin> {
+    let names = proptest::sample::select(vec!["obfs-local", "v2ray-plugin", "kcptun"]);
+    let options = proptest::collection::vec(("[a-z;=\\\\-]{1,8}", any::<Option<String>>()), 0..4);
+    (names, options).prop_map(|(name, options)| {
+        Plugin::from_options(
+            name,
+            options.iter().map(|(k, v)| (k.as_str(), v.as_deref())),
+        )
+    })
+}
+
+pub fn config() -> impl Strategy<Value = SSConfig> {
+    (
+        host(),
+        port(),
+        method(),
+        password(),
+        proptest::option::of(tag()),
+        proptest::option::of(plugin()),
+    )
+        .prop_map(|(host, port, method, password, tag, plugin)| SSConfig {
+            host,
+            port,
+            method,
+            password,
+            tag,
+            tag_raw: None,
+            extra: plugin.map(|plugin| [("plugin".to_string(), plugin.to_string())].into()),
+            prefix: None,
+            mode: None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Compat;
+
+    proptest! {
+        #[test]
+        fn configs_round_trip(config in config()) {
+            let uri = config.to_uri(Compat::Spec);
+            prop_assert_eq!(SSConfig::parse(&uri), Ok(config));
+        }
+
+        #[test]
+        fn plugins_round_trip(plugin in plugin()) {
+            prop_assert_eq!(Plugin::parse(&plugin.to_string()), plugin);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! rough strength estimates for the shared password of a config
+use crate::SSConfig;
+
+/// passwords and fragments guessed first, matches are worth a few bits however long they are
+const COMMON_PASSWORDS: &[&str] = &[
+    "password",
+    "passw0rd",
+    "qwerty",
+    "asdf",
+    "zxcv",
+    "letmein",
+    "welcome",
+    "admin",
+    "root",
+    "login",
+    "secret",
+    "iloveyou",
+    "monkey",
+    "dragon",
+    "shadowsocks",
+    "shadow",
+    "socks",
+    "proxy",
+    "vpn",
+    "test",
+    "123456",
+    "111111",
+    "000000",
+];
+
+/// how hard a password is to guess, ordered from weakest to strongest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Strength {
+    /// below 28 bits, guessed in seconds offline
+    VeryWeak,
+    /// below 36 bits
+    Weak,
+    /// below 60 bits
+    Fair,
+    Strong,
+}
+
+impl Strength {
+    pub fn from_entropy(bits: f64) -> Self {
+        match bits {
+            b if b < 28.0 => Strength::VeryWeak,
+            b if b < 36.0 => Strength::Weak,
+            b if b < 60.0 => Strength::Fair,
+            _ => Strength::Strong,
+        }
+    }
+    /// the strength of `password` according to [`password_entropy`]
+    pub fn of(password: &str) -> Self {
+        Self::from_entropy(password_entropy(password))
+    }
+}
+
+/// estimated entropy of `password` in bits, the size of the character classes it draws from
+/// for every character, except that common passwords count as a handful of bits and
+/// characters repeating or continuing a sequence like `abc` or `321` as one bit each
+///
+/// it only looks at the password itself, a generated one that happens to contain a word
+/// comes out lower than it should, a phrase of dictionary words higher
+/// ```
+/// use ss_uri::{password_entropy, Strength};
+/// assert_eq!(Strength::of("password123"), Strength::VeryWeak);
+/// assert_eq!(Strength::of("8Jp2nQ4xLw7vR1bZ"), Strength::Strong);
+/// assert!(password_entropy("aaaaaaaaaaaa") < password_entropy("a8Kz"));
+/// ```
+pub fn password_entropy(password: &str) -> f64 {
+    let chars = password.chars().collect::<Vec<_>>();
+    let lowered = chars
+        .iter()
+        .map(char::to_ascii_lowercase)
+        .collect::<Vec<_>>();
+    let mut bits = 0.0;
+    let mut covered = vec![false; chars.len()];
+    for word in COMMON_PASSWORDS {
+        let word = word.chars().collect::<Vec<_>>();
+        let mut start = 0;
+        while start + word.len() <= chars.len() {
+            let end = start + word.len();
+            if lowered[start..end] == word[..] && !covered[start..end].contains(&true) {
+                covered[start..end].fill(true);
+                bits += (COMMON_PASSWORDS.len() as f64).log2();
+                start = end;
+            } else {
+                start += 1;
+            }
+        }
+    }
+
+    let per_char = (pool_size(&chars) as f64).log2();
+    let mut previous: Option<char> = None;
+    for (c, covered) in chars.iter().zip(covered) {
+        if covered {
+            previous = None;
+            continue;
+        }
+        let patterned = previous.is_some_and(|p| (p as i64 - *c as i64).abs() <= 1);
+        bits += if patterned { 1.0 } else { per_char };
+        previous = Some(*c);
+    }
+    bits
+}
+
+/// how many characters an attacker has to try per position
+fn pool_size(chars: &[char]) -> u32 {
+    let has = |f: fn(&char) -> bool| chars.iter().any(f);
+    let mut pool = 0;
+    if has(char::is_ascii_lowercase) {
+        pool += 26;
+    }
+    if has(char::is_ascii_uppercase) {
+        pool += 26;
+    }
+    if has(char::is_ascii_digit) {
+        pool += 10;
+    }
+    if has(|c| c.is_ascii() && !c.is_ascii_alphanumeric()) {
+        pool += 33;
+    }
+    if has(|c| !c.is_ascii()) {
+        pool += 100;
+    }
+    pool.max(1)
+}
+
+impl SSConfig {
+    /// [`Strength::of`] the password, for refusing to hand out links with a guessable secret
+    pub fn password_strength(&self) -> Strength {
+        Strength::of(&self.password)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_are_cheap() {
+        assert_eq!(password_entropy(""), 0.0);
+        for weak in [
+            "test",
+            "12345678",
+            "abcdefghijklmnop",
+            "zzzzzzzzzzzzzzzzzzzz",
+            "Shadowsocks2024",
+            "qwertyQWERTY",
+        ] {
+            assert!(Strength::of(weak) <= Strength::Weak, "{weak}");
+        }
+        assert!(password_entropy("PASSWORD") < password_entropy("PASXWORD"));
+    }
+
+    #[test]
+    fn generated_passwords_are_strong() {
+        for strong in [
+            "fk3L9qZ0pW2mX7vB",
+            "s0me-Random_Words!here",
+            "тайный-пароль-42",
+            "YWVzLTI1Ni1nY206dGVzdA",
+        ] {
+            assert_eq!(Strength::of(strong), Strength::Strong, "{strong}");
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use core::fmt;
+
+use serde::Deserialize;
+
+use crate::{entry, Limits, ParseOptions, SSConfig, SSParseError, Sip008Document};
+
+/// bytes per gigabyte as used by SSD's traffic counters
+const SSD_GIGABYTE: f64 = (1u64 << 30) as f64;
+
+/// the format a subscription payload was detected as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubscriptionSource {
+    /// base64 encoded `ss://` lines
+    Base64Uris,
+    /// plain `ss://` lines
+    PlainUris,
+    Sip008,
+    /// ShadowsocksD `ssd://` payload
+    Ssd,
+    /// clash config, only detected with the `clash` feature
+    Clash,
+}
+
+/// a decoded subscription along with whatever metadata its format carries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    pub source: SubscriptionSource,
+    pub configs: Vec<SSConfig>,
+    /// provider name, SSD's `airport`
+    pub name: Option<String>,
+    pub bytes_used: Option<u64>,
+    pub bytes_remaining: Option<u64>,
+    /// expiry as given by the provider, SSD uses `YYYY-MM-DD HH:MM:SS`
+    pub expiry: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubscriptionError {
+    InvalidEncoding,
+    UnknownFormat,
+    InvalidDocument,
+    InvalidConfig(SSParseError),
+    /// the body or its decoded payload is longer than [`Limits::max_subscription_len`]
+    LimitExceeded,
+}
+impl fmt::Display for SubscriptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for SubscriptionError {}
+
+#[derive(Deserialize)]
+struct SsdDocument {
+    airport: Option<String>,
+    port: Option<u16>,
+    encryption: Option<String>,
+    password: Option<String>,
+    plugin: Option<String>,
+    plugin_options: Option<String>,
+    traffic_used: Option<f64>,
+    traffic_total: Option<f64>,
+    expiry: Option<String>,
+    servers: Vec<SsdServer>,
+}
+
+/// servers inherit missing fields from the document
+#[derive(Deserialize)]
+struct SsdServer {
+    server: String,
+    port: Option<u16>,
+    encryption: Option<String>,
+    password: Option<String>,
+    plugin: Option<String>,
+    plugin_options: Option<String>,
+    remarks: Option<String>,
+}
+
+impl Subscription {
+    /// detects the format of a fetched subscription body and decodes it, lines that aren't
+    /// `ss://` uris are skipped in uri lists
+    /// ```
+    /// use ss_uri::{Subscription, SubscriptionSource};
+    /// let body = base64::encode("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo\nvmess://abc\n");
+    /// let subscription = Subscription::parse(body.as_bytes()).unwrap();
+    /// assert_eq!(subscription.source, SubscriptionSource::Base64Uris);
+    /// assert_eq!(subscription.configs[0].tag, Some("Foo".to_string()));
+    /// ```
+    pub fn parse(body: &[u8]) -> Result<Self, SubscriptionError> {
+        Self::parse_with_options(body, &ParseOptions::default())
+    }
+
+    /// [`Subscription::parse`] with the given options, every uri goes through
+    /// [`SSConfig::parse_with_options`] and the body is bounded by [`Limits::max_subscription_len`]
+    /// ```
+    /// use ss_uri::{Limits, ParseOptions, Subscription, SubscriptionError};
+    /// let body = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888\n".repeat(100);
+    /// let bounded = ParseOptions {
+    ///     limits: Limits {
+    ///         max_subscription_len: Some(1024),
+    ///         ..Limits::UNTRUSTED
+    ///     },
+    ///     ..ParseOptions::default()
+    /// };
+    /// assert_eq!(
+    ///     Subscription::parse_with_options(body.as_bytes(), &bounded),
+    ///     Err(SubscriptionError::LimitExceeded)
+    /// );
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(len = body.len()), err(level = "debug"))
+    )]
+    pub fn parse_with_options(
+        body: &[u8],
+        options: &ParseOptions,
+    ) -> Result<Self, SubscriptionError> {
+        check_len(&options.limits, body.len())?;
+        let body = std::str::from_utf8(body).map_err(|_| SubscriptionError::InvalidEncoding)?;
+        let body = entry::trim_input(body);
+        if let Some(payload) = entry::strip_scheme(body, "ssd") {
+            return Self::parse_ssd(payload, &options.limits);
+        }
+        if body.starts_with('{') {
+            let document =
+                Sip008Document::parse(body).map_err(|_| SubscriptionError::InvalidDocument)?;
+            let mut subscription = Self::new(
+                SubscriptionSource::Sip008,
+                document
+                    .to_configs()
+                    .map_err(SubscriptionError::InvalidConfig)?,
+            );
+            subscription.bytes_used = document.bytes_used;
+            subscription.bytes_remaining = document.bytes_remaining;
+            return Ok(subscription);
+        }
+        if body.lines().any(is_ss_line) {
+            return Ok(Self::new(
+                SubscriptionSource::PlainUris,
+                parse_lines(body, options)?,
+            ));
+        }
+        #[cfg(feature = "clash")]
+        if body.lines().any(|line| line.starts_with("proxies:")) {
+            let configs = crate::parse_clash_proxies(body).map_err(|e| match e {
+                crate::ClashError::InvalidConfig(e) => SubscriptionError::InvalidConfig(e),
+                _ => SubscriptionError::InvalidDocument,
+            })?;
+            return Ok(Self::new(SubscriptionSource::Clash, configs));
+        }
+        let compact = body.split_whitespace().collect::<String>();
+        check_len(&options.limits, decoded_len(&compact))?;
+        match entry::decode_base64_lenient(&compact) {
+            Some(decoded) if decoded.lines().any(is_ss_line) => Ok(Self::new(
+                SubscriptionSource::Base64Uris,
+                parse_lines(&decoded, options)?,
+            )),
+            _ => Err(SubscriptionError::UnknownFormat),
+        }
+    }
+
+    fn new(source: SubscriptionSource, configs: Vec<SSConfig>) -> Self {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(?source, configs = configs.len(), "decoded subscription");
+        Self {
+            source,
+            configs,
+            name: None,
+            bytes_used: None,
+            bytes_remaining: None,
+            expiry: None,
+        }
+    }
+
+    fn parse_ssd(payload: &str, limits: &Limits) -> Result<Self, SubscriptionError> {
+        let payload = payload.trim();
+        check_len(limits, decoded_len(payload))?;
+        let json =
+            entry::decode_base64_lenient(payload).ok_or(SubscriptionError::InvalidEncoding)?;
+        let document: SsdDocument =
+            serde_json::from_str(&json).map_err(|_| SubscriptionError::InvalidDocument)?;
+        let configs = document
+            .servers
+            .iter()
+            .map(|server| {
+                let port = server.port.or(document.port);
+                let method = server.encryption.as_ref().or(document.encryption.as_ref());
+                let password = server.password.as_ref().or(document.password.as_ref());
+                let (port, method, password) = match (port, method, password) {
+                    (Some(port), Some(method), Some(password)) => (port, method, password),
+                    _ => return Err(SubscriptionError::InvalidDocument),
+                };
+                entry::build_config(
+                    &server.server,
+                    port,
+                    method,
+                    password,
+                    server.remarks.clone().filter(|r| !r.is_empty()),
+                    server
+                        .plugin
+                        .as_ref()
+                        .or(document.plugin.as_ref())
+                        .map(String::as_str),
+                    server
+                        .plugin_options
+                        .as_ref()
+                        .or(document.plugin_options.as_ref())
+                        .map(String::as_str),
+                )
+                .map_err(SubscriptionError::InvalidConfig)
+            })
+            .collect::<Result<_, _>>()?;
+        let gigabytes = |gb: f64| (gb * SSD_GIGABYTE) as u64;
+        let mut subscription = Self::new(SubscriptionSource::Ssd, configs);
+        subscription.name = document.airport;
+        subscription.bytes_used = document.traffic_used.map(gigabytes);
+        subscription.bytes_remaining = document
+            .traffic_total
+            .zip(document.traffic_used)
+            .map(|(total, used)| gigabytes((total - used).max(0.0)));
+        subscription.expiry = document.expiry;
+        Ok(subscription)
+    }
+}
+
+fn is_ss_line(line: &str) -> bool {
+    entry::strip_scheme(line.trim(), "ss").is_some()
+}
+
+fn parse_lines(body: &str, options: &ParseOptions) -> Result<Vec<SSConfig>, SubscriptionError> {
+    body.lines()
+        .map(str::trim)
+        .filter(|line| is_ss_line(line))
+        .map(|line| {
+            SSConfig::parse_with_options(line, options).map_err(SubscriptionError::InvalidConfig)
+        })
+        .collect()
+}
+
+/// upper bound of what `encoded` decodes to, known before decoding it
+fn decoded_len(encoded: &str) -> usize {
+    encoded.len() / 4 * 3 + 3
+}
+
+fn check_len(limits: &Limits, len: usize) -> Result<(), SubscriptionError> {
+    match limits.max_subscription_len {
+        Some(max) if len > max => Err(SubscriptionError::LimitExceeded),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URIS: &str = "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Foo\r\n\r\ntrojan://p@example.com:443\nss://YmYtY2ZiOnRlc3RAMTkyLjE2OC4xMDAuMTo4ODg4#Bar\n";
+
+    #[test]
+    fn detects_uri_lists() {
+        let plain = Subscription::parse(URIS.as_bytes()).unwrap();
+        assert_eq!(plain.source, SubscriptionSource::PlainUris);
+        assert_eq!(plain.configs.len(), 2);
+
+        let encoded = base64::encode_config(URIS, base64::URL_SAFE);
+        let wrapped = encoded
+            .as_bytes()
+            .chunks(76)
+            .map(|c| std::str::from_utf8(c).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let decoded = Subscription::parse(wrapped.as_bytes()).unwrap();
+        assert_eq!(decoded.source, SubscriptionSource::Base64Uris);
+        assert_eq!(decoded.configs, plain.configs);
+    }
+
+    #[test]
+    fn detects_sip008_documents() {
+        let subscription = Subscription::parse(
+            br#"{"version": 1, "bytes_used": 10, "servers": [{"id": "a", "server": "example.com", "server_port": 8388, "password": "p", "method": "aes-256-gcm"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(subscription.source, SubscriptionSource::Sip008);
+        assert_eq!(subscription.configs[0].port, 8388);
+        assert_eq!(subscription.bytes_used, Some(10));
+    }
+
+    #[test]
+    fn detects_ssd_payloads() {
+        let json = r#"{
+            "airport": "Airport",
+            "port": 8388,
+            "encryption": "aes-128-gcm",
+            "password": "password",
+            "traffic_used": 1.5,
+            "traffic_total": 10,
+            "expiry": "2030-01-01 00:00:00",
+            "servers": [
+                {"id": 1, "server": "a.example.com", "remarks": "A"},
+                {"id": 2, "server": "b.example.com", "port": 443, "encryption": "chacha20-ietf-poly1305", "plugin": "obfs-local", "plugin_options": "obfs=http", "remarks": "B"}
+            ]
+        }"#;
+        let body = format!("ssd://{}", base64::encode(json));
+        let subscription = Subscription::parse(body.as_bytes()).unwrap();
+        assert_eq!(subscription.source, SubscriptionSource::Ssd);
+        assert_eq!(subscription.name, Some("Airport".to_string()));
+        assert_eq!(subscription.bytes_used, Some(3 << 29));
+        assert_eq!(subscription.bytes_remaining, Some(17 << 29));
+        assert_eq!(subscription.configs[0].port, 8388);
+        assert_eq!(subscription.configs[1].password, "password");
+        assert_eq!(
+            subscription.configs[1].plugin().unwrap().to_string(),
+            "obfs-local;obfs=http"
+        );
+    }
+
+    #[cfg(feature = "clash")]
+    #[test]
+    fn detects_clash_configs() {
+        let subscription = Subscription::parse(
+            b"port: 7890\nproxies:\n  - {name: a, type: ss, server: example.com, port: 443, cipher: aes-128-gcm, password: p}\n",
+        )
+        .unwrap();
+        assert_eq!(subscription.source, SubscriptionSource::Clash);
+        assert_eq!(subscription.configs.len(), 1);
+    }
+
+    #[test]
+    fn bounds_bodies_payloads_and_uris() {
+        let bounded = |limits| ParseOptions {
+            limits,
+            ..ParseOptions::default()
+        };
+        let encoded = base64::encode(URIS);
+        let ssd = format!("ssd://{}", base64::encode(r#"{"servers": []}"#));
+        for body in [URIS, &encoded, &ssd] {
+            let options = bounded(Limits::UNTRUSTED);
+            assert!(Subscription::parse_with_options(body.as_bytes(), &options).is_ok());
+            let limits = Limits {
+                max_subscription_len: Some(body.len() - 1),
+                ..Limits::default()
+            };
+            assert_eq!(
+                Subscription::parse_with_options(body.as_bytes(), &bounded(limits)),
+                Err(SubscriptionError::LimitExceeded),
+                "{body}"
+            );
+        }
+        let limits = Limits {
+            max_input_len: Some(32),
+            ..Limits::default()
+        };
+        assert_eq!(
+            Subscription::parse_with_options(URIS.as_bytes(), &bounded(limits)),
+            Err(SubscriptionError::InvalidConfig(
+                SSParseError::LimitExceeded
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_payloads() {
+        assert_eq!(
+            Subscription::parse(b"<html></html>"),
+            Err(SubscriptionError::UnknownFormat)
+        );
+        assert_eq!(
+            Subscription::parse(&[0xff, 0xfe]),
+            Err(SubscriptionError::InvalidEncoding)
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use core::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// traffic and expiry information returned by subscription endpoints in the
+/// `subscription-userinfo` header, e.g. `upload=455727941; download=6174315083; total=1073741824000; expire=1671815872`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SubscriptionInfo {
+    /// uploaded bytes
+    pub upload: Option<u64>,
+    /// downloaded bytes
+    pub download: Option<u64>,
+    /// traffic quota in bytes
+    pub total: Option<u64>,
+    /// expiry of the subscription, `expire=0` is treated as never expiring
+    pub expire: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubscriptionInfoParseError {
+    InvalidPair,
+    InvalidNumber,
+}
+impl fmt::Display for SubscriptionInfoParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for SubscriptionInfoParseError {}
+
+impl SubscriptionInfo {
+    /// parses the value of a `subscription-userinfo` header, unknown keys are ignored
+    /// ```
+    /// use ss_uri::SubscriptionInfo;
+    /// let info = SubscriptionInfo::parse("upload=1024; download=2048; total=10240; expire=0").unwrap();
+    /// assert_eq!(info.used(), 3072);
+    /// assert_eq!(info.remaining(), Some(7168));
+    /// assert_eq!(info.expire, None);
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, SubscriptionInfoParseError> {
+        let mut info = SubscriptionInfo::default();
+        for pair in input.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or(SubscriptionInfoParseError::InvalidPair)?;
+            let key = key.trim().to_ascii_lowercase();
+            let parse = || {
+                value
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|_| SubscriptionInfoParseError::InvalidNumber)
+            };
+            match key.as_str() {
+                "upload" => info.upload = Some(parse()?),
+                "download" => info.download = Some(parse()?),
+                "total" => info.total = Some(parse()?),
+                "expire" => {
+                    info.expire = match parse()? {
+                        0 => None,
+                        seconds => Some(
+                            SystemTime::UNIX_EPOCH
+                                .checked_add(Duration::from_secs(seconds))
+                                .ok_or(SubscriptionInfoParseError::InvalidNumber)?,
+                        ),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(info)
+    }
+    /// uploaded plus downloaded bytes
+    pub fn used(&self) -> u64 {
+        self.upload
+            .unwrap_or(0)
+            .saturating_add(self.download.unwrap_or(0))
+    }
+    /// bytes left before reaching the quota, `None` when there is no quota
+    pub fn remaining(&self) -> Option<u64> {
+        self.total.map(|total| total.saturating_sub(self.used()))
+    }
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        self.expire.is_some_and(|expire| expire <= now)
+    }
+}
+
+impl FromStr for SubscriptionInfo {
+    type Err = SubscriptionInfoParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SubscriptionInfo::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_a_full_subscription_userinfo_header() {
+        let info = SubscriptionInfo::parse(
+            "upload=455727941; download=6174315083; total=1073741824000; expire=1671815872",
+        )
+        .unwrap();
+        assert_eq!(info.upload, Some(455727941));
+        assert_eq!(info.download, Some(6174315083));
+        assert_eq!(info.total, Some(1073741824000));
+        assert_eq!(
+            info.expire,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1671815872))
+        );
+        assert_eq!(info.used(), 6630043024);
+        assert_eq!(info.remaining(), Some(1073741824000 - 6630043024));
+        assert!(info.is_expired_at(SystemTime::UNIX_EPOCH + Duration::from_secs(1671815872)));
+    }
+
+    #[test]
+    fn tolerates_missing_and_unknown_keys() {
+        let info = SubscriptionInfo::parse("Download=10;foo=bar;").unwrap();
+        assert_eq!(info.download, Some(10));
+        assert_eq!(info.upload, None);
+        assert_eq!(info.remaining(), None);
+        assert!(!info.is_expired_at(SystemTime::now()));
+    }
+
+    #[test]
+    fn rejects_malformed_values() {
+        assert_eq!(
+            SubscriptionInfo::parse("upload"),
+            Err(SubscriptionInfoParseError::InvalidPair)
+        );
+        assert_eq!(
+            SubscriptionInfo::parse("upload=-1"),
+            Err(SubscriptionInfoParseError::InvalidNumber)
+        );
+        assert_eq!(
+            SubscriptionInfo::parse("expire=18446744073709551615"),
+            Err(SubscriptionInfoParseError::InvalidNumber)
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use core::fmt;
+
+use crate::{entry, Mode, Plugin, QueryParams, SSConfig, SSParseError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SurgeError {
+    InvalidLine,
+    UnsupportedType,
+    InvalidConfig(SSParseError),
+    /// surge only understands simple-obfs
+    UnsupportedPlugin,
+}
+impl fmt::Display for SurgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for SurgeError {}
+
+impl SSConfig {
+    /// parses a surge proxy line, `obfs` options become an `obfs-local` plugin, `udp-relay` is
+    /// read into [`SSConfig::mode`] and other options are kept as they are
+    /// ```
+    /// use ss_uri::SSConfig;
+    /// let config = SSConfig::parse_surge_line(
+    ///     "Proxy = ss, 1.2.3.4, 8000, encrypt-method=chacha20-ietf-poly1305, password=abcd1234, obfs=http, obfs-host=bing.com, udp-relay=true",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(config.tag, Some("Proxy".to_string()));
+    /// assert_eq!(config.plugin().unwrap().to_string(), "obfs-local;obfs=http;obfs-host=bing.com");
+    /// ```
+    pub fn parse_surge_line(line: &str) -> Result<SSConfig, SurgeError> {
+        let (name, definition) = line.split_once('=').ok_or(SurgeError::InvalidLine)?;
+        let fields = entry::split_fields(definition);
+        let (proxy_type, server, port) = match fields[..] {
+            [proxy_type, server, port, ..] => (proxy_type, server, port),
+            _ => return Err(SurgeError::InvalidLine),
+        };
+        if proxy_type != "ss" {
+            return Err(SurgeError::UnsupportedType);
+        }
+        let port = port.parse().map_err(|_| SurgeError::InvalidLine)?;
+        let mut method = None;
+        let mut password = None;
+        let mut obfs = Vec::new();
+        let mut extra = QueryParams::new();
+        let pairs = fields[3..]
+            .iter()
+            .map(|field| entry::split_pair(field))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(SurgeError::InvalidLine)?;
+        for (key, value) in &pairs {
+            let (key, value) = (*key, value.as_ref());
+            match key {
+                "encrypt-method" => method = Some(value),
+                "password" => password = Some(value),
+                "obfs" | "obfs-host" | "obfs-uri" => obfs.push((key, Some(value))),
+                "udp-relay" => {
+                    extra.insert("udp".to_string(), value.to_string());
+                }
+                key => {
+                    extra.insert(key.to_string(), value.to_string());
+                }
+            }
+        }
+        let plugin = (!obfs.is_empty()).then(|| Plugin::from_options("obfs-local", obfs));
+        let mut config = entry::build_config(
+            &entry::unquote(server),
+            port,
+            method.ok_or(SurgeError::InvalidLine)?,
+            password.ok_or(SurgeError::InvalidLine)?,
+            Some(name.trim().to_string()).filter(|n| !n.is_empty()),
+            plugin.as_ref().map(|p| p.name.as_str()),
+            plugin.as_ref().and_then(|p| p.opts.as_deref()),
+        )
+        .map_err(SurgeError::InvalidConfig)?;
+        config.mode = Mode::take_from(&mut extra);
+        if !extra.is_empty() {
+            config
+                .extra
+                .get_or_insert_with(QueryParams::new)
+                .extend(extra);
+        }
+        Ok(config)
+    }
+    /// serializes the config as a surge proxy line, extra parameters are emitted as options
+    pub fn to_surge_line(&self) -> Result<String, SurgeError> {
+        let mut line = format!(
+            "{} = ss, {}, {}, encrypt-method={}, password={}",
+            entry::name_or_endpoint(self),
+            entry::host_to_string(&self.host),
+            self.port,
+            self.method,
+            entry::quote(&self.password)
+        );
+        if let Some(plugin) = self.plugin() {
+            if !matches!(plugin.name.as_str(), "obfs-local" | "simple-obfs") {
+                return Err(SurgeError::UnsupportedPlugin);
+            }
+            for (key, value) in plugin.options() {
+                let value = value.ok_or(SurgeError::UnsupportedPlugin)?;
+                line.push_str(&format!(", {}={}", key, entry::quote(&value)));
+            }
+        }
+        let mut extra = self.extra.iter().flatten().collect::<Vec<_>>();
+        extra.sort();
+        for (key, value) in extra {
+            let key = match key.as_str() {
+                "plugin" => continue,
+                "udp" => "udp-relay",
+                key => key,
+            };
+            line.push_str(&format!(", {}={}", key, entry::quote(value)));
+        }
+        if let Some(mode) = self.mode {
+            line.push_str(&format!(", udp-relay={}", mode.udp()));
+        }
+        Ok(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Method;
+    use url::Host;
+
+    #[test]
+    fn can_parse_a_surge_line_with_quotes() {
+        let config = SSConfig::parse_surge_line(
+            r#"My Proxy = ss, 2001:db8::1, 8000, encrypt-method=aes-128-gcm, password="a,b=c", tfo=true"#,
+        )
+        .unwrap();
+        assert_eq!(config.tag, Some("My Proxy".to_string()));
+        assert_eq!(config.host, Host::parse("[2001:db8::1]").unwrap());
+        assert_eq!(config.method, Method::Aes128Gcm);
+        assert_eq!(config.password, "a,b=c");
+        assert_eq!(config.extra.as_ref().unwrap()["tfo"], "true");
+    }
+
+    #[test]
+    fn surge_lines_round_trip() {
+        let line = r#"Proxy = ss, example.com, 8000, encrypt-method=chacha20-ietf-poly1305, password="a,b", obfs=tls, obfs-host=bing.com, tfo=true, udp-relay=true"#;
+        let config = SSConfig::parse_surge_line(line).unwrap();
+        assert_eq!(config.to_surge_line().unwrap(), line);
+    }
+
+    #[test]
+    fn passwords_with_quotes_round_trip() {
+        let mut config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888").unwrap();
+        for password in [r#"a"b,c"#, r#"a\",b"#, r#"\"#] {
+            config.password = password.to_string();
+            let line = config.to_surge_line().unwrap();
+            assert_eq!(
+                SSConfig::parse_surge_line(&line).unwrap().password,
+                password
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_lines() {
+        assert_eq!(
+            SSConfig::parse_surge_line("Proxy = vmess, example.com, 443, username=id"),
+            Err(SurgeError::UnsupportedType)
+        );
+        assert_eq!(
+            SSConfig::parse_surge_line("Proxy = ss, example.com, 443, password=a"),
+            Err(SurgeError::InvalidLine)
+        );
+        let config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=v2ray-plugin%3Btls",
+        )
+        .unwrap();
+        assert_eq!(config.to_surge_line(), Err(SurgeError::UnsupportedPlugin));
+    }
+}
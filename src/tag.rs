@@ -0,0 +1,373 @@
+//! helpers for the tags aggregators show, cleaning up what providers send, renaming servers
+//! from a template, pulling out country flags and shortening tags for narrow lists
+use core::fmt;
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::SSConfig;
+
+/// the tag without control characters and bidi overrides, line breaks and tabs become spaces
+/// and the result is trimmed
+/// ```
+/// use ss_uri::tag;
+/// assert_eq!(tag::sanitize("\u{202e}HK\t01\u{0}\n"), "HK 01");
+/// ```
+pub fn sanitize(tag: &str) -> String {
+    let mut sanitized = String::with_capacity(tag.len());
+    for c in tag.chars() {
+        if matches!(c, '\t' | '\n' | '\r') {
+            sanitized.push(' ');
+        } else if !c.is_control() && !is_bidi_control(c) {
+            sanitized.push(c);
+        }
+    }
+    sanitized.trim().to_string()
+}
+
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// the first country flag in the tag
+/// ```
+/// use ss_uri::tag;
+/// assert_eq!(tag::flag("🚀 🇭🇰 Hong Kong 01"), Some("🇭🇰"));
+/// assert_eq!(tag::flag("Hong Kong 01"), None);
+/// ```
+pub fn flag(tag: &str) -> Option<&str> {
+    tag.graphemes(true).find(|g| country_of_flag(g).is_some())
+}
+
+/// the ISO 3166 code spelled by the first country flag in the tag
+/// ```
+/// use ss_uri::tag;
+/// assert_eq!(tag::country("🇯🇵 Tokyo").as_deref(), Some("JP"));
+/// ```
+pub fn country(tag: &str) -> Option<String> {
+    flag(tag).and_then(country_of_flag)
+}
+
+/// the flag of an ISO 3166 code, the inverse of [`country`]
+/// ```
+/// use ss_uri::tag;
+/// assert_eq!(tag::flag_of("jp").as_deref(), Some("🇯🇵"));
+/// assert_eq!(tag::flag_of("JPN"), None);
+/// ```
+pub fn flag_of(country: &str) -> Option<String> {
+    if country.len() != 2 {
+        return None;
+    }
+    country
+        .chars()
+        .map(|c| {
+            let c = c.to_ascii_uppercase();
+            let offset = u32::from(c)
+                .checked_sub(u32::from(b'A'))
+                .filter(|o| *o < 26)?;
+            char::from_u32(0x1f1e6 + offset)
+        })
+        .collect()
+}
+
+fn country_of_flag(grapheme: &str) -> Option<String> {
+    let mut chars = grapheme.chars();
+    let letters = [chars.next()?, chars.next()?];
+    if chars.next().is_some() {
+        return None;
+    }
+    letters
+        .into_iter()
+        .map(|c| {
+            let offset = u32::from(c).checked_sub(0x1f1e6).filter(|o| *o < 26)?;
+            char::from_u32(u32::from(b'A') + offset)
+        })
+        .collect()
+}
+
+/// the tag without flags and other emoji, with the whitespace they leave collapsed
+/// ```
+/// use ss_uri::tag;
+/// assert_eq!(tag::strip_emoji("🇭🇰 Hong Kong 🚀 01 👨‍👩‍👧"), "Hong Kong 01");
+/// ```
+pub fn strip_emoji(tag: &str) -> String {
+    let text = tag
+        .graphemes(true)
+        .filter(|g| !is_emoji(g))
+        .collect::<String>();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// pictographs, flags and sequences built from them, digits and `#` only when turned into
+/// keycaps
+fn is_emoji(grapheme: &str) -> bool {
+    grapheme.chars().any(|c| {
+        matches!(
+            c,
+            '\u{1f000}'..='\u{1faff}' | '\u{2600}'..='\u{27bf}' | '\u{2b00}'..='\u{2bff}' | '\u{fe0f}' | '\u{20e3}'
+        )
+    })
+}
+
+/// the tag cut to fit `width` terminal columns, wide characters count twice, clusters such as
+/// flags or letters with combining marks are never split and a cut tag ends in `…`
+/// ```
+/// use ss_uri::tag;
+/// assert_eq!(tag::truncate("Hong Kong 01", 20), "Hong Kong 01");
+/// assert_eq!(tag::truncate("Hong Kong 01", 8), "Hong Ko…");
+/// assert_eq!(tag::truncate("🇭🇰 香港 01", 6), "🇭🇰 香…");
+/// ```
+pub fn truncate(tag: &str, width: usize) -> Cow<'_, str> {
+    if tag.width() <= width {
+        return Cow::Borrowed(tag);
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for grapheme in tag.graphemes(true) {
+        let grapheme_width = grapheme.width();
+        if used + grapheme_width + 1 > width {
+            break;
+        }
+        truncated.push_str(grapheme);
+        used += grapheme_width;
+    }
+    if width > 0 {
+        truncated.push('…');
+    }
+    Cow::Owned(truncated)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagTemplateError {
+    /// a `{` without its `}` or a `}` without its `{`, write `{{` and `}}` for the braces
+    UnbalancedBrace,
+    /// a placeholder other than the ones [`TagTemplate`] lists
+    UnknownField,
+    /// anything but an optional `0` and a width of at most 255 after the `:`
+    InvalidFormat,
+}
+impl fmt::Display for TagTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for TagTemplateError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Field {
+    Index,
+    Tag,
+    Name,
+    Flag,
+    Country,
+    Host,
+    Port,
+    Method,
+    Group,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "index" => Field::Index,
+            "tag" => Field::Tag,
+            "name" => Field::Name,
+            "flag" => Field::Flag,
+            "country" => Field::Country,
+            "host" => Field::Host,
+            "port" => Field::Port,
+            "method" => Field::Method,
+            "group" => Field::Group,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Part {
+    Text(String),
+    Field {
+        field: Field,
+        width: usize,
+        zero: bool,
+    },
+}
+
+/// a naming scheme such as `"{flag} {country} {index:02}"` for renaming whole lists
+///
+/// placeholders are `index` (counting from 1), `tag`, `name` (the tag without emoji), `flag`,
+/// `country`, `host`, `port`, `method` and `group`, missing values render empty. a width up to
+/// 255 after a `:` pads numbers on the left and text on the right, `0` before it pads with zeros
+/// ```
+/// use ss_uri::tag::TagTemplate;
+/// use ss_uri::SSConfig;
+/// let mut configs = [
+///     "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#%F0%9F%87%AD%F0%9F%87%B0%20HK%20Premium",
+///     "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.2:8888#%F0%9F%87%AF%F0%9F%87%B5%20Tokyo",
+/// ]
+/// .map(|uri| SSConfig::parse(uri).unwrap());
+/// TagTemplate::parse("{flag} {country} {index:02}").unwrap().apply(&mut configs);
+/// assert_eq!(configs[0].tag.as_deref(), Some("🇭🇰 HK 01"));
+/// assert_eq!(configs[1].tag.as_deref(), Some("🇯🇵 JP 02"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TagTemplate {
+    parts: Vec<Part>,
+}
+
+impl TagTemplate {
+    pub fn parse(template: &str) -> Result<Self, TagTemplateError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut rest = template;
+        while let Some(i) = rest.find(['{', '}']) {
+            text.push_str(&rest[..i]);
+            let brace = &rest[i..i + 1];
+            rest = &rest[i + 1..];
+            if let Some(after) = rest.strip_prefix(brace) {
+                text.push_str(brace);
+                rest = after;
+                continue;
+            }
+            if brace == "}" {
+                return Err(TagTemplateError::UnbalancedBrace);
+            }
+            let (placeholder, after) = rest
+                .split_once('}')
+                .ok_or(TagTemplateError::UnbalancedBrace)?;
+            rest = after;
+            if !text.is_empty() {
+                parts.push(Part::Text(std::mem::take(&mut text)));
+            }
+            parts.push(Self::parse_placeholder(placeholder)?);
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(TagTemplate { parts })
+    }
+
+    fn parse_placeholder(placeholder: &str) -> Result<Part, TagTemplateError> {
+        let (name, format) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+        let field = Field::parse(name).ok_or(TagTemplateError::UnknownField)?;
+        if !format.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(TagTemplateError::InvalidFormat);
+        }
+        let zero = format.len() > 1 && format.starts_with('0');
+        let width = match format {
+            "" => 0,
+            format => format
+                .parse::<u8>()
+                .map_err(|_| TagTemplateError::InvalidFormat)?
+                .into(),
+        };
+        Ok(Part::Field { field, width, zero })
+    }
+
+    /// the tag of `config` as the `index`th server of its list
+    pub fn render(&self, config: &SSConfig, index: usize) -> String {
+        let tag = config.tag.as_deref().unwrap_or_default();
+        let mut rendered = String::new();
+        for part in &self.parts {
+            let (field, width, zero) = match part {
+                Part::Text(text) => {
+                    rendered.push_str(text);
+                    continue;
+                }
+                Part::Field { field, width, zero } => (*field, *width, *zero),
+            };
+            let value = match field {
+                Field::Index => index.to_string(),
+                Field::Port => config.port.to_string(),
+                Field::Tag => tag.to_string(),
+                Field::Name => strip_emoji(tag),
+                Field::Flag => flag(tag).unwrap_or_default().to_string(),
+                Field::Country => country(tag).unwrap_or_default(),
+                Field::Host => config.host_unicode(),
+                Field::Method => config.method.to_string(),
+                Field::Group => config.group().unwrap_or_default().into_owned(),
+            };
+            // placeholders left empty shouldn't leave doubled spaces behind
+            if value.is_empty() && width == 0 {
+                if rendered.ends_with(' ') {
+                    rendered.pop();
+                }
+                continue;
+            }
+            let _ = match (field, zero) {
+                (Field::Index | Field::Port, true) => write!(rendered, "{value:0>width$}"),
+                (Field::Index | Field::Port, false) => write!(rendered, "{value:>width$}"),
+                _ => write!(rendered, "{value:<width$}"),
+            };
+        }
+        rendered.trim().to_string()
+    }
+
+    /// retags every config, numbering them from 1 in the order given
+    pub fn apply<'a>(&self, configs: impl IntoIterator<Item = &'a mut SSConfig>) {
+        for (i, config) in configs.into_iter().enumerate() {
+            let tag = self.render(config, i + 1);
+            config.tag = Some(tag);
+            config.tag_raw = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(tag: &str) -> SSConfig {
+        SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?group=Asia")
+            .unwrap()
+            .with_tag(tag)
+    }
+
+    #[test]
+    fn renders_every_placeholder() {
+        let config = config("🇸🇬 Singapore 🚀");
+        for (template, tag) in [
+            ("{index}", "7"),
+            ("{index:03}|{port:6}|", "007|  8888|"),
+            ("{name}", "Singapore"),
+            ("{flag}{country}", "🇸🇬SG"),
+            (
+                "[{group}] {host}:{port} {method}",
+                "[Asia] 192.168.100.1:8888 aes-128-gcm",
+            ),
+            ("{{{tag}}}", "{🇸🇬 Singapore 🚀}"),
+            ("{country:4}|", "SG  |"),
+            ("{index:0255}", &format!("{:0>255}", 7)),
+        ] {
+            let template = TagTemplate::parse(template).unwrap();
+            assert_eq!(template.render(&config, 7), tag);
+        }
+        let template = TagTemplate::parse("{flag} {country} {index}").unwrap();
+        assert_eq!(template.render(&self::config("Home"), 1), "1");
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        for (template, error) in [
+            ("{index", TagTemplateError::UnbalancedBrace),
+            ("index}", TagTemplateError::UnbalancedBrace),
+            ("{password}", TagTemplateError::UnknownField),
+            ("{index:>2}", TagTemplateError::InvalidFormat),
+            ("{index:256}", TagTemplateError::InvalidFormat),
+            ("{index:1000000000000}", TagTemplateError::InvalidFormat),
+        ] {
+            assert_eq!(TagTemplate::parse(template), Err(error), "{template}");
+        }
+    }
+
+    #[test]
+    fn truncation_keeps_clusters_whole() {
+        assert_eq!(truncate("Cafe\u{301} Paris", 5), "Cafe\u{301}…");
+        assert_eq!(truncate("🇭🇰🇭🇰🇭🇰", 5), "🇭🇰🇭🇰…");
+        assert_eq!(truncate("香港香港", 4), "香…");
+        assert_eq!(truncate("abc", 0), "");
+    }
+}
@@ -0,0 +1,87 @@
+//! keyword filtering of servers by tag, the include/exclude patterns subscription converters
+//! take
+use regex::RegexSet;
+
+use crate::{SSConfig, ServerList};
+
+/// keeps tags matching any include pattern, every tag when there are none, unless they also
+/// match an exclude pattern, configs without a tag are matched as an empty tag
+/// ```
+/// use ss_uri::{SSConfig, ServerList, TagFilter};
+/// let list = ["HK%2001", "HK%2002%20%E8%BF%87%E6%9C%9F", "JP%2001", "US%2001"]
+///     .into_iter()
+///     .map(|tag| SSConfig::parse(&format!("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#{tag}")).unwrap())
+///     .collect::<ServerList>();
+/// let filter = TagFilter::new(["^HK", "(?i)jp"], ["过期"]).unwrap();
+/// let filtered = filter.apply(list);
+/// let tags = |list: &ServerList| list.iter().map(|c| c.tag.clone().unwrap()).collect::<Vec<_>>();
+/// assert_eq!(tags(&filtered.matched), ["HK 01", "JP 01"]);
+/// assert_eq!(tags(&filtered.rejected), ["HK 02 过期", "US 01"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TagFilter {
+    include: RegexSet,
+    exclude: RegexSet,
+}
+
+/// the result of [`TagFilter::apply`], both lists keep the input order
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FilteredServers {
+    pub matched: ServerList,
+    pub rejected: ServerList,
+}
+
+impl TagFilter {
+    pub fn new<I, E>(include: I, exclude: E) -> Result<Self, regex::Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        E: IntoIterator,
+        E::Item: AsRef<str>,
+    {
+        Ok(TagFilter {
+            include: RegexSet::new(include)?,
+            exclude: RegexSet::new(exclude)?,
+        })
+    }
+    pub fn matches(&self, config: &SSConfig) -> bool {
+        let tag = config.tag.as_deref().unwrap_or_default();
+        (self.include.is_empty() || self.include.is_match(tag)) && !self.exclude.is_match(tag)
+    }
+    pub fn apply(&self, configs: impl IntoIterator<Item = SSConfig>) -> FilteredServers {
+        let mut filtered = FilteredServers::default();
+        for config in configs {
+            if self.matches(&config) {
+                filtered.matched.push(config);
+            } else {
+                filtered.rejected.push(config);
+            }
+        }
+        filtered
+    }
+}
+
+impl ServerList {
+    /// keeps the configs `filter` matches
+    pub fn retain_tags(&mut self, filter: &TagFilter) {
+        self.retain(|config| filter.matches(config));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_pattern_lists_keep_everything() {
+        let untagged = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888").unwrap();
+        let tagged = untagged.clone().with_tag("剩余流量：10GB");
+        let filter = TagFilter::new([""; 0], [""; 0]).unwrap();
+        assert!(filter.matches(&untagged) && filter.matches(&tagged));
+        let filter = TagFilter::new([""; 0], ["剩余流量|到期"]).unwrap();
+        let mut list = ServerList::from(vec![tagged, untagged.clone()]);
+        list.retain_tags(&filter);
+        assert_eq!(list.into_vec(), [untagged]);
+        assert!(TagFilter::new(["("], [""; 0]).is_err());
+    }
+}
@@ -0,0 +1,40 @@
+//! helpers shared by the randomized tests, seeded so failures reproduce
+
+/// xorshift64, good enough to shuffle test inputs
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    /// a number below `bound`
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        self.next_u64() as usize % bound
+    }
+}
+
+/// hands `check` `rounds` inputs, each one of `seeds` with one to three characters inserted
+/// from `alphabet`, removed or replaced by one from `alphabet`
+pub(crate) fn mutate(seeds: &[&str], alphabet: &str, rounds: usize, mut check: impl FnMut(&str)) {
+    let alphabet = alphabet.chars().collect::<Vec<_>>();
+    let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+    for _ in 0..rounds {
+        let mut input = seeds[rng.below(seeds.len())].chars().collect::<Vec<_>>();
+        for _ in 0..=rng.below(3) {
+            let at = rng.below(input.len() + 1);
+            match rng.below(3) {
+                0 => input.insert(at, alphabet[rng.below(alphabet.len())]),
+                1 if at < input.len() => drop(input.remove(at)),
+                _ if at < input.len() => input[at] = alphabet[rng.below(alphabet.len())],
+                _ => {}
+            }
+        }
+        check(&input.into_iter().collect::<String>());
+    }
+}
@@ -0,0 +1,156 @@
+//! checks for configs that were built or deserialized rather than parsed
+use core::fmt;
+
+use url::Host;
+
+use crate::{Plugin, SSConfig};
+
+/// a broken invariant of a config, [`SSConfig::validate`] reports every one it finds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValidationError {
+    /// port 0, which no client can connect to
+    InvalidPort,
+    /// an empty password with an aead method, which derives its key from the password
+    EmptyPassword,
+    /// the `plugin` parameter has options but no plugin name
+    EmptyPluginName,
+    /// the plugin options end in a dangling `\` or have an option without a key
+    InvalidPluginOptions,
+    /// a domain the url parser rejects, an empty one or one with an overlong label
+    InvalidHost,
+    /// `0.0.0.0` or `::`, which only make sense for listening
+    UnspecifiedHost,
+}
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for ValidationError {}
+
+impl SSConfig {
+    /// checks the config as a whole, parsing already rules out most of this but fields can be
+    /// set to anything after construction
+    ///
+    /// the 2022 ciphers, whose password is a key of a fixed length, aren't supported by
+    /// [`Method`](crate::Method) so there is no key length to check yet
+    /// ```
+    /// use ss_uri::{SSConfig, ValidationError};
+    /// let mut config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888").unwrap();
+    /// assert_eq!(config.validate(), Ok(()));
+    /// config.port = 0;
+    /// config.password.clear();
+    /// assert_eq!(
+    ///     config.validate(),
+    ///     Err(vec![ValidationError::InvalidPort, ValidationError::EmptyPassword])
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        if self.port == 0 {
+            errors.push(ValidationError::InvalidPort);
+        }
+        if self.password.is_empty() && self.method.is_aead() {
+            errors.push(ValidationError::EmptyPassword);
+        }
+        if let Some(plugin) = self.plugin() {
+            if plugin.name.is_empty() {
+                errors.push(ValidationError::EmptyPluginName);
+            }
+            if !valid_plugin_options(&plugin) {
+                errors.push(ValidationError::InvalidPluginOptions);
+            }
+        }
+        match &self.host {
+            Host::Domain(domain) if !valid_domain(domain) => {
+                errors.push(ValidationError::InvalidHost)
+            }
+            Host::Ipv4(ip) if ip.is_unspecified() => errors.push(ValidationError::UnspecifiedHost),
+            Host::Ipv6(ip) if ip.is_unspecified() => errors.push(ValidationError::UnspecifiedHost),
+            _ => {}
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// a trailing backslash escapes nothing, so it can't have come from an escaped option
+fn dangling_escape(s: &str) -> bool {
+    s.bytes().rev().take_while(|b| *b == b'\\').count() % 2 == 1
+}
+
+fn valid_plugin_options(plugin: &Plugin) -> bool {
+    if dangling_escape(&plugin.name) {
+        return false;
+    }
+    let opts = match &plugin.opts {
+        Some(opts) => opts,
+        None => return true,
+    };
+    !dangling_escape(opts) && plugin.options().iter().all(|(key, _)| !key.is_empty())
+}
+
+/// a trailing dot is allowed, it makes the domain fully qualified
+fn valid_domain(domain: &str) -> bool {
+    let name = domain.strip_suffix('.').unwrap_or(domain);
+    !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(|l| !l.is_empty() && l.len() <= 63)
+        && matches!(Host::parse(domain), Ok(Host::Domain(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SSConfig {
+        SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@example.com:8888").unwrap()
+    }
+
+    #[test]
+    fn reports_every_violation() {
+        let mut config = config();
+        config.host = Host::Domain("exa mple..com".to_string());
+        config.port = 0;
+        config.password = String::new();
+        config.extra = Some([("plugin".to_string(), ";=http;a\\".to_string())].into());
+        assert_eq!(
+            config.validate(),
+            Err(vec![
+                ValidationError::InvalidPort,
+                ValidationError::EmptyPassword,
+                ValidationError::EmptyPluginName,
+                ValidationError::InvalidPluginOptions,
+                ValidationError::InvalidHost,
+            ])
+        );
+    }
+
+    #[test]
+    fn accepts_what_parsing_produces() {
+        for uri in [
+            "ss://YWVzLTEyOC1nY206dGVzdA@[::1]:8888/?plugin=v2ray-plugin%3Bpath%3D%5C%3Bws#Foo",
+            "ss://YWVzLTEyOC1nY206dGVzdA@example.com.:8888/?plugin=obfs-local%3Bfast-open",
+            "ss://YWVzLTEyOC1nY206dGVzdA@例え.jp:8888",
+            "ss://cmM0LW1kNTo@192.168.100.1:8888",
+        ] {
+            assert_eq!(SSConfig::parse(uri).unwrap().validate(), Ok(()), "{uri}");
+        }
+    }
+
+    #[test]
+    fn rejects_hosts_clients_cant_reach() {
+        let mut config = config();
+        for host in [
+            Host::Domain(String::new()),
+            Host::Domain(format!("{}.com", "a".repeat(64))),
+            Host::Ipv4([0, 0, 0, 0].into()),
+        ] {
+            config.host = host;
+            assert!(config.validate().is_err(), "{:?}", config.host);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! SIP008 client for wasm32 built on the global `fetch` of browsers, workers and node,
+//! the reqwest based `Sip008Client` needs sockets and a tls backend which wasm lacks
+use core::fmt;
+
+use js_sys::{Promise, Uint8Array};
+use url::Url;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Headers, Request, RequestInit, Response};
+
+use crate::{
+    parse_outline_dynamic_key, OutlineKeyError, SIP008Config, SSConfig, Sip008Document,
+    Sip008DocumentError, SubscriptionInfo,
+};
+
+pub const WASM_DEFAULT_MAX_BODY_SIZE: usize = 4 * 1024 * 1024;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = fetch)]
+    fn global_fetch(request: &Request) -> Promise;
+}
+
+/// builder for [`WasmSip008Client`]
+#[derive(Debug, Clone)]
+pub struct WasmSip008ClientBuilder {
+    headers: Vec<(String, String)>,
+    max_body_size: usize,
+    request_body: Vec<u8>,
+}
+
+/// downloads SIP008 online configurations with `fetch`, redirects and timeouts are left to
+/// the javascript runtime
+#[derive(Debug, Clone)]
+pub struct WasmSip008Client {
+    settings: WasmSip008ClientBuilder,
+}
+
+/// a fetched document along with the http metadata `fetch` exposes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmFetchResult<T = Sip008Document> {
+    pub document: T,
+    pub status: u16,
+    pub etag: Option<String>,
+    /// parsed `subscription-userinfo` header, browsers only expose it for cors requests when
+    /// the server lists it in `Access-Control-Expose-Headers`
+    pub subscription_info: Option<SubscriptionInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WasmFetchError {
+    InvalidLocation,
+    InvalidHttpMethod,
+    /// message of the javascript error `fetch` rejected with
+    Request(String),
+    /// `fetch` has no access to the peer certificate so pinned configs can't be verified
+    FingerprintUnsupported,
+    BodyTooLarge,
+    Status(u16),
+    Document(Sip008DocumentError),
+    AccessKey(OutlineKeyError),
+}
+impl fmt::Display for WasmFetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for WasmFetchError {}
+impl From<JsValue> for WasmFetchError {
+    fn from(value: JsValue) -> Self {
+        let message = match value.dyn_ref::<js_sys::Error>() {
+            Some(error) => String::from(error.message()),
+            None => value.as_string().unwrap_or_else(|| format!("{value:?}")),
+        };
+        WasmFetchError::Request(message)
+    }
+}
+
+impl<T> WasmFetchResult<T> {
+    fn try_map<U>(
+        self,
+        f: impl FnOnce(T) -> Result<U, WasmFetchError>,
+    ) -> Result<WasmFetchResult<U>, WasmFetchError> {
+        Ok(WasmFetchResult {
+            document: f(self.document)?,
+            status: self.status,
+            etag: self.etag,
+            subscription_info: self.subscription_info,
+        })
+    }
+}
+
+impl Default for WasmSip008ClientBuilder {
+    fn default() -> Self {
+        Self {
+            headers: Vec::new(),
+            max_body_size: WASM_DEFAULT_MAX_BODY_SIZE,
+            request_body: Vec::new(),
+        }
+    }
+}
+
+impl WasmSip008ClientBuilder {
+    /// adds a header sent with every request, forbidden header names are dropped by the runtime
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+    /// maximum accepted size of the response body in bytes
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+    /// body sent when the ssconf uri asks for a method other than GET, empty by default
+    pub fn request_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.request_body = body.into();
+        self
+    }
+    pub fn build(self) -> WasmSip008Client {
+        WasmSip008Client { settings: self }
+    }
+}
+
+impl Default for WasmSip008Client {
+    fn default() -> Self {
+        WasmSip008ClientBuilder::default().build()
+    }
+}
+
+impl WasmSip008Client {
+    pub fn builder() -> WasmSip008ClientBuilder {
+        WasmSip008ClientBuilder::default()
+    }
+    /// fetches and parses the document at `config.location`, configs pinning a certificate
+    /// fingerprint fail with [`WasmFetchError::FingerprintUnsupported`]
+    pub async fn fetch(&self, config: &SIP008Config) -> Result<WasmFetchResult, WasmFetchError> {
+        self.fetch_raw(config).await?.try_map(|body| {
+            let body = std::str::from_utf8(&body)
+                .map_err(|_| WasmFetchError::Document(Sip008DocumentError::InvalidJson))?;
+            Sip008Document::parse(body).map_err(WasmFetchError::Document)
+        })
+    }
+    /// resolves an outline dynamic access key, see [`parse_outline_dynamic_key`]
+    pub async fn resolve_outline_key(
+        &self,
+        config: &SIP008Config,
+    ) -> Result<WasmFetchResult<SSConfig>, WasmFetchError> {
+        self.fetch_raw(config).await?.try_map(|body| {
+            let body = std::str::from_utf8(&body)
+                .map_err(|_| WasmFetchError::AccessKey(OutlineKeyError::InvalidJson))?;
+            parse_outline_dynamic_key(body).map_err(WasmFetchError::AccessKey)
+        })
+    }
+
+    async fn fetch_raw(
+        &self,
+        config: &SIP008Config,
+    ) -> Result<WasmFetchResult<Vec<u8>>, WasmFetchError> {
+        let url = Url::parse(&config.location).map_err(|_| WasmFetchError::InvalidLocation)?;
+        if config.cert_finger_print.is_some() {
+            return Err(WasmFetchError::FingerprintUnsupported);
+        }
+        let method = match config.http_method.as_deref() {
+            Some(method) if is_token(method) => method.to_ascii_uppercase(),
+            Some(_) => return Err(WasmFetchError::InvalidHttpMethod),
+            None => "GET".to_string(),
+        };
+
+        let headers = Headers::new()?;
+        for (name, value) in &self.settings.headers {
+            headers.append(name, value)?;
+        }
+        let init = RequestInit::new();
+        init.set_method(&method);
+        init.set_headers(&headers);
+        if method != "GET" && method != "HEAD" {
+            init.set_body(&Uint8Array::from(self.settings.request_body.as_slice()));
+        }
+        let request = Request::new_with_str_and_init(url.as_str(), &init)?;
+        let response: Response = JsFuture::from(global_fetch(&request)).await?.dyn_into()?;
+        let status = response.status();
+        if !response.ok() {
+            return Err(WasmFetchError::Status(status));
+        }
+        let headers = response.headers();
+        let etag = headers.get("etag")?;
+        let subscription_info = headers
+            .get("subscription-userinfo")?
+            .and_then(|v| SubscriptionInfo::parse(&v).ok());
+
+        let body = self.read_body(&response).await?;
+        Ok(WasmFetchResult {
+            document: body,
+            status,
+            etag,
+            subscription_info,
+        })
+    }
+
+    async fn read_body(&self, response: &Response) -> Result<Vec<u8>, WasmFetchError> {
+        let max_body_size = self.settings.max_body_size;
+        if response
+            .headers()
+            .get("content-length")?
+            .and_then(|length| length.parse::<u64>().ok())
+            .is_some_and(|length| length > max_body_size as u64)
+        {
+            return Err(WasmFetchError::BodyTooLarge);
+        }
+        let buffer = JsFuture::from(response.array_buffer()?).await?;
+        let body = Uint8Array::new(&buffer);
+        if body.length() as usize > max_body_size {
+            return Err(WasmFetchError::BodyTooLarge);
+        }
+        Ok(body.to_vec())
+    }
+}
+
+/// whether `method` is a valid http token, `fetch` throws on anything else
+fn is_token(method: &str) -> bool {
+    !method.is_empty()
+        && method
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
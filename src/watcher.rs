@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use futures_util::stream::{self, Stream};
+use tokio::time::{self, Interval, MissedTickBehavior};
+
+use crate::{FetchError, SIP008Config, Sip008Change, Sip008Client, Sip008Document};
+
+/// periodically refetches an online config and reports what changed
+///
+/// the first successful fetch reports every server as added, later fetches only report
+/// differences, fetches that change nothing are swallowed.
+///
+/// the `tokio` feature doesn't pick a TLS backend, enable `rustls` or `native-tls` with it.
+pub struct Sip008Watcher {
+    client: Sip008Client,
+    config: SIP008Config,
+    interval: Interval,
+    current: Option<Sip008Document>,
+}
+
+impl Sip008Watcher {
+    /// creates a watcher fetching `config` every `period`, the first fetch happens immediately
+    pub fn new(client: Sip008Client, config: SIP008Config, period: Duration) -> Self {
+        let mut interval = time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Self {
+            client,
+            config,
+            interval,
+            current: None,
+        }
+    }
+    /// the last successfully fetched document
+    pub fn current(&self) -> Option<&Sip008Document> {
+        self.current.as_ref()
+    }
+    /// waits until a fetch yields changes, failed fetches are returned as errors and
+    /// leave the current document untouched
+    pub async fn next_changes(&mut self) -> Result<Vec<Sip008Change>, FetchError> {
+        loop {
+            self.interval.tick().await;
+            let document = self.client.fetch(&self.config).await?.document;
+            let changes = match &self.current {
+                Some(current) => Sip008Document::diff(current, &document),
+                None => document
+                    .servers
+                    .iter()
+                    .cloned()
+                    .map(Sip008Change::Added)
+                    .collect(),
+            };
+            self.current = Some(document);
+            if !changes.is_empty() {
+                return Ok(changes);
+            }
+        }
+    }
+    /// turns the watcher into an endless stream of changes
+    pub fn into_stream(self) -> impl Stream<Item = Result<Vec<Sip008Change>, FetchError>> {
+        stream::unfold(self, |mut watcher| async move {
+            let changes = watcher.next_changes().await;
+            Some((changes, watcher))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetch::tests::{document_response, serve_sequence};
+    use futures_util::StreamExt;
+
+    fn server(id: &str, port: u16) -> String {
+        format!(
+            r#"{{"id": "{id}", "server": "example.com", "server_port": {port}, "password": "p", "method": "aes-256-gcm"}}"#
+        )
+    }
+
+    fn document(servers: &[String]) -> Vec<u8> {
+        document_response(&format!(
+            r#"{{"version": 1, "servers": [{}]}}"#,
+            servers.join(",")
+        ))
+    }
+
+    #[tokio::test]
+    async fn reports_added_removed_and_modified_servers() {
+        let (config, _) = serve_sequence(vec![
+            document(&[server("a", 1), server("b", 2)]),
+            document(&[server("a", 1), server("b", 2)]),
+            document(&[server("a", 3), server("c", 4)]),
+        ])
+        .await;
+        let watcher =
+            Sip008Watcher::new(Sip008Client::default(), config, Duration::from_millis(10));
+        let mut changes = Box::pin(watcher.into_stream());
+
+        let first = changes.next().await.unwrap().unwrap();
+        assert_eq!(first.len(), 2);
+        assert!(first.iter().all(|c| matches!(c, Sip008Change::Added(_))));
+
+        let second = changes.next().await.unwrap().unwrap();
+        assert_eq!(second.len(), 3);
+        assert!(matches!(
+            &second[0],
+            Sip008Change::Modified { old, new } if old.server_port == 1 && new.server_port == 3
+        ));
+        assert!(matches!(&second[1], Sip008Change::Added(s) if s.id == "c"));
+        assert!(matches!(&second[2], Sip008Change::Removed(s) if s.id == "b"));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_current_document_on_errors() {
+        let (config, _) = serve_sequence(vec![
+            document(&[server("a", 1)]),
+            b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_vec(),
+        ])
+        .await;
+        let mut watcher =
+            Sip008Watcher::new(Sip008Client::default(), config, Duration::from_millis(10));
+        watcher.next_changes().await.unwrap();
+        let err = watcher.next_changes().await.unwrap_err();
+        assert!(matches!(err, FetchError::Status(500)));
+        assert_eq!(watcher.current().unwrap().servers.len(), 1);
+    }
+}
@@ -0,0 +1,124 @@
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{entry, SSConfig};
+
+/// protocol name of shadowsocks outbounds
+pub const XRAY_SHADOWSOCKS: &str = "shadowsocks";
+
+/// an Xray / V2Ray `outbounds` entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XrayOutbound {
+    pub protocol: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    pub settings: XraySettings,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XraySettings {
+    pub servers: Vec<XrayServer>,
+}
+
+/// a server of a shadowsocks outbound
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XrayServer {
+    pub address: String,
+    pub port: u16,
+    pub method: String,
+    pub password: String,
+    #[serde(default)]
+    pub level: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum XrayExportError {
+    /// xray has no SIP003 plugin support
+    UnsupportedPlugin,
+}
+impl fmt::Display for XrayExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl std::error::Error for XrayExportError {}
+
+impl XrayOutbound {
+    /// builds a shadowsocks outbound with a single server, the tag is used as outbound tag
+    /// ```
+    /// use ss_uri::{SSConfig, XrayOutbound};
+    /// let config = SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#proxy").unwrap();
+    /// let outbound = XrayOutbound::from_config(&config).unwrap();
+    /// assert_eq!(outbound.tag, Some("proxy".to_string()));
+    /// assert_eq!(outbound.settings.servers[0].method, "aes-128-gcm");
+    /// ```
+    pub fn from_config(config: &SSConfig) -> Result<Self, XrayExportError> {
+        Ok(Self {
+            protocol: XRAY_SHADOWSOCKS.to_string(),
+            tag: config.tag.clone(),
+            settings: XraySettings {
+                servers: vec![XrayServer::from_config(config)?],
+            },
+        })
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("xray outbound is always serializable")
+    }
+}
+
+impl XrayServer {
+    pub fn from_config(config: &SSConfig) -> Result<Self, XrayExportError> {
+        if config.plugin().is_some() {
+            return Err(XrayExportError::UnsupportedPlugin);
+        }
+        Ok(Self {
+            address: entry::host_to_string(&config.host),
+            port: config.port,
+            method: config.method.to_string(),
+            password: config.password.clone(),
+            level: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_export_an_outbound() {
+        let config =
+            SSConfig::parse("ss://YWVzLTEyOC1nY206dGVzdA@[2001:db8::1]:8888#example").unwrap();
+        let outbound = XrayOutbound::from_config(&config).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&outbound.to_json()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "protocol": "shadowsocks",
+                "tag": "example",
+                "settings": {
+                    "servers": [{
+                        "address": "2001:db8::1",
+                        "port": 8888,
+                        "method": "aes-128-gcm",
+                        "password": "test",
+                        "level": 0
+                    }]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_configs_with_plugins() {
+        let config = SSConfig::parse(
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888/?plugin=obfs-local%3Bobfs%3Dhttp",
+        )
+        .unwrap();
+        assert_eq!(
+            XrayOutbound::from_config(&config),
+            Err(XrayExportError::UnsupportedPlugin)
+        );
+    }
+}